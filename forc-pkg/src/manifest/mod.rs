@@ -8,7 +8,7 @@ use semver::Version;
 use serde::{de, Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Display,
     path::{Path, PathBuf},
     str::FromStr,
@@ -64,6 +64,21 @@ pub enum ManifestFile {
     Workspace(WorkspaceManifestFile),
 }
 
+impl ManifestFile {
+    /// Construct the manifest at `path` from the given contents of its `Forc.toml` instead of
+    /// reading it, e.g. for a manifest with unsaved changes, telling package and workspace
+    /// manifests apart like [ManifestFile::from_file] does.
+    pub fn from_string(contents: String, path: &Path) -> Result<Self> {
+        match PackageManifestFile::from_string(contents.clone(), path) {
+            Ok(pkg_manifest) => Ok(ManifestFile::Package(Box::new(pkg_manifest))),
+            Err(e) if e.to_string().contains("missing field `project`") => {
+                WorkspaceManifestFile::from_string(&contents, path).map(ManifestFile::Workspace)
+            }
+            Err(e) => bail!("{}", e),
+        }
+    }
+}
+
 impl GenericManifestFile for ManifestFile {
     /// Returns a `PackageManifestFile` if the path is within a package directory, otherwise
     /// returns a `WorkspaceManifestFile` if within a workspace directory.
@@ -365,9 +380,37 @@ impl Dependency {
             Self::Detailed(ref det) => det.version.as_deref(),
         }
     }
+
+    /// The string of the `git` field if specified.
+    pub fn git(&self) -> Option<&str> {
+        match *self {
+            Self::Simple(_) => None,
+            Self::Detailed(ref det) => det.git.as_deref(),
+        }
+    }
+
+    /// The string of the `ipfs` field if specified.
+    pub fn ipfs(&self) -> Option<&str> {
+        match *self {
+            Self::Simple(_) => None,
+            Self::Detailed(ref det) => det.ipfs.as_deref(),
+        }
+    }
 }
 
 impl PackageManifestFile {
+    /// Construct the manifest at `path` from the given contents of its `Forc.toml` instead of
+    /// reading it.
+    ///
+    /// This also `validate`s the manifest, like [PackageManifestFile::from_file].
+    pub fn from_string(contents: String, path: &Path) -> Result<Self> {
+        let path = path.canonicalize()?;
+        let manifest = PackageManifest::from_string(contents)?;
+        let manifest_file = Self { manifest, path };
+        manifest_file.validate()?;
+        Ok(manifest_file)
+    }
+
     /// Returns an iterator over patches defined in underlying `PackageManifest` if this is a
     /// standalone package.
     ///
@@ -925,12 +968,18 @@ pub struct Workspace {
 }
 
 impl WorkspaceManifestFile {
-    /// Produce an iterator yielding all listed patches.
-    pub fn patches(&self) -> impl Iterator<Item = (&String, &PatchMap)> {
-        self.patch
-            .as_ref()
-            .into_iter()
-            .flat_map(|patches| patches.iter())
+    /// Construct the manifest at `path` from the given contents of its `Forc.toml` instead of
+    /// reading it.
+    ///
+    /// This also `validate`s the manifest, like [WorkspaceManifestFile::from_file].
+    pub fn from_string(contents: &str, path: &Path) -> Result<Self> {
+        let path = path.canonicalize()?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow!("Cannot get parent dir of {:?}", path))?;
+        let manifest = WorkspaceManifest::from_string(contents)?;
+        manifest.validate(parent)?;
+        Ok(Self { manifest, path })
     }
 
     /// Returns an iterator over relative paths of workspace members.
@@ -942,11 +991,7 @@ impl WorkspaceManifestFile {
     ///
     /// This will always return canonical paths.
     pub fn member_paths(&self) -> Result<impl Iterator<Item = PathBuf> + '_> {
-        Ok(self
-            .workspace
-            .members
-            .iter()
-            .map(|member| self.dir().join(member)))
+        Ok(self.member_dirs(self.dir())?.into_iter())
     }
 
     /// Returns an iterator over workspace member package manifests.
@@ -1033,13 +1078,20 @@ impl GenericManifestFile for WorkspaceManifestFile {
 impl WorkspaceManifest {
     /// Given a path to a `Forc.toml`, read it and construct a `WorkspaceManifest`.
     pub fn from_file(path: &Path) -> Result<Self> {
+        let manifest_str = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read manifest at {:?}: {}", path, e))?;
+        Self::from_string(&manifest_str)
+    }
+
+    /// Given the contents of a `Forc.toml`, construct a `WorkspaceManifest`.
+    ///
+    /// The members aren't validated, as they are relative to the manifest's directory.
+    pub fn from_string(manifest_str: &str) -> Result<Self> {
         // While creating a `ManifestFile` we need to check if the given path corresponds to a
         // package or a workspace. While doing so, we should be printing the warnings if the given
         // file parses so that we only see warnings for the correct type of manifest.
         let mut warnings = vec![];
-        let manifest_str = std::fs::read_to_string(path)
-            .map_err(|e| anyhow!("failed to read manifest at {:?}: {}", path, e))?;
-        let toml_de = toml::de::Deserializer::new(&manifest_str);
+        let toml_de = toml::de::Deserializer::new(manifest_str);
         let manifest: Self = serde_ignored::deserialize(toml_de, |path| {
             let warning = format!("unused manifest key: {path}");
             warnings.push(warning);
@@ -1051,17 +1103,33 @@ impl WorkspaceManifest {
         Ok(manifest)
     }
 
+    /// Produce an iterator yielding all listed patches.
+    pub fn patches(&self) -> impl Iterator<Item = (&String, &PatchMap)> {
+        self.patch
+            .as_ref()
+            .into_iter()
+            .flat_map(|patches| patches.iter())
+    }
+
     /// Validate the `WorkspaceManifest`
     ///
     /// This checks if the listed members in the `WorkspaceManifest` are indeed in the given `Forc.toml`'s directory.
     pub fn validate(&self, path: &Path) -> Result<()> {
         let mut pkg_name_to_paths: HashMap<String, Vec<PathBuf>> = HashMap::new();
-        for member in &self.workspace.members {
-            let member_path = path.join(member).join("Forc.toml");
+        let member_dirs = self.expand_member_dirs(path, |member_dir, pattern| {
+            println_warning(&format!(
+                "{:?} matches the workspace member pattern {pattern:?} but has no {}, skipping it",
+                member_dir,
+                constants::MANIFEST_FILE_NAME
+            ));
+        })?;
+        for member_dir in member_dirs {
+            let member = member_dir.strip_prefix(path).unwrap_or(&member_dir);
+            let member_path = member_dir.join("Forc.toml");
             if !member_path.exists() {
                 bail!(
                     "{:?} is listed as a member of the workspace but {:?} does not exists",
-                    &member,
+                    member,
                     member_path
                 );
             }
@@ -1101,6 +1169,50 @@ impl WorkspaceManifest {
         }
         Ok(())
     }
+
+    /// Returns the member directories of the workspace at `path`, expanding the glob patterns
+    /// listed in the members, e.g. `packages/*`, relative to `path`.
+    ///
+    /// Directories matched by a pattern that don't contain a `Forc.toml` are skipped, while the
+    /// members listed as plain paths are returned as-is. The skipped directories are only warned
+    /// about by [WorkspaceManifest::validate], so that they aren't warned about on every call.
+    pub fn member_dirs(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.expand_member_dirs(path, |_member_dir, _pattern| {})
+    }
+
+    /// Expand the members like [WorkspaceManifest::member_dirs], calling `on_skipped` with each
+    /// directory skipped for lacking a `Forc.toml` and the pattern that matched it.
+    fn expand_member_dirs(
+        &self,
+        path: &Path,
+        mut on_skipped: impl FnMut(&Path, &str),
+    ) -> Result<Vec<PathBuf>> {
+        let mut member_dirs = Vec::new();
+        for member in &self.workspace.members {
+            let member_str = member.to_string_lossy();
+            if !member_str.contains(['*', '?', '[']) {
+                member_dirs.push(path.join(member));
+                continue;
+            }
+            let pattern = Path::new(&glob::Pattern::escape(&path.to_string_lossy())).join(member);
+            let matches = glob::glob(&pattern.to_string_lossy())
+                .with_context(|| format!("Invalid workspace member pattern {member_str:?}"))?;
+            for member_dir in matches {
+                let member_dir = member_dir?;
+                if !member_dir.is_dir() {
+                    continue;
+                }
+                if !member_dir.join(constants::MANIFEST_FILE_NAME).exists() {
+                    on_skipped(&member_dir, &member_str);
+                    continue;
+                }
+                member_dirs.push(member_dir);
+            }
+        }
+        let mut seen = HashSet::new();
+        member_dirs.retain(|member_dir| seen.insert(member_dir.clone()));
+        Ok(member_dirs)
+    }
 }
 
 impl std::ops::Deref for WorkspaceManifestFile {
@@ -1705,6 +1817,61 @@ mod tests {
         assert_eq!(deep.get("another").unwrap().as_str().unwrap(), "value");
     }
 
+    #[test]
+    fn test_workspace_member_globs() {
+        let workspace_dir = tempfile::tempdir().unwrap();
+        let root = workspace_dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join(constants::MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"packages/*\", \"tool\"]\n",
+        )
+        .unwrap();
+        for member in ["packages/a", "packages/b", "tool"] {
+            let member_dir = root.join(member);
+            std::fs::create_dir_all(member_dir.join("src")).unwrap();
+            std::fs::write(
+                member_dir.join(constants::MANIFEST_FILE_NAME),
+                format!(
+                    "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"{}\"\n",
+                    member.replace('/', "_")
+                ),
+            )
+            .unwrap();
+            std::fs::write(member_dir.join("src").join("main.sw"), "library;\n").unwrap();
+        }
+        // Directories matched by the pattern without a manifest are skipped.
+        std::fs::create_dir_all(root.join("packages").join("docs")).unwrap();
+        std::fs::write(root.join("packages").join("README.md"), "").unwrap();
+
+        let manifest = WorkspaceManifestFile::from_dir(&root).unwrap();
+        let member_paths: Vec<_> = manifest.member_paths().unwrap().collect();
+        assert_eq!(
+            member_paths,
+            vec![
+                root.join("packages").join("a"),
+                root.join("packages").join("b"),
+                root.join("tool"),
+            ]
+        );
+        let member_manifests = manifest.member_manifests().unwrap();
+        assert_eq!(
+            member_manifests.keys().collect::<Vec<_>>(),
+            vec!["packages_a", "packages_b", "tool"]
+        );
+
+        // The skipped directory is reported once to the callback `validate` warns from.
+        let mut skipped = Vec::new();
+        manifest
+            .expand_member_dirs(&root, |member_dir, pattern| {
+                skipped.push((member_dir.to_path_buf(), pattern.to_string()))
+            })
+            .unwrap();
+        assert_eq!(
+            skipped,
+            vec![(root.join("packages").join("docs"), "packages/*".to_string())]
+        );
+    }
+
     #[test]
     fn test_workspace_metadata_roundtrip() {
         let original = WorkspaceManifest {
@@ -427,7 +427,7 @@ impl __ProgramVisitor {
                 ty_block
                     .contents
                     .iter()
-                    .find(|ty_node| statement.span().contains(&ty_node.span))
+                    .find(|ty_node| statement.span().contains_span(&ty_node.span))
             });
             match statement {
                 Statement::Let(statement_let) => {
@@ -1,7 +1,7 @@
 use crate::priv_prelude::*;
 
 /// Left-hand side of an assignment.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Assignable {
     /// A single variable or a path to a part of an aggregate.
     /// E.g.:
@@ -24,7 +24,7 @@ pub enum Assignable {
     },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ElementAccess {
     Var(Ident),
     Index {
@@ -1,11 +1,22 @@
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Annotated<T> {
     pub attributes: Vec<AttributeDecl>,
     pub value: T,
 }
 
+/// The span of an annotated element starts at its first attribute, e.g. the `#[cfg(...)]`
+/// of an `Annotated<ItemConst>`, so that folding and go-to cover the attributes as well.
+impl<T: Spanned> Spanned for Annotated<T> {
+    fn span(&self) -> Span {
+        match self.attributes.first() {
+            Some(attr0) => Span::join(attr0.span(), &self.value.span()),
+            None => self.value.span(),
+        }
+    }
+}
+
 // Storage access and purity.
 pub const STORAGE_ATTRIBUTE_NAME: &str = "storage";
 pub const STORAGE_READ_ARG_NAME: &str = "read";
@@ -85,7 +96,7 @@ pub const KNOWN_ATTRIBUTE_NAMES: &[&str] = &[
 // TODO: Currently, inner attributes are supported only on module doc comments,
 //       those starting with `//!`.
 //       See: https://github.com/FuelLabs/sway/issues/6924
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AttributeDecl {
     pub hash_kind: AttributeHashKind,
     pub attribute: SquareBrackets<Punctuated<Attribute, CommaToken>>,
@@ -189,7 +200,7 @@ impl Spanned for AttributeDecl {
 /// // In annotates whichever item it is declared in.
 /// #![allow(dead_code)]
 /// ```
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AttributeHashKind {
     /// Inner specifies that the attribute annotates
     /// the item that the attribute is declared within.
@@ -208,7 +219,7 @@ impl Spanned for AttributeHashKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AttributeArg {
     pub name: Ident,
     pub value: Option<Literal>,
@@ -224,7 +235,7 @@ impl Spanned for AttributeArg {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attribute {
     pub name: Ident,
     pub args: Option<Parens<Punctuated<AttributeArg, CommaToken>>>,
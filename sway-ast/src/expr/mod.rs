@@ -5,11 +5,12 @@ use crate::{assignable::ElementAccess, priv_prelude::*, PathExprSegment};
 pub mod asm;
 pub mod op_code;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Expr {
     /// A malformed expression.
     ///
     /// Used for parser recovery when we cannot form a more specific node.
+    #[serde(skip_deserializing)]
     Error(Box<[Span]>, #[serde(skip_serializing)] ErrorEmitted),
     Path(PathExpr),
     Literal(Literal),
@@ -292,13 +293,13 @@ impl Spanned for Expr {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReassignmentOp {
     pub variant: ReassignmentOpVariant,
     pub span: Span,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ReassignmentOpVariant {
     Equals,
     AddEquals,
@@ -335,7 +336,7 @@ impl ReassignmentOpVariant {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AbiCastArgs {
     pub name: PathType,
     pub comma_token: CommaToken,
@@ -343,7 +344,7 @@ pub struct AbiCastArgs {
 }
 
 #[allow(clippy::type_complexity)]
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct IfExpr {
     pub if_token: IfToken,
     pub condition: IfCondition,
@@ -354,7 +355,7 @@ pub struct IfExpr {
     )>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum IfCondition {
     Expr(Box<Expr>),
     Let {
@@ -365,7 +366,7 @@ pub enum IfCondition {
     },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum LoopControlFlow<B, C = ()> {
     Continue(C),
     Break(B),
@@ -385,7 +386,7 @@ impl Spanned for IfExpr {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ExprTupleDescriptor {
     Nil,
     Cons {
@@ -395,7 +396,7 @@ pub enum ExprTupleDescriptor {
     },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ExprArrayDescriptor {
     Sequence(Punctuated<Expr, CommaToken>),
     Repeat {
@@ -405,7 +406,7 @@ pub enum ExprArrayDescriptor {
     },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MatchBranch {
     pub pattern: Pattern,
     pub fat_right_arrow_token: FatRightArrowToken,
@@ -419,7 +420,7 @@ impl Spanned for MatchBranch {
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MatchBranchKind {
     Block {
         block: Braces<CodeBlockContents>,
@@ -448,7 +449,7 @@ impl Spanned for MatchBranchKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CodeBlockContents {
     pub statements: Vec<Statement>,
     pub final_expr_opt: Option<Box<Expr>>,
@@ -461,7 +462,7 @@ impl Spanned for CodeBlockContents {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExprStructField {
     pub field_name: Ident,
     pub expr_opt: Option<(ColonToken, Box<Expr>)>,
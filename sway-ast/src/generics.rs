@@ -1,17 +1,17 @@
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum GenericParam {
     Trait { ident: Ident },
     Const { ident: Ident, ty: Ident },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GenericParams {
     pub parameters: AngleBrackets<Punctuated<GenericParam, CommaToken>>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GenericArgs {
     pub parameters: AngleBrackets<Punctuated<Ty, CommaToken>>,
 }
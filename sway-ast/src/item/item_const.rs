@@ -1,29 +1,667 @@
-use crate::priv_prelude::*;
+use crate::{
+    literal::{LitBoolType, LitIntType},
+    priv_prelude::*,
+    token::{Comment, CommentKind},
+};
+use num_traits::{CheckedSub, ToPrimitive, Zero};
+use std::fmt;
+use sway_error::diagnostic::{Code, Diagnostic, Hint, Issue, Reason};
+use sway_types::{span::Source, SourceId};
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug)]
 pub struct ItemConst {
     pub pub_token: Option<PubToken>,
     pub const_token: ConstToken,
     pub name: Ident,
     pub ty_opt: Option<(ColonToken, Ty)>,
-    pub eq_token_opt: Option<EqToken>,
-    pub expr_opt: Option<Expr>,
+    pub expr_opt: Option<(EqToken, Expr)>,
     pub semicolon_token: SemicolonToken,
+    /// The line comments on the lines right above the const, in source order, e.g. for a
+    /// formatter to reproduce them. They aren't part of the const's `span`, see
+    /// [ItemConst::span_with_trivia]. Only module-level consts, parsed as an [ItemKind], get trivia.
+    pub leading_trivia: Vec<Comment>,
+    /// The line comment following the const's semicolon on the same line, if any.
+    pub trailing_trivia: Option<Comment>,
+}
+
+/// Serializes the fields of the [ItemConst] followed by its computed `span`, as byte offsets
+/// into its source, so that the range of the whole item doesn't have to be re-derived from its tokens.
+/// The comments are serialized as byte offsets too, along with their kind.
+impl Serialize for ItemConst {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let span = self.span();
+        let mut state = serializer.serialize_struct("ItemConst", 9)?;
+        state.serialize_field("pub_token", &self.pub_token)?;
+        state.serialize_field("const_token", &self.const_token)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("ty_opt", &self.ty_opt)?;
+        state.serialize_field("expr_opt", &self.expr_opt)?;
+        state.serialize_field("semicolon_token", &self.semicolon_token)?;
+        state.serialize_field(
+            "leading_trivia",
+            &self
+                .leading_trivia
+                .iter()
+                .map(SerializedComment::from)
+                .collect::<Vec<_>>(),
+        )?;
+        state.serialize_field(
+            "trailing_trivia",
+            &self.trailing_trivia.as_ref().map(SerializedComment::from),
+        )?;
+        state.serialize_field("span", &SpanRange::from(&span))?;
+        state.end()
+    }
+}
+
+/// Deserializes the form serialized by the [ItemConst]'s `Serialize`. The spans of its tokens share
+/// a single source, like when parsed, see [Source::deserialize_shared]. The comments are rebuilt in
+/// the source of the `const` keyword, which they were parsed from, and the serialized `span` is
+/// skipped as it is computed from the tokens.
+impl<'de> Deserialize<'de> for ItemConst {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SerializedItemConst {
+            pub_token: Option<PubToken>,
+            const_token: ConstToken,
+            name: Ident,
+            ty_opt: Option<(ColonToken, Ty)>,
+            expr_opt: Option<(EqToken, Expr)>,
+            semicolon_token: SemicolonToken,
+            leading_trivia: Vec<SerializedComment>,
+            trailing_trivia: Option<SerializedComment>,
+        }
+
+        let item = Source::deserialize_shared(|| SerializedItemConst::deserialize(deserializer))?;
+        let src = item.const_token.span().src().clone();
+        let comment = |comment: SerializedComment| {
+            let SerializedComment {
+                range,
+                comment_kind,
+            } = comment;
+            Span::new(src.clone(), range.start, range.end, range.source_id)
+                .map(|span| Comment { span, comment_kind })
+                .ok_or_else(|| serde::de::Error::custom("comment out of the const's source"))
+        };
+        Ok(ItemConst {
+            pub_token: item.pub_token,
+            const_token: item.const_token,
+            name: item.name,
+            ty_opt: item.ty_opt,
+            expr_opt: item.expr_opt,
+            semicolon_token: item.semicolon_token,
+            leading_trivia: item
+                .leading_trivia
+                .into_iter()
+                .map(comment)
+                .collect::<Result<_, _>>()?,
+            trailing_trivia: item.trailing_trivia.map(comment).transpose()?,
+        })
+    }
+}
+
+/// The byte range of a [Span] within its source, without the source text itself.
+#[derive(Serialize, Deserialize)]
+struct SpanRange {
+    start: usize,
+    end: usize,
+    source_id: Option<SourceId>,
+}
+
+impl From<&Span> for SpanRange {
+    fn from(span: &Span) -> Self {
+        SpanRange {
+            start: span.start(),
+            end: span.end(),
+            source_id: span.source_id().copied(),
+        }
+    }
+}
+
+/// A comment of an [ItemConst]'s trivia, as the byte range of its span along with its kind.
+#[derive(Serialize, Deserialize)]
+struct SerializedComment {
+    #[serde(flatten)]
+    range: SpanRange,
+    comment_kind: CommentKind,
+}
+
+impl From<&Comment> for SerializedComment {
+    fn from(comment: &Comment) -> Self {
+        SerializedComment {
+            range: SpanRange::from(&comment.span),
+            comment_kind: comment.comment_kind.clone(),
+        }
+    }
+}
+
+/// Serializes the structural content of an [ItemConst] without any span, e.g. to diff the
+/// serialized AST across unrelated edits or compiler versions, see [ItemConst::structural].
+///
+/// The name is serialized as a string, and the type and initializer as their source text.
+/// Comments aren't serialized.
+#[derive(Clone, Copy, Debug)]
+pub struct StructuralItemConst<'a>(pub &'a ItemConst);
+
+impl Serialize for StructuralItemConst<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let item = self.0;
+        let mut state = serializer.serialize_struct("ItemConst", 4)?;
+        state.serialize_field("is_public", &item.is_public())?;
+        state.serialize_field("name", item.name.as_str())?;
+        state.serialize_field("ty", &item.type_span().as_ref().map(Span::as_str))?;
+        state.serialize_field(
+            "expr",
+            &item
+                .expr_opt
+                .as_ref()
+                .map(|(_eq_token, expr)| expr.span().as_str().to_string()),
+        )?;
+        state.end()
+    }
 }
 
 impl Spanned for ItemConst {
+    fn span(&self) -> Span {
+        let start = self
+            .visibility_span()
+            .unwrap_or_else(|| self.const_token.span());
+        let spans = [
+            Some(start.clone()),
+            Some(self.name.span()),
+            self.ty_opt.as_ref().map(|(_colon_token, ty)| ty.span()),
+            self.expr_opt.as_ref().map(|(_eq_token, expr)| expr.span()),
+        ];
+        // The spans may come from different sources, e.g. for macro-expanded consts,
+        // in which case the primary token's span is used on its own. The dummy spans of
+        // the tokens synthesized by `ItemConst::builder` are left out.
+        Span::try_join_all(spans.into_iter().flatten().filter(|span| !span.is_dummy()))
+            .unwrap_or(start)
+    }
+}
+
+/// Renders the const back to source as `pub const NAME: TY = EXPR;`, with single spaces between its
+/// tokens. The type and the initializer are rendered from their source text as is, so they have to
+/// be backed by source even for a const made with [ItemConst::builder], e.g. parsed by `sway-parse`.
+/// Formatting them is left to `swayfmt`.
+impl fmt::Display for ItemConst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_public() {
+            write!(f, "{} ", PubToken::AS_STR)?;
+        }
+        write!(f, "{} {}", ConstToken::AS_STR, self.name.as_raw_ident_str())?;
+        if let Some((_colon_token, ty)) = &self.ty_opt {
+            write!(f, "{} {}", ColonToken::AS_STR, ty.span().as_str())?;
+        }
+        if let Some((_eq_token, expr)) = &self.expr_opt {
+            write!(f, " {} {}", EqToken::AS_STR, expr.span().as_str())?;
+        }
+        write!(f, "{}", SemicolonToken::AS_STR)
+    }
+}
+
+impl ItemConst {
+    /// Starts building a private const named `name` of type `ty` without an initializer,
+    /// e.g. to generate Sway source. See [ItemConstBuilder].
+    pub fn builder(name: Ident, ty: Ty) -> ItemConstBuilder {
+        ItemConstBuilder {
+            name,
+            ty,
+            is_public: false,
+            expr_opt: None,
+        }
+    }
+
+    /// Wraps the const to serialize its structural content without spans, see [StructuralItemConst].
+    /// The const itself serializes along with its spans.
+    pub fn structural(&self) -> StructuralItemConst<'_> {
+        StructuralItemConst(self)
+    }
+
+    /// Returns true if the const is declared `pub`.
+    pub fn is_public(&self) -> bool {
+        self.pub_token.is_some()
+    }
+
+    /// The span of the `pub` keyword, if the const is public.
+    pub fn visibility_span(&self) -> Option<Span> {
+        self.pub_token.as_ref().map(|pub_token| pub_token.span())
+    }
+
+    /// The span of the `const` keyword.
+    pub fn const_span(&self) -> Span {
+        self.const_token.span()
+    }
+
+    /// The span of the const's name.
+    pub fn name_span(&self) -> Span {
+        self.name.span()
+    }
+
+    /// The span of the const's type annotation, if it has one.
+    pub fn type_span(&self) -> Option<Span> {
+        self.ty_opt.as_ref().map(|(_colon_token, ty)| ty.span())
+    }
+
+    /// The span of the const along with its leading and trailing comments, from the first leading
+    /// comment to the trailing one. It is the const's `span` if it has neither.
+    pub fn span_with_trivia(&self) -> Span {
+        let span = self.span();
+        let spans = self
+            .leading_trivia
+            .first()
+            .map(Spanned::span)
+            .into_iter()
+            .chain([span.clone()])
+            .chain(self.trailing_trivia.as_ref().map(Spanned::span));
+        Span::try_join_all(spans).unwrap_or(span)
+    }
+
+    /// Evaluates the const's initializer without type checking it, e.g. to show its value on hover.
+    ///
+    /// Only literals, and the unary and binary operators applied to them, are evaluated.
+    /// Returns `None` for anything else, like references to other consts or function calls,
+    /// and for operations that would fail, like overflows or divisions by zero.
+    pub fn try_const_eval(&self) -> Option<ConstValue> {
+        let (_eq_token, expr) = self.expr_opt.as_ref()?;
+        let annotation = match &self.ty_opt {
+            Some((_colon_token, ty)) => Some(ConstType::from_ty(ty)?),
+            None => None,
+        };
+        let int_ty = match &annotation {
+            Some(ConstType::Int(ty)) => Some(ty.clone()),
+            _ => None,
+        };
+        match (annotation, eval_expr(expr, int_ty)?) {
+            (None | Some(ConstType::Int(_)), EvalValue::Int { value, ty }) => {
+                let ty = ty.unwrap_or(LitIntType::U64);
+                (value <= int_max(&ty)?).then_some(ConstValue::Int { value, ty })
+            }
+            (None | Some(ConstType::Bool), EvalValue::Bool(value)) => Some(ConstValue::Bool(value)),
+            (None | Some(ConstType::Str), EvalValue::String(value)) => {
+                Some(ConstValue::String(value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Warns about an integer literal initializer that doesn't fit in the const's annotated
+    /// unsigned integer type, like `const X: u8 = 256;`, before the const is type checked.
+    ///
+    /// It is conservative and returns `None` for any other initializer, including expressions
+    /// of literals, for other annotations, and for literals whose suffix contradicts the
+    /// annotation, as those are type errors rather than range ones.
+    ///
+    /// The diagnostic is built from the spans alone, so its source paths must be resolved by
+    /// [Diagnostic::resolve_source_paths] before it is displayed.
+    pub fn lint_literal_range(&self) -> Option<Diagnostic> {
+        let (_colon_token, ty) = self.ty_opt.as_ref()?;
+        let Some(ConstType::Int(int_ty)) = ConstType::from_ty(ty) else {
+            return None;
+        };
+        let (_eq_token, Expr::Literal(Literal::Int(lit))) = self.expr_opt.as_ref()? else {
+            return None;
+        };
+        if lit
+            .ty_opt
+            .as_ref()
+            .is_some_and(|(suffix, _span)| *suffix != int_ty)
+        {
+            return None;
+        }
+        let max = int_max(&int_ty)?;
+        if lit.parsed <= max {
+            return None;
+        }
+        let ty = ty.span();
+        Some(Diagnostic {
+            reason: Some(Reason::new(
+                Code::warnings(1),
+                "Literal is out of range".to_string(),
+            )),
+            issue: Issue::unresolved_warning(
+                lit.span.clone(),
+                format!(
+                    "Literal `{}` does not fit in the type \"{}\" of constant \"{}\".",
+                    lit.span.as_str(),
+                    ty.as_str(),
+                    self.name
+                ),
+            ),
+            hints: vec![Hint::unresolved_info(
+                ty.clone(),
+                format!("The values of \"{}\" range from 0 to {max}.", ty.as_str()),
+            )],
+            help: vec![],
+        })
+    }
+
+    /// The spans of the const's tokens in source order: visibility, `const` keyword, name, type,
+    /// `=`, expression and semicolon. The optional ones are skipped if missing.
+    pub fn token_spans(&self) -> impl Iterator<Item = Span> + '_ {
+        self.visibility_span()
+            .into_iter()
+            .chain([self.const_span(), self.name_span()])
+            .chain(self.type_span())
+            .chain(
+                self.expr_opt
+                    .iter()
+                    .flat_map(|(eq_token, expr)| [eq_token.span(), expr.span()]),
+            )
+            .chain([self.semicolon_token.span()])
+    }
+}
+
+/// Builds an [ItemConst] programmatically, as returned by [ItemConst::builder].
+///
+/// The tokens it synthesizes, like the `const` keyword and the semicolon, have dummy spans.
+/// They are left out of the const's `span`, which is the dummy span if all of them are dummies.
+#[derive(Clone, Debug)]
+pub struct ItemConstBuilder {
+    name: Ident,
+    ty: Ty,
+    is_public: bool,
+    expr_opt: Option<Expr>,
+}
+
+impl ItemConstBuilder {
+    /// Declares the const `pub` or not.
+    pub fn public(mut self, is_public: bool) -> Self {
+        self.is_public = is_public;
+        self
+    }
+
+    /// Initializes the const with `expr`.
+    pub fn initializer(mut self, expr: Expr) -> Self {
+        self.expr_opt = Some(expr);
+        self
+    }
+
+    pub fn build(self) -> ItemConst {
+        ItemConst {
+            pub_token: self.is_public.then(|| PubToken::new(Span::dummy())),
+            const_token: ConstToken::new(Span::dummy()),
+            name: self.name,
+            ty_opt: Some((ColonToken::default(), self.ty)),
+            expr_opt: self.expr_opt.map(|expr| (EqToken::default(), expr)),
+            semicolon_token: SemicolonToken::default(),
+            leading_trivia: Vec::new(),
+            trailing_trivia: None,
+        }
+    }
+}
+
+/// The value of a const initializer, as computed by [ItemConst::try_const_eval].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstValue {
+    /// An unsigned integer. Its type is `u64` unless the const or the literals specify one.
+    Int {
+        value: BigUint,
+        ty: LitIntType,
+    },
+    Bool(bool),
+    String(String),
+}
+
+/// The types of const annotations that [ItemConst::try_const_eval] can evaluate.
+enum ConstType {
+    Int(LitIntType),
+    Bool,
+    Str,
+}
+
+impl ConstType {
+    fn from_ty(ty: &Ty) -> Option<ConstType> {
+        let path = match ty {
+            Ty::Path(path) => path,
+            Ty::StringSlice(_) => return Some(ConstType::Str),
+            _ => return None,
+        };
+        if path.root_opt.is_some() || !path.suffix.is_empty() || path.prefix.generics_opt.is_some()
+        {
+            return None;
+        }
+        let ty = match path.prefix.name.as_str() {
+            "u8" => LitIntType::U8,
+            "u16" => LitIntType::U16,
+            "u32" => LitIntType::U32,
+            "u64" => LitIntType::U64,
+            "u256" => LitIntType::U256,
+            "bool" => return Some(ConstType::Bool),
+            _ => return None,
+        };
+        Some(ConstType::Int(ty))
+    }
+}
+
+/// An intermediate value of [ItemConst::try_const_eval]. Integers have no type until a literal
+/// suffix or the const's annotation gives them one.
+enum EvalValue {
+    Int {
+        value: BigUint,
+        ty: Option<LitIntType>,
+    },
+    Bool(bool),
+    String(String),
+}
+
+/// The maximum value of an unsigned integer type, or `None` for the signed ones.
+fn int_max(ty: &LitIntType) -> Option<BigUint> {
+    let bits = int_bits(ty)?;
+    Some((BigUint::from(1u8) << bits) - 1u8)
+}
+
+fn int_bits(ty: &LitIntType) -> Option<usize> {
+    match ty {
+        LitIntType::U8 => Some(8),
+        LitIntType::U16 => Some(16),
+        LitIntType::U32 => Some(32),
+        LitIntType::U64 => Some(64),
+        LitIntType::U256 => Some(256),
+        LitIntType::I8 | LitIntType::I16 | LitIntType::I32 | LitIntType::I64 => None,
+    }
+}
+
+/// Evaluates `expr`, whose integers are expected to be of type `int_ty` if known.
+fn eval_expr(expr: &Expr, int_ty: Option<LitIntType>) -> Option<EvalValue> {
+    match expr {
+        Expr::Literal(Literal::Int(lit)) => {
+            let ty = match (&lit.ty_opt, int_ty) {
+                (Some((suffix, _span)), Some(ty)) if *suffix != ty => return None,
+                (Some((suffix, _span)), _) => Some(suffix.clone()),
+                (None, ty) => ty,
+            };
+            // Integers without a type yet are only bounded once the whole expression is evaluated.
+            let max = int_max(ty.as_ref().unwrap_or(&LitIntType::U256))?;
+            (lit.parsed <= max).then(|| EvalValue::Int {
+                value: lit.parsed.clone(),
+                ty,
+            })
+        }
+        Expr::Literal(Literal::Bool(lit)) => Some(EvalValue::Bool(lit.kind == LitBoolType::True)),
+        Expr::Literal(Literal::String(lit)) => Some(EvalValue::String(lit.parsed.clone())),
+        Expr::Parens(parens) => eval_expr(&parens.inner, int_ty),
+        Expr::Not { expr, .. } => match eval_expr(expr, int_ty)? {
+            EvalValue::Bool(value) => Some(EvalValue::Bool(!value)),
+            EvalValue::Int { value, ty } => {
+                let max = int_max(ty.as_ref().unwrap_or(&LitIntType::U64))?;
+                Some(EvalValue::Int {
+                    value: max ^ value,
+                    ty,
+                })
+            }
+            EvalValue::String(_) => None,
+        },
+        Expr::Add { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| Some(lhs + rhs)),
+        Expr::Sub { lhs, rhs, .. } => {
+            eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| lhs.checked_sub(&rhs))
+        }
+        Expr::Mul { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| Some(lhs * rhs)),
+        Expr::Div { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| {
+            (!rhs.is_zero()).then(|| lhs / rhs)
+        }),
+        Expr::Modulo { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| {
+            (!rhs.is_zero()).then(|| lhs % rhs)
+        }),
+        Expr::Pow { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| {
+            let exponent = rhs.to_u32()?;
+            // Bail out before computing results too large for any integer type.
+            if lhs.bits() > 1 && (lhs.bits() - 1) * u64::from(exponent) > 256 {
+                return None;
+            }
+            Some(lhs.pow(exponent))
+        }),
+        Expr::Shl { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, ty| {
+            // The bits shifted out are discarded.
+            let bits = int_bits(ty)?;
+            match rhs.to_usize().filter(|shift| *shift < bits) {
+                Some(shift) => Some((lhs << shift) & int_max(ty)?),
+                None => Some(BigUint::zero()),
+            }
+        }),
+        Expr::Shr { lhs, rhs, .. } => eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| {
+            Some(
+                rhs.to_usize()
+                    .map_or_else(BigUint::zero, |shift| lhs >> shift),
+            )
+        }),
+        Expr::BitAnd { lhs, rhs, .. } => {
+            eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| Some(lhs & rhs))
+        }
+        Expr::BitXor { lhs, rhs, .. } => {
+            eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| Some(lhs ^ rhs))
+        }
+        Expr::BitOr { lhs, rhs, .. } => {
+            eval_int_op(lhs, rhs, int_ty, |lhs, rhs, _| Some(lhs | rhs))
+        }
+        Expr::Equal { lhs, rhs, .. } => eval_equality(lhs, rhs).map(EvalValue::Bool),
+        Expr::NotEqual { lhs, rhs, .. } => eval_equality(lhs, rhs).map(|eq| EvalValue::Bool(!eq)),
+        Expr::LessThan { lhs, rhs, .. } => eval_comparison(lhs, rhs, |lhs, rhs| lhs < rhs),
+        Expr::GreaterThan { lhs, rhs, .. } => eval_comparison(lhs, rhs, |lhs, rhs| lhs > rhs),
+        Expr::LessThanEq { lhs, rhs, .. } => eval_comparison(lhs, rhs, |lhs, rhs| lhs <= rhs),
+        Expr::GreaterThanEq { lhs, rhs, .. } => eval_comparison(lhs, rhs, |lhs, rhs| lhs >= rhs),
+        Expr::LogicalAnd { lhs, rhs, .. } => eval_bool_op(lhs, rhs, |lhs, rhs| lhs && rhs),
+        Expr::LogicalOr { lhs, rhs, .. } => eval_bool_op(lhs, rhs, |lhs, rhs| lhs || rhs),
+        _ => None,
+    }
+}
+
+/// Evaluates both operands as integers of the same type, and applies `op` to them,
+/// along with their type or `u64` if it isn't known yet.
+/// Returns `None` if the result doesn't fit in that type.
+fn eval_int_op(
+    lhs: &Expr,
+    rhs: &Expr,
+    int_ty: Option<LitIntType>,
+    op: impl FnOnce(BigUint, BigUint, &LitIntType) -> Option<BigUint>,
+) -> Option<EvalValue> {
+    let (
+        EvalValue::Int {
+            value: lhs,
+            ty: lhs_ty,
+        },
+        EvalValue::Int {
+            value: rhs,
+            ty: rhs_ty,
+        },
+    ) = (eval_expr(lhs, int_ty.clone())?, eval_expr(rhs, int_ty)?)
+    else {
+        return None;
+    };
+    let ty = unify_int_types(lhs_ty, rhs_ty)?;
+    let value = op(lhs, rhs, ty.as_ref().unwrap_or(&LitIntType::U64))?;
+    (value <= int_max(ty.as_ref().unwrap_or(&LitIntType::U256))?)
+        .then_some(EvalValue::Int { value, ty })
+}
+
+/// Returns the type of an operation on integers of types `lhs` and `rhs`,
+/// or `None` if they are different types.
+fn unify_int_types(lhs: Option<LitIntType>, rhs: Option<LitIntType>) -> Option<Option<LitIntType>> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) if lhs != rhs => None,
+        (lhs, rhs) => Some(lhs.or(rhs)),
+    }
+}
+
+fn eval_equality(lhs: &Expr, rhs: &Expr) -> Option<bool> {
+    match (eval_expr(lhs, None)?, eval_expr(rhs, None)?) {
+        (
+            EvalValue::Int {
+                value: lhs,
+                ty: lhs_ty,
+            },
+            EvalValue::Int {
+                value: rhs,
+                ty: rhs_ty,
+            },
+        ) => unify_int_types(lhs_ty, rhs_ty).map(|_| lhs == rhs),
+        (EvalValue::Bool(lhs), EvalValue::Bool(rhs)) => Some(lhs == rhs),
+        (EvalValue::String(lhs), EvalValue::String(rhs)) => Some(lhs == rhs),
+        _ => None,
+    }
+}
+
+fn eval_comparison(
+    lhs: &Expr,
+    rhs: &Expr,
+    op: impl FnOnce(&BigUint, &BigUint) -> bool,
+) -> Option<EvalValue> {
+    let (
+        EvalValue::Int {
+            value: lhs,
+            ty: lhs_ty,
+        },
+        EvalValue::Int {
+            value: rhs,
+            ty: rhs_ty,
+        },
+    ) = (eval_expr(lhs, None)?, eval_expr(rhs, None)?)
+    else {
+        return None;
+    };
+    unify_int_types(lhs_ty, rhs_ty)?;
+    Some(EvalValue::Bool(op(&lhs, &rhs)))
+}
+
+fn eval_bool_op(lhs: &Expr, rhs: &Expr, op: impl FnOnce(bool, bool) -> bool) -> Option<EvalValue> {
+    match (eval_expr(lhs, None)?, eval_expr(rhs, None)?) {
+        (EvalValue::Bool(lhs), EvalValue::Bool(rhs)) => Some(EvalValue::Bool(op(lhs, rhs))),
+        _ => None,
+    }
+}
+
+/// A const whose initializer is kept as the [Span] of its unparsed tokens rather than as an [Expr],
+/// for tooling that only needs the const's signature: its visibility, name and type.
+///
+/// This is parsed instead of an [ItemConst] on request only, and the initializer can be parsed
+/// later on with `sway_parse::ItemConstSignatureExt::parse_initializer`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ItemConstSignature {
+    pub pub_token: Option<PubToken>,
+    pub const_token: ConstToken,
+    pub name: Ident,
+    pub ty_opt: Option<(ColonToken, Ty)>,
+    pub initializer_opt: Option<(EqToken, Span)>,
+    pub semicolon_token: SemicolonToken,
+}
+
+impl Spanned for ItemConstSignature {
     fn span(&self) -> Span {
         let start = match &self.pub_token {
             Some(pub_token) => pub_token.span(),
             None => self.const_token.span(),
         };
-        let end = match &self.expr_opt {
-            Some(expr) => expr.span(),
+        let end = match &self.initializer_opt {
+            Some((_eq_token, initializer)) => initializer.clone(),
             None => match &self.ty_opt {
                 Some((_colon, ty)) => ty.span(),
                 None => self.name.span(),
             },
         };
-        Span::join(start, &end)
+        Span::try_join(start.clone(), &end).unwrap_or(start)
     }
 }
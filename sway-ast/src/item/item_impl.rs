@@ -10,7 +10,7 @@ pub enum ImplItemParent {
     Other,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ItemImplItem {
     Fn(ItemFn),
     Const(ItemConst),
@@ -35,7 +35,7 @@ impl ItemImplItem {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ItemImpl {
     pub impl_token: ImplToken,
     pub generic_params_opt: Option<GenericParams>,
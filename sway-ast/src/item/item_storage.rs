@@ -1,6 +1,6 @@
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ItemStorage {
     pub storage_token: StorageToken,
     pub entries: Braces<Punctuated<Annotated<StorageEntry>, CommaToken>>,
@@ -12,7 +12,7 @@ impl Spanned for ItemStorage {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct StorageEntry {
     pub name: Ident,
@@ -44,7 +44,7 @@ impl Spanned for StorageEntry {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 
 pub struct StorageField {
     pub name: Ident,
@@ -2,12 +2,13 @@ use sway_error::handler::ErrorEmitted;
 
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ItemTraitItem {
     Fn(FnSignature, Option<SemicolonToken>),
     Const(ItemConst, Option<SemicolonToken>),
     Type(TraitType, Option<SemicolonToken>),
     // to handle parser recovery: Error represents an incomplete trait item
+    #[serde(skip_deserializing)]
     Error(Box<[Span]>, #[serde(skip_serializing)] ErrorEmitted),
 }
 
@@ -24,7 +25,7 @@ impl ItemTraitItem {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ItemTrait {
     pub visibility: Option<PubToken>,
     pub trait_token: TraitToken,
@@ -74,7 +75,7 @@ impl Spanned for ItemTraitItem {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Traits {
     pub prefix: PathType,
     pub suffixes: Vec<(AddToken, PathType)>,
@@ -16,17 +16,8 @@ pub mod item_use;
 
 pub type Item = Annotated<ItemKind>;
 
-impl Spanned for Item {
-    fn span(&self) -> Span {
-        match self.attributes.first() {
-            Some(attr0) => Span::join(attr0.span(), &self.value.span()),
-            None => self.value.span(),
-        }
-    }
-}
-
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ItemKind {
     Submodule(Submodule),
     Use(ItemUse),
@@ -41,6 +32,7 @@ pub enum ItemKind {
     Configurable(ItemConfigurable),
     TypeAlias(ItemTypeAlias),
     // to handle parser recovery: Error represents an incomplete item
+    #[serde(skip_deserializing)]
     Error(Box<[Span]>, #[serde(skip_serializing)] ErrorEmitted),
 }
 
@@ -101,7 +93,7 @@ impl Spanned for ItemKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TypeField {
     pub visibility: Option<PubToken>,
     pub name: Ident,
@@ -120,7 +112,7 @@ impl Spanned for TypeField {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum FnArgs {
     Static(Punctuated<FnArg, CommaToken>),
     NonStatic {
@@ -131,7 +123,7 @@ pub enum FnArgs {
     },
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FnArg {
     pub pattern: Pattern,
     pub colon_token: ColonToken,
@@ -144,7 +136,7 @@ impl Spanned for FnArg {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FnSignature {
     pub visibility: Option<PubToken>,
     pub fn_token: FnToken,
@@ -172,7 +164,7 @@ impl Spanned for FnSignature {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TraitType {
     pub name: Ident,
     pub type_token: TypeToken,
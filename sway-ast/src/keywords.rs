@@ -14,7 +14,7 @@ pub trait Keyword: Spanned + Sized {
 
 macro_rules! define_keyword (
     ($ty_name:ident, $keyword:literal) => {
-        #[derive(Clone, Debug, Serialize)]
+        #[derive(Clone, Debug, Serialize, Deserialize)]
         pub struct $ty_name {
             span: Span,
         }
@@ -106,7 +106,7 @@ pub trait Token: Spanned + Sized {
 
 macro_rules! define_token (
     ($ty_name:ident, $description:literal, $as_str:literal, [$($punct_kinds:ident),*], [$($not_followed_by:ident),*]) => {
-        #[derive(Clone, Debug, Serialize)]
+        #[derive(Clone, Debug, Serialize, Deserialize)]
         pub struct $ty_name {
             span: Span,
         }
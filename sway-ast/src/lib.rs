@@ -33,7 +33,9 @@ pub use crate::{
     item::{
         item_abi::ItemAbi,
         item_configurable::{ConfigurableField, ItemConfigurable},
-        item_const::ItemConst,
+        item_const::{
+            ConstValue, ItemConst, ItemConstBuilder, ItemConstSignature, StructuralItemConst,
+        },
         item_enum::ItemEnum,
         item_fn::ItemFn,
         item_impl::{ImplItemParent, ItemImpl, ItemImplItem},
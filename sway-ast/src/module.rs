@@ -2,7 +2,7 @@ use sway_types::SourceId;
 
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Module {
     pub kind: ModuleKind,
     pub semicolon_token: SemicolonToken,
@@ -37,7 +37,7 @@ impl Spanned for Module {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ModuleKind {
     Script { script_token: ScriptToken },
     Contract { contract_token: ContractToken },
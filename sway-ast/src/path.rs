@@ -1,17 +1,17 @@
 use crate::priv_prelude::*;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathExpr {
     pub root_opt: Option<(Option<AngleBrackets<QualifiedPathRoot>>, DoubleColonToken)>,
     pub prefix: PathExprSegment,
     pub suffix: Vec<(DoubleColonToken, PathExprSegment)>,
     // path expression with incomplete suffix are needed to do
     // parser recovery on inputs like foo::
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub incomplete_suffix: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathExprSegment {
     pub name: Ident,
     pub generics_opt: Option<(DoubleColonToken, GenericArgs)>,
@@ -75,7 +75,7 @@ impl Spanned for PathExprSegment {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathType {
     pub root_opt: Option<(Option<AngleBrackets<QualifiedPathRoot>>, DoubleColonToken)>,
     pub prefix: PathTypeSegment,
@@ -117,7 +117,7 @@ impl Spanned for PathType {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathTypeSegment {
     pub name: Ident,
     pub generics_opt: Option<(Option<DoubleColonToken>, GenericArgs)>,
@@ -133,7 +133,7 @@ impl Spanned for PathTypeSegment {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QualifiedPathRoot {
     pub ty: Box<Ty>,
     pub as_trait: (AsToken, Box<PathType>),
@@ -35,7 +35,7 @@ impl<T> Spanned for GenericGroup<T> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum CommentKind {
     /// A newlined comment is a comment with a preceding newline before another token.
     ///
@@ -205,6 +205,8 @@ impl From<CommentedTree> for CommentedTokenTree {
 pub struct TokenStream {
     token_trees: Vec<TokenTree>,
     full_span: Span,
+    /// The comments stripped from the token trees of this stream, but not of its nested groups.
+    comments: Vec<Comment>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
@@ -262,6 +264,11 @@ impl TokenStream {
     pub fn token_trees(&self) -> &[TokenTree] {
         &self.token_trees
     }
+
+    /// The comments between the token trees, in source order.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
 }
 
 impl Spanned for TokenStream {
@@ -293,14 +300,22 @@ impl CommentedTokenStream {
     }
 
     pub fn strip_comments(self) -> TokenStream {
+        let mut comments = Vec::new();
         let token_trees = self
             .token_trees
             .into_iter()
-            .filter_map(|tree| tree.strip_comments())
+            .filter_map(|tree| match tree {
+                CommentedTokenTree::Comment(comment) => {
+                    comments.push(comment);
+                    None
+                }
+                tree => tree.strip_comments(),
+            })
             .collect();
         TokenStream {
             token_trees,
             full_span: self.full_span,
+            comments,
         }
     }
 }
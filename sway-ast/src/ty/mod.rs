@@ -1,7 +1,7 @@
 use crate::priv_prelude::*;
 
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Ty {
     Path(PathType),
     Tuple(Parens<TyTupleDescriptor>),
@@ -70,7 +70,7 @@ impl Ty {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum TyTupleDescriptor {
     Nil,
     Cons {
@@ -95,7 +95,7 @@ impl TyTupleDescriptor {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TyArrayDescriptor {
     pub ty: Box<Ty>,
     pub semicolon_token: SemicolonToken,
@@ -1046,7 +1046,7 @@ pub(crate) fn item_const_to_constant_declaration(
     let span = item_const.span();
 
     let expr = match item_const.expr_opt {
-        Some(expr) => Some(expr_to_expression(context, handler, engines, expr)?),
+        Some((_eq_token, expr)) => Some(expr_to_expression(context, handler, engines, expr)?),
         None => {
             if require_expression {
                 let err = ConvertParseTreeError::ConstantRequiresExpression { span: span.clone() };
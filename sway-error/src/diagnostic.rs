@@ -36,6 +36,16 @@ impl Diagnostic {
         &self.issue
     }
 
+    /// Resolves the source paths of the labels created without a [SourceEngine],
+    /// e.g. by [Issue::unresolved_warning], so that they get displayed.
+    pub fn resolve_source_paths(mut self, source_engine: &SourceEngine) -> Self {
+        self.issue.label.resolve_source_path(source_engine);
+        for hint in self.hints.iter_mut() {
+            hint.label.resolve_source_path(source_engine);
+        }
+        self
+    }
+
     /// All the labels, potentially in different source files.
     pub fn labels(&self) -> Vec<&Label> {
         let mut labels = Vec::<&Label>::new();
@@ -186,6 +196,24 @@ impl Label {
         }
     }
 
+    /// Creates a `Label` without a [SourceEngine] at hand, e.g. in the parser.
+    /// Its source path stays unknown, and the `Label` is not [Label::is_in_source],
+    /// until it gets resolved by [Diagnostic::resolve_source_paths].
+    fn unresolved(label_type: LabelType, span: Span, text: String) -> Label {
+        Label {
+            label_type,
+            span,
+            text,
+            source_path: None,
+        }
+    }
+
+    fn resolve_source_path(&mut self, source_engine: &SourceEngine) {
+        if self.source_path.is_none() {
+            self.source_path = Self::get_source_path(source_engine, &self.span);
+        }
+    }
+
     /// True if the `Label` is actually related to a span of source code in a source file.
     pub fn is_in_source(&self) -> bool {
         self.source_path.is_some() && (self.span.start() < self.span.end())
@@ -258,6 +286,13 @@ impl Issue {
             label: Label::info(source_engine, span, text),
         }
     }
+
+    /// A warning whose source path is resolved later, see [Label::unresolved].
+    pub fn unresolved_warning(span: Span, text: String) -> Self {
+        Self {
+            label: Label::unresolved(LabelType::Warning, span, text),
+        }
+    }
 }
 
 impl Default for Issue {
@@ -290,6 +325,13 @@ impl Hint {
         }
     }
 
+    /// An info whose source path is resolved later, see [Label::unresolved].
+    pub fn unresolved_info(span: Span, text: String) -> Self {
+        Self {
+            label: Label::unresolved(LabelType::Info, span, text),
+        }
+    }
+
     pub fn underscored_info(source_engine: &SourceEngine, span: Span) -> Self {
         Self::info(source_engine, span, "".to_string())
     }
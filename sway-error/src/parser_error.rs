@@ -103,6 +103,8 @@ pub enum ParseErrorKind {
     UnexpectedRestPattern,
     #[error("Identifier cannot be a reserved keyword.")]
     ReservedKeywordIdentifier,
+    #[error("Constant name cannot be the reserved keyword `{}`.", keyword)]
+    ReservedKeywordConstName { keyword: Ident },
     #[error("Unnecessary visibility qualifier, `{}` is implied here.", visibility)]
     UnnecessaryVisibilityQualifier { visibility: Ident },
     #[error("Expected a doc comment.")]
@@ -132,14 +132,15 @@ impl<'a> HoverLinkContents<'a> {
         let mut all_spans = vec![decl_span.clone()];
         all_spans.append(&mut impl_spans);
         all_spans.dedup();
-        for span in &all_spans {
-            let span_result = self
-                .session
-                .sync
-                .temp_to_workspace_span(self.engines.se(), span);
-            if let Ok(span) = span_result {
-                self.implementations.push(span);
-            }
-        }
+        let sync = &self.session.sync;
+        let spans = match sync.temp_to_workspace_spans(self.engines.se(), &all_spans) {
+            Ok(spans) => spans,
+            // Convert the spans one by one to only skip the ones that can't be converted.
+            Err(_) => all_spans
+                .iter()
+                .filter_map(|span| sync.temp_to_workspace_span(self.engines.se(), span).ok())
+                .collect(),
+        };
+        self.implementations.extend(spans);
     }
 }
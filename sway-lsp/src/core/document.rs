@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{fmt, path::Path, sync::Arc};
 
 use crate::{
+    core::file_system,
     error::{DirectoryError, DocumentError, LanguageServerError},
     utils::document,
 };
@@ -113,6 +114,7 @@ impl TextDocument {
     }
 }
 
+#[derive(Debug)]
 pub struct Documents(DashMap<String, TextDocument>);
 
 impl Default for Documents {
@@ -135,12 +137,19 @@ impl Documents {
     }
 
     /// Asynchronously writes the changes to the file and updates the document.
+    ///
+    /// If `normalize_line_endings` is set, the file is written with LF line endings, like the
+    /// copies of [crate::core::sync::SyncWorkspace::normalize_line_endings].
     pub async fn write_changes_to_file(
         &self,
         uri: &Url,
         changes: &[TextDocumentContentChangeEvent],
+        normalize_line_endings: bool,
     ) -> Result<(), LanguageServerError> {
-        let src = self.update_text_document(uri, changes)?;
+        let mut src = self.update_text_document(uri, changes)?;
+        if normalize_line_endings {
+            src = file_system::normalize_line_endings(&src);
+        }
 
         let mut file =
             File::create(uri.path())
@@ -207,6 +216,22 @@ impl Documents {
     }
 }
 
+/// A store of the documents open in the editor, whose contents are authoritative over the
+/// files on disk as they can have unsaved edits.
+pub trait DocumentStore: fmt::Debug + Send + Sync {
+    /// Return the contents of the open document at `path`, or `None` if it isn't open.
+    fn document_text(&self, path: &Path) -> Option<String>;
+}
+
+impl DocumentStore for Documents {
+    fn document_text(&self, path: &Path) -> Option<String> {
+        // The documents are keyed by the path of their url, see [Documents::store_document].
+        let url = Url::from_file_path(path).ok()?;
+        self.get(url.path())
+            .map(|document| document.get_text().to_string())
+    }
+}
+
 impl std::ops::Deref for Documents {
     type Target = DashMap<String, TextDocument>;
     fn deref(&self) -> &Self::Target {
@@ -291,6 +316,19 @@ mod tests {
         assert_eq!(result, DocumentError::DocumentNotFound { path });
     }
 
+    #[tokio::test]
+    async fn document_text_returns_the_contents_of_open_documents() {
+        let documents = Documents::new();
+        let path = get_absolute_path("sway-lsp/tests/fixtures/cats.txt");
+        let document = TextDocument::build_from_path(&path).await.unwrap();
+        let content = document.get_text().to_string();
+        documents.store_document(document).unwrap();
+
+        assert_eq!(documents.document_text(Path::new(&path)), Some(content));
+        let other = get_absolute_path("sway-lsp/tests/fixtures/dogs.txt");
+        assert_eq!(documents.document_text(Path::new(&other)), None);
+    }
+
     #[tokio::test]
     async fn store_document_returns_empty_tuple() {
         let documents = Documents::new();
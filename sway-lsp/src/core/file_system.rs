@@ -0,0 +1,504 @@
+use dashmap::DashMap;
+use std::{
+    ffi::OsStr,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+use sway_utils::SWAY_EXTENSION;
+use tempfile::Builder;
+use tokio_util::sync::CancellationToken;
+
+/// The filesystem operations used to sync the workspace into the temp directory, so that it
+/// can be synced to a backend other than the real filesystem, e.g. an in-memory one.
+pub trait FileSystem: fmt::Debug + Send + Sync {
+    /// Return the entries of the directory at `path`, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>>;
+    /// Copy the contents of the file at `from` to `to`, replacing it if it exists.
+    /// Returns the number of bytes copied, like [std::fs::copy].
+    ///
+    /// The files copied from the real filesystem keep their permissions, as [std::fs::copy]
+    /// preserves them, e.g. so that synced scripts stay executable.
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64>;
+    /// Create the directory at `path` along with its missing parents.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    /// Replace the contents of the file at `path`. Readers of the file must either see its old
+    /// or its new contents, but never a partially written file.
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()>;
+    /// Remove the directory at `path` along with everything it contains.
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Remove the file or symlink at `path`.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    /// Return what the entry at `path` is and when it was last modified, without following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+}
+
+/// The metadata of an entry, as returned by [FileSystem::metadata].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub kind: EntryKind,
+    pub modified: SystemTime,
+}
+
+/// An entry of a directory, as returned by [FileSystem::read_dir].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    pub kind: EntryKind,
+}
+
+impl DirEntry {
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+}
+
+/// What a [DirEntry] is. Symlinks are reported as such and not followed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// The real filesystem, through [std::fs].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        fs::read_dir(path)?
+            .map(|entry| {
+                let path = entry?.path();
+                let ty = fs::symlink_metadata(&path)?.file_type();
+                let kind = if ty.is_symlink() {
+                    EntryKind::Symlink
+                } else if ty.is_dir() {
+                    EntryKind::Dir
+                } else {
+                    EntryKind::File
+                };
+                Ok(DirEntry { path, kind })
+            })
+            .collect()
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    /// Write `contents` to a sibling file of `path` and rename it to `path`, as the rename is atomic.
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        let dir = path
+            .parent()
+            .ok_or_else(|| io::Error::other(format!("{path:?} has no parent directory")))?;
+        let mut file = Builder::new().prefix(".forc_lsp_").tempfile_in(dir)?;
+        file.write_all(contents.as_bytes())?;
+        file.persist(path).map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::symlink_metadata(path)?;
+        let ty = metadata.file_type();
+        let kind = if ty.is_symlink() {
+            EntryKind::Symlink
+        } else if ty.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+        Ok(Metadata {
+            kind,
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// A [FileSystem] reading some files from an in-memory overlay instead of the underlying one,
+/// e.g. the unsaved contents of a manifest open in the editor. Everything else goes through
+/// the underlying filesystem.
+#[derive(Debug)]
+pub(crate) struct OverlayFileSystem {
+    pub(crate) file_system: Arc<dyn FileSystem>,
+    /// The contents read instead of the files, keyed by path.
+    pub(crate) overlays: Arc<DashMap<PathBuf, String>>,
+}
+
+impl FileSystem for OverlayFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.file_system.read_dir(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        match self.overlays.get(from) {
+            Some(contents) => {
+                self.file_system.write(to, &contents)?;
+                Ok(contents.len() as u64)
+            }
+            None => self.file_system.copy(from, to),
+        }
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.overlays.get(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => self.file_system.read_to_string(path),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.file_system.write(path, contents)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.file_system.metadata(path)
+    }
+}
+
+/// A [FileSystem] converting the CRLF line endings of the `.sw` files it copies to LF, see
+/// [crate::core::sync::SyncWorkspace::normalize_line_endings]. Everything else goes through
+/// the underlying filesystem unchanged.
+#[derive(Debug)]
+pub(crate) struct LineEndingsFileSystem {
+    pub(crate) file_system: Arc<dyn FileSystem>,
+}
+
+impl FileSystem for LineEndingsFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.file_system.read_dir(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        if from.extension() != Some(OsStr::new(SWAY_EXTENSION)) {
+            return self.file_system.copy(from, to);
+        }
+        let contents = self.file_system.read_to_string(from)?;
+        if !contents.contains("\r\n") {
+            return self.file_system.copy(from, to);
+        }
+        let contents = normalize_line_endings(&contents);
+        self.file_system.write(to, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.file_system.read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.file_system.write(path, contents)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.file_system.metadata(path)
+    }
+}
+
+/// A [FileSystem] failing its copies once `cancel` is cancelled, so that a sync walking the
+/// workspace stops between two files, see [crate::core::sync::SyncWorkspace::resync_async].
+/// Everything else goes through the underlying filesystem.
+#[derive(Debug)]
+pub(crate) struct CancellableFileSystem {
+    pub(crate) file_system: Arc<dyn FileSystem>,
+    pub(crate) cancel: CancellationToken,
+}
+
+impl FileSystem for CancellableFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.file_system.read_dir(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        if self.cancel.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "sync cancelled"));
+        }
+        self.file_system.copy(from, to)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.file_system.read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.file_system.write(path, contents)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.file_system.metadata(path)
+    }
+}
+
+/// A [FileSystem] counting the files it copies and their bytes, for the
+/// [crate::core::sync::ResyncMetrics]. Everything else goes through the underlying filesystem.
+#[derive(Debug)]
+pub(crate) struct MeteredFileSystem {
+    pub(crate) file_system: Arc<dyn FileSystem>,
+    pub(crate) files_copied: AtomicUsize,
+    pub(crate) bytes_copied: AtomicU64,
+}
+
+impl MeteredFileSystem {
+    pub(crate) fn new(file_system: Arc<dyn FileSystem>) -> Self {
+        Self {
+            file_system,
+            files_copied: AtomicUsize::new(0),
+            bytes_copied: AtomicU64::new(0),
+        }
+    }
+}
+
+impl FileSystem for MeteredFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.file_system.read_dir(path)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let bytes = self.file_system.copy(from, to)?;
+        self.files_copied.fetch_add(1, Ordering::Relaxed);
+        self.bytes_copied.fetch_add(bytes, Ordering::Relaxed);
+        Ok(bytes)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.create_dir_all(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.file_system.read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        self.file_system.write(path, contents)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.file_system.remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.file_system.metadata(path)
+    }
+}
+
+/// Replace the CRLF line endings of `text` with LF. Lone CRs are left in place.
+pub(crate) fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// A [FileSystem] kept in memory, for tests that shouldn't touch the real filesystem.
+/// Directories are stored without contents, and files with theirs.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct MemoryFileSystem {
+    entries: parking_lot::Mutex<std::collections::BTreeMap<PathBuf, Option<String>>>,
+    /// The modification times of the entries, ticking forward on each write.
+    modified: parking_lot::Mutex<std::collections::BTreeMap<PathBuf, SystemTime>>,
+    clock: AtomicU64,
+}
+
+#[cfg(test)]
+impl MemoryFileSystem {
+    /// Create a filesystem holding the given files, along with their parent directories.
+    pub(crate) fn with_files<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let file_system = Self::default();
+        for (path, contents) in files {
+            let path = Path::new(path);
+            file_system.create_dir_all(path.parent().unwrap()).unwrap();
+            file_system.write(path, contents).unwrap();
+        }
+        file_system
+    }
+
+    /// Return the paths of the files, along with their contents.
+    pub(crate) fn files(&self) -> Vec<(PathBuf, String)> {
+        self.entries
+            .lock()
+            .iter()
+            .filter_map(|(path, contents)| Some((path.clone(), contents.clone()?)))
+            .collect()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.entries.lock().get(path), Some(None))
+    }
+
+    /// Mark `path` as modified after every other entry.
+    fn touch(&self, path: &Path) {
+        let tick = self.clock.fetch_add(1, Ordering::SeqCst) + 1;
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(tick);
+        self.modified.lock().insert(path.to_path_buf(), modified);
+    }
+}
+
+#[cfg(test)]
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{path:?} not found"))
+}
+
+#[cfg(test)]
+impl FileSystem for MemoryFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        if !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        Ok(self
+            .entries
+            .lock()
+            .iter()
+            .filter(|(entry, _)| entry.parent() == Some(path))
+            .map(|(entry, contents)| DirEntry {
+                path: entry.clone(),
+                kind: match contents {
+                    Some(_) => EntryKind::File,
+                    None => EntryKind::Dir,
+                },
+            })
+            .collect())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let contents = self.read_to_string(from)?;
+        self.write(to, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries.lock();
+        for dir in path.ancestors() {
+            match entries.get(dir) {
+                Some(Some(_)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{dir:?} is a file"),
+                    ))
+                }
+                Some(None) => {}
+                None => {
+                    entries.insert(dir.to_path_buf(), None);
+                    self.touch(dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.entries
+            .lock()
+            .get(path)
+            .cloned()
+            .flatten()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Path, contents: &str) -> io::Result<()> {
+        if !path.parent().is_some_and(|parent| self.is_dir(parent)) {
+            return Err(not_found(path));
+        }
+        self.entries
+            .lock()
+            .insert(path.to_path_buf(), Some(contents.to_string()));
+        self.touch(path);
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        if !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        self.entries
+            .lock()
+            .retain(|entry, _| !entry.starts_with(path));
+        self.modified
+            .lock()
+            .retain(|entry, _| !entry.starts_with(path));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        if !matches!(self.entries.lock().get(path), Some(Some(_))) {
+            return Err(not_found(path));
+        }
+        self.entries.lock().remove(path);
+        self.modified.lock().remove(path);
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let kind = match self.entries.lock().get(path) {
+            Some(Some(_)) => EntryKind::File,
+            Some(None) => EntryKind::Dir,
+            None => return Err(not_found(path)),
+        };
+        let modified = self
+            .modified
+            .lock()
+            .get(path)
+            .copied()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        Ok(Metadata { kind, modified })
+    }
+}
@@ -1,4 +1,5 @@
 pub mod document;
+pub mod file_system;
 pub mod session;
 pub(crate) mod sync;
 pub(crate) mod token;
@@ -29,7 +29,7 @@ use pkg::{
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic::AtomicBool, Arc},
     time::SystemTime,
 };
@@ -95,31 +95,28 @@ impl Session {
         }
     }
 
+    /// Sync the project in `manifest_dir`, the directory of a package or a workspace manifest,
+    /// into the temp directory and start watching its manifests.
     pub async fn init(
         &self,
-        uri: &Url,
-        documents: &Documents,
+        manifest_dir: &Path,
+        documents: &Arc<Documents>,
     ) -> Result<ProjectDirectory, LanguageServerError> {
-        let manifest_dir = PathBuf::from(uri.path());
+        // Convert the spans against the unsaved contents of the open documents.
+        self.sync.set_document_store(documents.clone());
         // Create a new temp dir that clones the current workspace
         // and store manifest and temp paths
-        self.sync.create_temp_dir_from_workspace(&manifest_dir)?;
+        self.sync.create_temp_dir_from_workspace(manifest_dir)?;
         self.sync.clone_manifest_dir_to_temp()?;
         // iterate over the project dir, parse all sway files
         let _ = self.store_sway_files(documents).await;
-        self.sync.watch_and_sync_manifest();
+        self.sync.watch_and_sync_manifest()?;
         self.sync.manifest_dir().map_err(Into::into)
     }
 
-    pub fn shutdown(&self) {
-        // shutdown the thread watching the manifest file
-        let handle = self.sync.notify_join_handle.read();
-        if let Some(join_handle) = &*handle {
-            join_handle.abort();
-        }
-
-        // Delete the temporary directory.
-        self.sync.remove_temp_dir();
+    pub async fn shutdown(&self) {
+        // Shutdown the threads watching the manifest and source files, then delete the temporary directory.
+        self.sync.close().await;
     }
 
     /// Return a reference to the [TokenMap] of the current session.
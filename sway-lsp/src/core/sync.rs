@@ -1,376 +1,7597 @@
 use crate::{
+    core::document::DocumentStore,
+    core::file_system::{
+        normalize_line_endings, CancellableFileSystem, EntryKind, FileSystem,
+        LineEndingsFileSystem, MeteredFileSystem, OverlayFileSystem, RealFileSystem,
+    },
     error::{DirectoryError, DocumentError, LanguageServerError},
     utils::document::{get_path_from_url, get_url_from_path, get_url_from_span},
 };
+use arc_swap::ArcSwap;
 use dashmap::DashMap;
-use forc_pkg::manifest::{GenericManifestFile, ManifestFile};
-use forc_pkg::PackageManifestFile;
+use forc_pkg::{
+    manifest::{GenericManifestFile, ManifestFile, PackageManifest, WorkspaceManifest},
+    source, Lock,
+};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use lsp_types::Url;
 use notify::RecursiveMode;
 use notify_debouncer_mini::new_debouncer;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use rayon_cond::CondIterator;
+use serde::Deserialize;
 use std::{
-    fs,
-    path::{Path, PathBuf},
-    time::Duration,
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap, HashSet},
+    ffi::OsStr,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
-use sway_types::{SourceEngine, Span};
+use sway_types::{span::Source, LineCol, SourceEngine, SourceId, Span};
 use sway_utils::{
     constants::{LOCK_FILE_NAME, MANIFEST_FILE_NAME},
-    SWAY_EXTENSION,
+    find_parent_manifest_dir, SWAY_EXTENSION,
+};
+use tempfile::{Builder, TempDir};
+use tokio::{
+    sync::{broadcast, mpsc::UnboundedSender, watch},
+    task::JoinHandle,
 };
-use tempfile::Builder;
-use tokio::task::JoinHandle;
-use tracing::error;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, trace, warn};
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Directory {
     Manifest,
     Temp,
+    /// The manifest directory of a workspace member, keyed by package name.
+    MemberManifest(String),
+    /// The temp directory of a workspace member, keyed by package name.
+    MemberTemp(String),
+}
+
+/// A package directory in the user's workspace along with its copy in the temp directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackageDirs {
+    pub manifest_dir: PathBuf,
+    pub temp_dir: PathBuf,
+}
+
+/// A change picked up by the watchers of a [SyncWorkspace], as published to [SyncWorkspace::subscribe].
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The `Forc.toml` files changed in the workspace, after their temp copies were rewritten.
+    ManifestChanged(Vec<PathBuf>),
+    /// The temp paths of the source files that were synced after changing in the workspace.
+    SourceChanged(Vec<PathBuf>),
+    /// The temp paths of the `Forc.lock` files that were synced after changing in the workspace,
+    /// e.g. by `forc update`, for the dependencies to be resolved again.
+    DependenciesChanged(Vec<PathBuf>),
+}
+
+/// What [SyncWorkspace::resync] would do to the temp directory, without doing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResyncPlan {
+    /// The paths in the temp directory that would be copied or removed.
+    pub changed_files: Vec<PathBuf>,
+    /// The temp manifests whose contents would change once their dependency paths are rewritten.
+    pub rewritten_manifests: Vec<PathBuf>,
+    /// The dependency paths that couldn't be resolved.
+    pub warnings: Vec<DependencyPathWarning>,
+}
+
+/// A path dependency that failed to canonicalize while rewriting a manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyPathWarning {
+    /// The directory of the manifest declaring the dependency.
+    pub manifest_dir: PathBuf,
+    /// The name of the dependency.
+    pub dependency: String,
+    /// The dependency path, joined to the manifest directory.
+    pub path: PathBuf,
 }
 
 #[derive(Debug)]
 pub struct SyncWorkspace {
     pub directories: DashMap<Directory, PathBuf>,
+    /// A snapshot of `directories`, refreshed whenever they change, for the hot path lookups.
+    cached_directories: ArcSwap<Directories>,
     pub notify_join_handle: RwLock<Option<JoinHandle<()>>>,
+    pub source_watch_join_handle: RwLock<Option<JoinHandle<()>>>,
+    /// The channel the source watcher sends the synced paths over, kept to restart the watcher.
+    pub source_changes: RwLock<Option<UnboundedSender<Vec<PathBuf>>>>,
+    /// The directory the temp workspace is created in, or the system temp directory if `None`.
+    pub temp_root: Option<PathBuf>,
+    /// The patterns of the workspace's ignore files, excluding paths from the temp workspace.
+    pub ignore_patterns: Arc<RwLock<Gitignore>>,
+    /// A process-wide unique id, included in the name of the temp directory to tell sessions apart.
+    pub session_id: usize,
+    /// When the workspace was last copied or resynced into the temp directory.
+    pub last_sync: Arc<RwLock<Option<Instant>>>,
+    /// Set to true once the workspace was first copied into the temp directory.
+    pub initial_sync: watch::Sender<bool>,
+    /// The files that get synced into the temp directory.
+    pub synced_files: SyncedFiles,
+    /// How long sync requests are coalesced for by [SyncWorkspace::resync_debounced]
+    /// and the manifest watcher, before the latest one runs.
+    pub resync_debounce: Duration,
+    /// How long the filesystem events of the manifest directories are debounced for by the manifest
+    /// watcher, before they are coalesced for `resync_debounce`, and by the source watcher.
+    pub manifest_watch_debounce: Duration,
+    /// The number of calls to [SyncWorkspace::resync_debounced], to tell whether a call is the latest.
+    resync_requests: AtomicU64,
+    /// The number of successful copies and resyncs into the temp directory, see [SyncWorkspace::generation].
+    generation: Arc<AtomicU64>,
+    /// The filesystem the workspace is copied, rewritten and cleaned up through.
+    pub file_system: Arc<dyn FileSystem>,
+    /// The channel the watchers publish their changes to, see [SyncWorkspace::subscribe].
+    watch_events: broadcast::Sender<WatchEvent>,
+    /// The channel the metrics of each sync are published to, see [SyncWorkspace::subscribe_metrics].
+    metrics: broadcast::Sender<ResyncMetrics>,
+    /// The contents the manifests are read from instead of the disk, keyed by manifest path,
+    /// see [SyncWorkspace::set_manifest_overlay].
+    manifest_overlays: Arc<DashMap<PathBuf, String>>,
+    /// The number of syncs running, see [SyncWorkspace::sync_generation].
+    running_syncs: Arc<Mutex<usize>>,
+    /// If set, path dependencies resolving outside of it are warned about, e.g. for sandboxing.
+    pub dependency_root: Option<PathBuf>,
+    /// Patterns in the `.gitignore` format of the paths left out of the temp workspace on top of
+    /// the ignore files, e.g. `tests/`. None are excluded by default.
+    pub excluded_patterns: Vec<String>,
+    /// If set, the server operates on the workspace in place when the temp directory can't be
+    /// created, e.g. on a runner without a writable temp directory, instead of failing.
+    pub temp_dir_fallback: bool,
+    /// If set, the CRLF line endings of the `.sw` files are converted to LF in their temp copies.
+    /// Off by default, see [SyncWorkspace::with_normalize_line_endings].
+    pub normalize_line_endings: bool,
+    /// If set, the temp root is named after a hash of the manifest directory instead of randomly,
+    /// see [SyncWorkspace::with_deterministic_temp_dir].
+    pub deterministic_temp_dir: bool,
+    /// If set, the copies of the `.sw` files made by [SyncWorkspace::clone_manifest_dir_to_temp] are
+    /// checked against their source, see [SyncWorkspace::with_verify_copies].
+    pub verify_copies: bool,
+    /// If set, only the source directories of the packages are copied along with the manifests
+    /// and lock files, see [SyncWorkspace::with_source_dirs_only].
+    pub source_dirs_only: bool,
+    /// The parsed manifest of the manifest directory, see [SyncWorkspace::manifest].
+    manifest: Arc<RwLock<Option<ManifestFile>>>,
+    /// The options of the project's [ProjectConfig::FILE_NAME], read by
+    /// [SyncWorkspace::create_temp_dir_from_workspace].
+    project_config: RwLock<ProjectConfig>,
+    /// Independent projects synced along with this one, keyed by their manifest directory,
+    /// see [SyncWorkspace::register_root].
+    roots: DashMap<PathBuf, Arc<SyncWorkspace>>,
+    /// If set, the urls and spans aren't converted between the workspace and the temp directory,
+    /// see [SyncWorkspace::set_direct_mode].
+    direct_mode: AtomicBool,
+    /// If set, the watchers drop the events they receive, see [SyncWorkspace::pause_watcher].
+    watcher_paused: Arc<AtomicBool>,
+    /// The documents open in the editor, read instead of the files on disk when converting spans,
+    /// see [SyncWorkspace::set_document_store].
+    document_store: RwLock<Option<Arc<dyn DocumentStore>>>,
+    /// The workspace files compared with the temp sources of converted spans, keyed by workspace
+    /// path, see [SyncWorkspace::differing_workspace_source].
+    workspace_sources: DashMap<PathBuf, WorkspaceSource>,
+}
+
+/// A workspace file as compared with the text of its temp copy the compiler produced spans in.
+#[derive(Clone)]
+struct WorkspaceSource {
+    /// The text of the temp copy it was compared with.
+    temp_text: Arc<str>,
+    /// The contents of the workspace file, if the offsets of the spans have to be recomputed.
+    differing: Option<Source>,
+}
+
+impl fmt::Debug for WorkspaceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspaceSource")
+            .field("temp_len", &self.temp_text.len())
+            .field("differing", &self.differing.is_some())
+            .finish()
+    }
+}
+
+/// The sync options of a project, read from the [ProjectConfig::FILE_NAME] in its manifest
+/// directory, e.g.:
+///
+/// ```toml
+/// ignore = ["tests/"]
+/// synced-extensions = ["abi.json"]
+/// temp-root = "../.sway-lsp"
+/// resync-debounce-ms = 200
+/// manifest-watch-debounce-ms = 1000
+/// ```
+///
+/// The lists extend the options the [SyncWorkspace] was built with, and the other options
+/// replace them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProjectConfig {
+    /// Patterns excluding paths from the temp workspace, see [SyncWorkspace::with_excluded_patterns].
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Extensions of the files synced on top of [SyncWorkspace::synced_files], without the leading dot.
+    #[serde(default)]
+    pub synced_extensions: Vec<String>,
+    /// The directory the temp workspace is created in, relative to the manifest directory.
+    pub temp_root: Option<PathBuf>,
+    pub resync_debounce_ms: Option<u64>,
+    pub manifest_watch_debounce_ms: Option<u64>,
+}
+
+impl ProjectConfig {
+    pub const FILE_NAME: &'static str = ".sway-lsp.toml";
+
+    /// Parse the options in `contents`. Unknown keys are logged and skipped.
+    pub fn parse(contents: &str) -> Result<Self, toml_edit::de::Error> {
+        let deserializer = contents.parse::<toml_edit::de::Deserializer>()?;
+        serde_ignored::deserialize(deserializer, |key| {
+            warn!("Unknown key in {}: {}", Self::FILE_NAME, key);
+        })
+    }
+
+    /// Read the [ProjectConfig::FILE_NAME] of `manifest_dir`. A missing file yields the default
+    /// options, and one that fails to be read or parsed is logged and yields them too.
+    fn from_dir(file_system: &dyn FileSystem, manifest_dir: &Path) -> Self {
+        let path = manifest_dir.join(Self::FILE_NAME);
+        let contents = match file_system.read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                warn!("Failed to read {:?}: {}", path, err);
+                return Self::default();
+            }
+        };
+        Self::parse(&contents).unwrap_or_else(|err| {
+            warn!("Failed to parse {:?}: {}", path, err);
+            Self::default()
+        })
+    }
+}
+
+/// The files that get synced into the temp directory, matched by file name or extension.
+/// Defaults to the files ending with `.sw`, and the specific files `Forc.toml` and `Forc.lock`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncedFiles {
+    /// The names of the files to sync, e.g. `Forc.toml`.
+    pub file_names: BTreeSet<String>,
+    /// The extensions of the files to sync, without the leading dot, e.g. `sw` or `abi.json`.
+    pub extensions: BTreeSet<String>,
+}
+
+impl Default for SyncedFiles {
+    fn default() -> Self {
+        Self {
+            file_names: [MANIFEST_FILE_NAME, LOCK_FILE_NAME]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            extensions: BTreeSet::from([SWAY_EXTENSION.to_string()]),
+        }
+    }
+}
+
+impl SyncedFiles {
+    /// Returns true if the file at `path` gets synced into the temp directory.
+    pub fn contains(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| {
+                self.file_names.contains(file_name)
+                    || self
+                        .extensions
+                        .iter()
+                        .any(|extension| file_name.ends_with(&format!(".{extension}")))
+            })
+    }
+}
+
+/// A diagnostic reported by the `forc build` of [SyncWorkspace::build_temp], along with the file
+/// it is in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildDiagnostic {
+    pub uri: Url,
+    pub diagnostic: lsp_types::Diagnostic,
+}
+
+/// How much a successful sync into the temp directory copied and how long it took, see
+/// [SyncWorkspace::subscribe_metrics].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResyncMetrics {
+    /// The number of files copied, which for a resync only counts the files that changed.
+    pub files_copied: usize,
+    pub bytes_copied: u64,
+    pub elapsed: Duration,
+}
+
+impl ResyncMetrics {
+    fn new(file_system: &MeteredFileSystem, start: Instant) -> Self {
+        Self {
+            files_copied: file_system.files_copied.load(Ordering::Relaxed),
+            bytes_copied: file_system.bytes_copied.load(Ordering::Relaxed),
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// A snapshot of the state of a [SyncWorkspace], for status reporting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncStatus {
+    pub manifest_dir: Option<PathBuf>,
+    pub temp_dir: Option<PathBuf>,
+    /// Whether the manifest or source files are being watched for changes.
+    pub watcher_active: bool,
+    pub last_sync: Option<Instant>,
+}
+
+/// The [SyncWorkspace::generation], and whether any sync is running, as written to
+/// [SyncWorkspace::SYNC_GENERATION_FILE_NAME] in the temp root.
+///
+/// External tools reading the temp workspace can read it before and after, and read again if
+/// a sync was running or the generation changed in the meantime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncGeneration {
+    pub generation: u64,
+    pub syncing: bool,
+}
+
+impl fmt::Display for SyncGeneration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.generation)?;
+        if self.syncing {
+            write!(f, " syncing")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for SyncGeneration {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (generation, syncing) = match s.strip_suffix(" syncing") {
+            Some(generation) => (generation, true),
+            None => (s, false),
+        };
+        Ok(Self {
+            generation: generation.parse()?,
+            syncing,
+        })
+    }
+}
+
+/// Keeps the [SyncGeneration] of a [SyncWorkspace] up to date in the temp root.
+#[derive(Clone, Debug)]
+struct SyncTracker {
+    /// The successful syncs, see [SyncWorkspace::generation].
+    generation: Arc<AtomicU64>,
+    /// The number of syncs running.
+    running: Arc<Mutex<usize>>,
+    file_system: Arc<dyn FileSystem>,
+    /// The file the generation is written to, if the temp root was created by the [SyncWorkspace].
+    file: Option<PathBuf>,
+}
+
+impl SyncTracker {
+    /// Mark a sync as running until the returned guard is dropped.
+    ///
+    /// Syncs can begin while another one is running, e.g. the clone of a resync, in which case
+    /// the temp root is only written to when the first one begins and the last one ends.
+    fn begin(&self) -> SyncGuard<'_> {
+        let mut running = self.running.lock();
+        *running += 1;
+        if *running == 1 {
+            self.write(true);
+        }
+        SyncGuard(self)
+    }
+
+    fn end(&self) {
+        let mut running = self.running.lock();
+        *running -= 1;
+        if *running == 0 {
+            self.write(false);
+        }
+    }
+
+    /// Write the current generation to the temp root. Called with the lock of `running` held, so
+    /// that the file ends up with the latest state.
+    fn write(&self, syncing: bool) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let generation = SyncGeneration {
+            generation: self.generation.load(Ordering::SeqCst),
+            syncing,
+        };
+        if let Err(err) = self.file_system.write(file, &format!("{generation}\n")) {
+            warn!("Failed to write sync generation to {:?}: {}", file, err);
+        }
+    }
+}
+
+/// The state a resync of [SyncWorkspace::resync_changed] operates on, detached from the
+/// [SyncWorkspace] so that it can run on another thread, see [SyncWorkspace::resync_async].
+struct ResyncJob {
+    tracker: SyncTracker,
+    copy_file_system: Arc<dyn FileSystem>,
+    manifest_file_system: Arc<dyn FileSystem>,
+    copied_dirs: Vec<PackageDirs>,
+    package_dirs: Vec<PackageDirs>,
+    ignore: Gitignore,
+    synced_files: SyncedFiles,
+    dependency_root: Option<PathBuf>,
+    temp_dir: PathBuf,
+    last_sync: Arc<RwLock<Option<Instant>>>,
+    metrics: broadcast::Sender<ResyncMetrics>,
+}
+
+impl ResyncJob {
+    /// Sync the package directories and rewrite the manifests, stopping between two files once
+    /// `cancel` is cancelled.
+    fn run(self, cancel: Option<&CancellationToken>) -> Result<Vec<PathBuf>, LanguageServerError> {
+        let _sync = self.tracker.begin();
+        let start = Instant::now();
+        let is_cancelled = || cancel.is_some_and(CancellationToken::is_cancelled);
+        let copy_file_system: Arc<dyn FileSystem> = match cancel {
+            Some(cancel) => Arc::new(CancellableFileSystem {
+                file_system: self.copy_file_system.clone(),
+                cancel: cancel.clone(),
+            }),
+            None => self.copy_file_system.clone(),
+        };
+        let copy_file_system = MeteredFileSystem::new(copy_file_system);
+        let mut changed = Vec::new();
+        let mut cancelled = false;
+        for dirs in &self.copied_dirs {
+            if is_cancelled() {
+                cancelled = true;
+                break;
+            }
+            match sync_dir_contents(
+                &copy_file_system,
+                &dirs.manifest_dir,
+                &dirs.temp_dir,
+                &self.ignore,
+                &self.synced_files,
+                false,
+            ) {
+                Ok(dir_changed) => changed.extend(dir_changed),
+                Err(_) if is_cancelled() => {
+                    cancelled = true;
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        // The manifests copied before a cancellation are rewritten too, as they would otherwise
+        // be left with the relative dependency paths of the workspace.
+        edit_manifests_dependency_paths(
+            &*self.manifest_file_system,
+            &self.package_dirs,
+            self.dependency_root.as_deref(),
+        )?;
+        if cancelled {
+            debug!(synced = changed.len(), "Resync cancelled");
+            return Err(LanguageServerError::ResyncCancelled);
+        }
+
+        *self.last_sync.write() = Some(Instant::now());
+        // The temp directory was synced even if its lock file turns out to be out of date.
+        self.tracker.generation.fetch_add(1, Ordering::SeqCst);
+        // Sending only fails if nobody subscribed.
+        let _ = self
+            .metrics
+            .send(ResyncMetrics::new(&copy_file_system, start));
+
+        let dependencies = lock_file_mismatches(&*self.manifest_file_system, &self.temp_dir)?;
+        if !dependencies.is_empty() {
+            return Err(LanguageServerError::LockFileOutOfDate { dependencies });
+        }
+        Ok(changed)
+    }
+}
+
+/// A sync that is running, see [SyncTracker::begin].
+struct SyncGuard<'a>(&'a SyncTracker);
+
+impl Drop for SyncGuard<'_> {
+    fn drop(&mut self) {
+        self.0.end();
+    }
 }
 
+/// The id of the next [SyncWorkspace] to be created.
+static NEXT_SESSION_ID: AtomicUsize = AtomicUsize::new(0);
+
 impl SyncWorkspace {
     pub const LSP_TEMP_PREFIX: &'static str = "SWAY_LSP_TEMP_DIR";
+    pub const DEFAULT_RESYNC_DEBOUNCE: Duration = Duration::from_millis(100);
+    pub const DEFAULT_MANIFEST_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+    /// The file in the temp root holding the [SyncGeneration], next to the project's temp directory.
+    pub const SYNC_GENERATION_FILE_NAME: &'static str = ".sync_generation";
+    /// How many [WatchEvent]s are kept for subscribers lagging behind.
+    const WATCH_EVENTS_CAPACITY: usize = 64;
 
     pub(crate) fn new() -> Self {
+        Self::with_temp_root(None)
+    }
+
+    /// Create a [SyncWorkspace] whose temp workspace is created in `temp_root` instead of the
+    /// system temp directory, e.g. to keep it on the same filesystem as the project.
+    pub(crate) fn with_temp_root(temp_root: Option<PathBuf>) -> Self {
         Self {
             directories: DashMap::new(),
+            cached_directories: ArcSwap::default(),
             notify_join_handle: RwLock::new(None),
+            source_watch_join_handle: RwLock::new(None),
+            source_changes: RwLock::new(None),
+            temp_root,
+            ignore_patterns: Arc::new(RwLock::new(Gitignore::empty())),
+            session_id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            last_sync: Arc::default(),
+            initial_sync: watch::Sender::new(false),
+            synced_files: SyncedFiles::default(),
+            resync_debounce: Self::DEFAULT_RESYNC_DEBOUNCE,
+            manifest_watch_debounce: Self::DEFAULT_MANIFEST_WATCH_DEBOUNCE,
+            resync_requests: AtomicU64::new(0),
+            generation: Arc::default(),
+            file_system: Arc::new(RealFileSystem),
+            watch_events: broadcast::Sender::new(Self::WATCH_EVENTS_CAPACITY),
+            metrics: broadcast::Sender::new(Self::WATCH_EVENTS_CAPACITY),
+            manifest_overlays: Arc::default(),
+            running_syncs: Arc::default(),
+            dependency_root: None,
+            excluded_patterns: Vec::new(),
+            temp_dir_fallback: false,
+            normalize_line_endings: false,
+            deterministic_temp_dir: false,
+            verify_copies: false,
+            source_dirs_only: false,
+            manifest: Arc::default(),
+            project_config: RwLock::default(),
+            roots: DashMap::new(),
+            direct_mode: AtomicBool::new(false),
+            watcher_paused: Arc::default(),
+            document_store: RwLock::new(None),
+            workspace_sources: DashMap::new(),
         }
     }
 
-    /// Overwrite the contents of the tmp/folder with everything in
-    /// the current workspace.
-    pub fn resync(&self) -> Result<(), LanguageServerError> {
-        self.clone_manifest_dir_to_temp()?;
-        if let (Ok(manifest_dir), Some(manifest_path), Some(temp_manifest_path)) = (
-            self.manifest_dir(),
-            self.manifest_path(),
-            self.temp_manifest_path(),
-        ) {
-            edit_manifest_dependency_paths(&manifest_dir, &manifest_path, &temp_manifest_path)?;
-        }
-        Ok(())
+    /// Subscribe to the changes picked up by the manifest and source watchers, e.g. to invalidate
+    /// caches. Only the events published after subscribing are received.
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.watch_events.subscribe()
     }
 
-    /// Clean up the temp directory that was created once the
-    /// server closes down.
-    pub(crate) fn remove_temp_dir(&self) {
-        if let Ok(dir) = self.temp_dir() {
-            dir.parent().map(fs::remove_dir);
-        }
+    /// Subscribe to the [ResyncMetrics] of each successful [SyncWorkspace::clone_manifest_dir_to_temp]
+    /// and [SyncWorkspace::resync], e.g. for a performance dashboard. Only the metrics of the syncs
+    /// completed after subscribing are received.
+    pub fn subscribe_metrics(&self) -> broadcast::Receiver<ResyncMetrics> {
+        self.metrics.subscribe()
     }
 
-    pub(crate) fn create_temp_dir_from_workspace(
+    /// Create a [SyncWorkspace] for a project in `manifest_dir` whose temp workspace is the existing
+    /// `temp_dir`, e.g. for tools managing their own temp directories, instead of creating one with
+    /// [SyncWorkspace::create_temp_dir_from_workspace].
+    ///
+    /// The temp directory is left in place by [SyncWorkspace::remove_temp_dir].
+    pub fn with_directories(manifest_dir: PathBuf, temp_dir: PathBuf) -> Self {
+        let sync = Self::new();
+        *sync.ignore_patterns.write() =
+            ignore_patterns(&*sync.file_system, &manifest_dir, &sync.excluded_patterns);
+        sync.insert_directory(Directory::Manifest, manifest_dir);
+        sync.insert_directory(Directory::Temp, temp_dir);
+        sync
+    }
+
+    /// Sync `synced_files` into the temp directory instead of the default [SyncedFiles].
+    pub fn with_synced_files(mut self, synced_files: SyncedFiles) -> Self {
+        self.synced_files = synced_files;
+        self
+    }
+
+    /// Go through `file_system` instead of the real filesystem.
+    pub fn with_file_system(mut self, file_system: Arc<dyn FileSystem>) -> Self {
+        self.file_system = file_system;
+        self
+    }
+
+    /// Read the manifest at `manifest_path` from `contents` instead of the disk, e.g. the unsaved
+    /// contents of the editor buffer, and rewrite the temp manifests with it.
+    ///
+    /// Contents that aren't valid TOML, e.g. a half-written edit, are returned as
+    /// [DocumentError::IOError] and leave the previous overlay in place.
+    pub fn set_manifest_overlay(
         &self,
-        manifest_dir: &Path,
+        manifest_path: PathBuf,
+        contents: String,
     ) -> Result<(), LanguageServerError> {
-        let manifest = PackageManifestFile::from_dir(manifest_dir).map_err(|_| {
-            DocumentError::ManifestFileNotFound {
-                dir: manifest_dir.to_string_lossy().to_string(),
+        if let Err(err) = contents.parse::<toml_edit::DocumentMut>() {
+            return Err(DocumentError::IOError {
+                path: manifest_path.to_string_lossy().to_string(),
+                error: format!("Failed to parse TOML: {}", err),
             }
-        })?;
+            .into());
+        }
+        self.manifest_overlays.insert(manifest_path, contents);
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        edit_manifests_dependency_paths(
+            &*self.manifest_file_system(),
+            &self.package_dirs(),
+            self.dependency_root.as_deref(),
+        )
+    }
 
-        // strip Forc.toml from the path to get the manifest directory
-        let manifest_dir = manifest
-            .path()
-            .parent()
-            .ok_or(DirectoryError::ManifestDirNotFound)?;
+    /// Read the manifest at `manifest_path` from the disk again, e.g. once its editor buffer was
+    /// saved or closed, and rewrite the temp manifests if it had an overlay.
+    pub fn clear_manifest_overlay(&self, manifest_path: &Path) -> Result<(), LanguageServerError> {
+        if self.manifest_overlays.remove(manifest_path).is_none() {
+            return Ok(());
+        }
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        edit_manifests_dependency_paths(
+            &*self.manifest_file_system(),
+            &self.package_dirs(),
+            self.dependency_root.as_deref(),
+        )
+    }
 
-        // extract the project name from the path
-        let project_name = manifest_dir
-            .file_name()
-            .and_then(|name| name.to_str())
-            .ok_or(DirectoryError::CantExtractProjectName {
-                dir: manifest_dir.to_string_lossy().to_string(),
+    /// Point the dependency `name` of the temp manifest at `new_path` without a full resync, e.g.
+    /// to switch it between a local checkout and the registry. A relative `new_path` is resolved
+    /// against the manifest directory, like the paths of the workspace manifest.
+    ///
+    /// Only the temp manifest is rewritten, so the change is undone by the next resync of the
+    /// workspace manifest. Returns [DocumentError::DependencyNotFound] if the temp manifest has no
+    /// such dependency.
+    pub fn rewrite_dependency_path(
+        &self,
+        name: &str,
+        new_path: &Path,
+    ) -> Result<(), LanguageServerError> {
+        let manifest_dir = self.manifest_dir()?;
+        if self.is_in_place() {
+            warn!(
+                "Not rewriting dependency {} of the workspace manifest in {:?} used in place",
+                name, manifest_dir
+            );
+            return Ok(());
+        }
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        let (temp_manifest_path, mut doc) = self.read_temp_manifest()?;
+        let section = DEPENDENCY_SECTIONS.into_iter().find(|section| {
+            doc.get(section)
+                .and_then(|v| v.as_table())
+                .is_some_and(|deps_table| deps_table.contains_key(name))
+        });
+        let deps_table = section
+            .and_then(|section| doc.get_mut(section))
+            .and_then(|v| v.as_table_mut())
+            .ok_or_else(|| DocumentError::DependencyNotFound {
+                name: name.to_string(),
+                path: temp_manifest_path.to_string_lossy().to_string(),
             })?;
+        // A registry dependency declared as `dep = "0.1.0"` has no table to hold a path.
+        if deps_table.get(name).is_some_and(|item| item.is_str()) {
+            deps_table.insert(name, toml_edit::value(toml_edit::InlineTable::new()));
+        }
 
-        // Create a new temporary directory that we can clone the current workspace into.
-        let temp_dir = Builder::new()
-            .prefix(SyncWorkspace::LSP_TEMP_PREFIX)
-            .tempdir()
-            .map_err(|_| DirectoryError::TempDirFailed)?;
-
-        let temp_path = temp_dir
-            .into_path()
-            .canonicalize()
-            .map_err(|_| DirectoryError::CanonicalizeFailed)?
-            .join(project_name);
-
-        self.directories
-            .insert(Directory::Manifest, manifest_dir.to_path_buf());
-        self.directories.insert(Directory::Temp, temp_path);
+        let mut details = forc_pkg::manifest::DependencyDetails::default();
+        details.path = Some(new_path.to_string_lossy().to_string());
+        let deps = BTreeMap::from([(
+            name.to_string(),
+            forc_pkg::manifest::Dependency::Detailed(details),
+        )]);
+        process_dependencies(
+            &manifest_dir,
+            &deps,
+            deps_table,
+            &self.package_dirs(),
+            None,
+            self.dependency_root.as_deref(),
+        );
 
+        self.file_system
+            .write(&temp_manifest_path, &doc.to_string())
+            .map_err(|err| DocumentError::UnableToWriteFile {
+                path: temp_manifest_path.to_string_lossy().to_string(),
+                err: err.to_string(),
+            })?;
         Ok(())
     }
 
-    pub(crate) fn clone_manifest_dir_to_temp(&self) -> Result<(), DirectoryError> {
-        copy_dir_contents(self.manifest_dir()?, self.temp_dir()?)
-            .map_err(|_| DirectoryError::CopyContentsFailed)?;
+    /// Return the name and path of each path dependency of the temp manifest, in the order of
+    /// its dependency sections, e.g. to check that they were all rewritten. The paths are the ones
+    /// written to the temp manifest: a relative one is a dependency the sync didn't rewrite.
+    ///
+    /// Git and registry dependencies have no path and are left out.
+    pub fn temp_dependency_paths(&self) -> Result<Vec<(String, PathBuf)>, LanguageServerError> {
+        let (_temp_manifest_path, doc) = self.read_temp_manifest()?;
+        let paths = DEPENDENCY_SECTIONS
+            .into_iter()
+            .filter_map(|section| doc.get(section).and_then(|deps| deps.as_table_like()))
+            .flat_map(|deps| deps.iter())
+            .filter_map(|(name, dependency)| {
+                let path = dependency.as_table_like()?.get("path")?.as_str()?;
+                Some((name.to_string(), PathBuf::from(path)))
+            })
+            .collect();
+        Ok(paths)
+    }
 
-        Ok(())
+    /// Run `forc build` in the temp directory, e.g. for external build tooling, and return the
+    /// diagnostics it reported, with the paths of the temp files converted to the workspace.
+    ///
+    /// `forc build` has no machine-readable output, so the diagnostics are parsed from the
+    /// rendered ones: each gets the message of its title, or of its first label if it has none,
+    /// and the position its `-->` line points at. Returns [LanguageServerError::ForcNotFound] if
+    /// `forc` isn't on the PATH, and [LanguageServerError::ForcBuildFailed] if the build failed
+    /// without reporting any diagnostic.
+    pub fn build_temp(&self) -> Result<Vec<BuildDiagnostic>, LanguageServerError> {
+        self.build_temp_with(OsStr::new("forc"))
     }
 
-    /// Convert the Url path from the client to point to the same file in our temp folder
-    pub(crate) fn workspace_to_temp_url(&self, uri: &Url) -> Result<Url, DirectoryError> {
-        convert_url(uri, &self.temp_dir()?, &self.manifest_dir()?)
+    fn build_temp_with(&self, forc: &OsStr) -> Result<Vec<BuildDiagnostic>, LanguageServerError> {
+        let temp_dir = self.temp_dir()?;
+        let output = std::process::Command::new(forc)
+            .arg("build")
+            .arg("--path")
+            .arg(&temp_dir)
+            .env("NO_COLOR", "1")
+            .output()
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => LanguageServerError::ForcNotFound,
+                _ => LanguageServerError::ForcBuildFailed(err.to_string()),
+            })?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let diagnostics = parse_build_diagnostics(&format!("{stdout}\n{stderr}"))
+            .into_iter()
+            .map(|mut diagnostic| {
+                if self.is_path_in_temp_workspace(&diagnostic.uri) {
+                    diagnostic.uri = self.temp_to_workspace_url(&diagnostic.uri)?;
+                }
+                Ok(diagnostic)
+            })
+            .collect::<Result<Vec<_>, DirectoryError>>()?;
+        if !output.status.success() && diagnostics.is_empty() {
+            return Err(LanguageServerError::ForcBuildFailed(
+                stderr.trim().to_string(),
+            ));
+        }
+        Ok(diagnostics)
     }
 
-    /// Convert the [Url] path from the temp folder to point to the same file in the users workspace.
-    pub(crate) fn temp_to_workspace_url(&self, uri: &Url) -> Result<Url, DirectoryError> {
-        convert_url(uri, &self.manifest_dir()?, &self.temp_dir()?)
+    /// Read and parse the manifest of the temp directory, returning its path along with it.
+    fn read_temp_manifest(&self) -> Result<(PathBuf, toml_edit::DocumentMut), LanguageServerError> {
+        let temp_manifest_path = self.temp_dir()?.join(MANIFEST_FILE_NAME);
+        let contents = self
+            .file_system
+            .read_to_string(&temp_manifest_path)
+            .map_err(|err| DocumentError::IOError {
+                path: temp_manifest_path.to_string_lossy().to_string(),
+                error: err.to_string(),
+            })?;
+        let doc =
+            contents
+                .parse::<toml_edit::DocumentMut>()
+                .map_err(|err| DocumentError::IOError {
+                    path: temp_manifest_path.to_string_lossy().to_string(),
+                    error: format!("Failed to parse TOML: {}", err),
+                })?;
+        Ok((temp_manifest_path, doc))
     }
 
-    /// If it is a path to a temp directory, convert the path in the [Span] to the same file in the user's
-    /// workspace. Otherwise, return the span as-is.
-    pub(crate) fn temp_to_workspace_span(
-        &self,
-        source_engine: &SourceEngine,
-        span: &Span,
-    ) -> Result<Span, DirectoryError> {
-        let url = get_url_from_span(source_engine, span)?;
-        if is_path_in_temp_workspace(&url) {
-            let converted_url = convert_url(&url, &self.manifest_dir()?, &self.temp_dir()?)?;
-            let converted_path = get_path_from_url(&converted_url)?;
-            let source_id = source_engine.get_source_id(&converted_path);
-            let converted_span = Span::new(
-                span.src().clone(),
-                span.start(),
-                span.end(),
-                Some(source_id),
-            );
-            match converted_span {
-                Some(span) => Ok(span),
-                None => Err(DirectoryError::SpanFromPathFailed {
-                    path: converted_path.to_string_lossy().to_string(),
-                }),
-            }
+    /// The filesystem the manifests are read through, which reads the overlaid manifests
+    /// from their overlay.
+    fn manifest_file_system(&self) -> Arc<dyn FileSystem> {
+        Arc::new(OverlayFileSystem {
+            file_system: self.file_system.clone(),
+            overlays: self.manifest_overlays.clone(),
+        })
+    }
+
+    /// Wrap `file_system` to convert the line endings of the `.sw` files it copies into the temp
+    /// directory, if [SyncWorkspace::normalize_line_endings] is set.
+    fn copy_file_system(&self, file_system: Arc<dyn FileSystem>) -> Arc<dyn FileSystem> {
+        if self.normalize_line_endings {
+            Arc::new(LineEndingsFileSystem { file_system })
         } else {
-            Ok(span.clone())
+            file_system
         }
     }
 
-    /// If path is part of the users workspace, then convert URL from temp to workspace dir.
-    /// Otherwise, pass through if it points to a dependency path
-    pub(crate) fn to_workspace_url(&self, url: Url) -> Option<Url> {
-        if is_path_in_temp_workspace(&url) {
-            Some(self.temp_to_workspace_url(&url).ok()?)
-        } else {
-            Some(url)
+    /// Warn about the path dependencies resolving outside of `dependency_root`, e.g. an ancestor of
+    /// the manifest directory. They are still rewritten, as the warning isn't a hard failure.
+    pub fn with_dependency_root(mut self, dependency_root: PathBuf) -> Self {
+        self.dependency_root = Some(dependency_root);
+        self
+    }
+
+    /// Leave the paths matching `excluded_patterns` out of the temp workspace, e.g. `tests/` to
+    /// only compile the production sources. They are matched like the patterns of a `.gitignore`
+    /// in the manifest directory, and never exclude the `Forc.toml` and `Forc.lock` files.
+    pub fn with_excluded_patterns(mut self, excluded_patterns: Vec<String>) -> Self {
+        self.excluded_patterns = excluded_patterns;
+        if let Ok(manifest_dir) = self.manifest_dir() {
+            *self.ignore_patterns.write() = ignore_patterns(
+                &*self.file_system,
+                &manifest_dir,
+                &self.effective_excluded_patterns(),
+            );
         }
+        self
     }
 
-    pub(crate) fn temp_manifest_path(&self) -> Option<PathBuf> {
-        self.temp_dir()
-            .map(|dir| dir.join(sway_utils::constants::MANIFEST_FILE_NAME))
-            .ok()
+    /// Operate on the workspace in place instead of failing when the temp directory can't be
+    /// created, see [SyncWorkspace::temp_dir_fallback].
+    pub fn with_temp_dir_fallback(mut self, temp_dir_fallback: bool) -> Self {
+        self.temp_dir_fallback = temp_dir_fallback;
+        self
     }
 
-    pub fn manifest_path(&self) -> Option<PathBuf> {
-        self.manifest_dir()
-            .map(|dir| dir.join(sway_utils::constants::MANIFEST_FILE_NAME))
-            .ok()
+    /// Convert the CRLF line endings of the `.sw` files to LF when copying them into the temp
+    /// directory, e.g. for the files checked out on Windows.
+    ///
+    /// The compiler then computes the byte offsets of its spans on the LF copies, which differ from
+    /// the offsets in the workspace files by the number of CRs before them. Line and column positions
+    /// are unaffected, as the CRs are at the end of the lines, so the spans of the temp files have
+    /// their offsets recomputed from their lines and columns in the workspace files, see
+    /// [SyncWorkspace::temp_to_workspace_spans], while [SyncWorkspace::workspace_to_temp_span] moves
+    /// the spans of the workspace files onto the LF text.
+    pub fn with_normalize_line_endings(mut self, normalize_line_endings: bool) -> Self {
+        self.normalize_line_endings = normalize_line_endings;
+        self
     }
 
-    /// Watch the manifest directory and check for any save events on Forc.toml
-    pub(crate) fn watch_and_sync_manifest(&self) {
-        if let (Ok(manifest_dir), Some(manifest_path), Some(temp_manifest_path)) = (
-            self.manifest_dir(),
-            self.manifest_path(),
-            self.temp_manifest_path(),
-        ) {
-            if let Err(err) =
-                edit_manifest_dependency_paths(&manifest_dir, &manifest_path, &temp_manifest_path)
-            {
-                error!("Failed to edit manifest dependency paths: {}", err);
-            }
+    /// Name the temp root after a hash of the canonical manifest directory instead of a random
+    /// suffix, so that the temp workspace of a project keeps the same path across sessions, e.g.
+    /// for caches keyed on absolute paths.
+    ///
+    /// A temp root left over by a previous session, e.g. one that crashed, is removed and
+    /// created again. Two servers running on the same project therefore share the temp root.
+    pub fn with_deterministic_temp_dir(mut self, deterministic_temp_dir: bool) -> Self {
+        self.deterministic_temp_dir = deterministic_temp_dir;
+        self
+    }
 
-            let handle = tokio::spawn(async move {
-                let (tx, mut rx) = tokio::sync::mpsc::channel(10);
-                // Setup debouncer. No specific tickrate, max debounce time 500 milliseconds
-                let mut debouncer = new_debouncer(Duration::from_millis(500), move |event| {
-                    if let Ok(e) = event {
-                        let _ = tx.blocking_send(e);
-                    }
-                })
-                .unwrap();
+    /// Check the size and hash of each `.sw` file copied into the temp directory by
+    /// [SyncWorkspace::clone_manifest_dir_to_temp] against its source, returning
+    /// [DirectoryError::CopyVerificationFailed] if they differ. Off by default, as it reads every
+    /// file twice.
+    pub fn with_verify_copies(mut self, verify_copies: bool) -> Self {
+        self.verify_copies = verify_copies;
+        self
+    }
 
-                debouncer
-                    .watcher()
-                    .watch(&manifest_dir, RecursiveMode::NonRecursive)
-                    .unwrap();
-                while let Some(_events) = rx.recv().await {
-                    // Rescan the Forc.toml and convert
-                    // relative paths to absolute. Save into our temp directory.
-                    if let Err(err) = edit_manifest_dependency_paths(
-                        &manifest_dir,
-                        &manifest_path,
-                        &temp_manifest_path,
-                    ) {
-                        error!("Failed to edit manifest dependency paths: {}", err);
-                    }
-                }
-            });
+    /// Only copy the directory holding the entry point of each package, i.e. its `src/`, along
+    /// with the manifests and lock files, instead of the whole manifest directory, e.g. to leave
+    /// scratch files out of the temp workspace. Off by default, which copies the whole directory.
+    ///
+    /// The source directories are read from the parsed manifest when the temp directory is created.
+    /// Workspace members outside of the workspace root are still copied whole, and so are the path
+    /// dependencies nested in a package, which are compiled from the workspace instead.
+    pub fn with_source_dirs_only(mut self, source_dirs_only: bool) -> Self {
+        self.source_dirs_only = source_dirs_only;
+        self
+    }
 
-            // Store the join handle so we can clean up the thread on shutdown
-            {
-                let mut join_handle = self.notify_join_handle.write();
-                *join_handle = Some(handle);
-            }
-        }
+    /// Debounce the events of the manifest and source watchers for `manifest_watch_debounce` instead
+    /// of [SyncWorkspace::DEFAULT_MANIFEST_WATCH_DEBOUNCE], e.g. longer on slow disks.
+    pub fn with_manifest_watch_debounce(mut self, manifest_watch_debounce: Duration) -> Self {
+        self.manifest_watch_debounce = manifest_watch_debounce;
+        self
     }
 
-    /// Return the path to the projects manifest directory.
-    pub(crate) fn manifest_dir(&self) -> Result<PathBuf, DirectoryError> {
-        self.directories
-            .try_get(&Directory::Manifest)
-            .try_unwrap()
-            .map(|item| item.value().clone())
-            .ok_or(DirectoryError::ManifestDirNotFound)
+    /// Coalesce sync requests for `resync_debounce` instead of [SyncWorkspace::DEFAULT_RESYNC_DEBOUNCE].
+    pub fn with_resync_debounce(mut self, resync_debounce: Duration) -> Self {
+        self.resync_debounce = resync_debounce;
+        self
     }
 
-    /// Return the path to the temporary directory that was created for the current session.
-    pub(crate) fn temp_dir(&self) -> Result<PathBuf, DirectoryError> {
-        self.directories
-            .try_get(&Directory::Temp)
-            .try_unwrap()
-            .map(|item| item.value().clone())
-            .ok_or(DirectoryError::TempDirNotFound)
+    /// Start in direct mode, see [SyncWorkspace::set_direct_mode].
+    pub fn with_direct_mode(self, direct_mode: bool) -> Self {
+        self.set_direct_mode(direct_mode);
+        self
     }
-}
 
-/// Check if the current path is part of the users workspace.
-/// Returns false if the path is from a dependency
-pub(crate) fn is_path_in_temp_workspace(uri: &Url) -> bool {
-    uri.as_ref().contains(SyncWorkspace::LSP_TEMP_PREFIX)
-}
+    /// Make the conversions of urls and spans between the workspace and the temp directory the
+    /// identity, so that the server compiles the manifest directory itself, e.g. for read-only
+    /// requests like go-to-definition that don't need the round trip through the temp workspace.
+    ///
+    /// The compiler then reads the workspace files as they are on disk, so it isn't isolated from
+    /// edits made to them while it runs, which the temp workspace otherwise protects it from.
+    pub fn set_direct_mode(&self, direct_mode: bool) {
+        self.direct_mode.store(direct_mode, Ordering::SeqCst);
+    }
 
-fn convert_url(uri: &Url, from: &Path, to: &PathBuf) -> Result<Url, DirectoryError> {
-    let path = from.join(
-        PathBuf::from(uri.path())
-            .strip_prefix(to)
-            .map_err(DirectoryError::StripPrefixError)?,
-    );
-    get_url_from_path(&path)
-}
+    /// Returns true if the conversions are the identity, see [SyncWorkspace::set_direct_mode].
+    pub fn direct_mode(&self) -> bool {
+        self.direct_mode.load(Ordering::SeqCst)
+    }
 
-/// Deserialize the manifest file and loop through the dependencies.
-/// Check if the dependency is specifying a 'path'.
-/// If so, check if the path is relative and convert the relative path to an absolute path.
-/// Edit the toml entry using toml_edit with the absolute path.
-/// Save the manifest to temp_dir/Forc.toml.
-pub(crate) fn edit_manifest_dependency_paths(
-    manifset_dir: &Path,
-    manifest_path: &Path,
-    temp_manifest_path: &Path,
-) -> Result<(), LanguageServerError> {
-    // Read and parse the original manifest
-    let manifest_content =
-        std::fs::read_to_string(manifest_path).map_err(|err| DocumentError::IOError {
-            path: manifest_path.to_string_lossy().to_string(),
-            error: err.to_string(),
-        })?;
+    /// Read the documents open in the editor from `document_store`, see
+    /// [SyncWorkspace::set_document_store].
+    pub fn with_document_store(self, document_store: Arc<dyn DocumentStore>) -> Self {
+        self.set_document_store(document_store);
+        self
+    }
 
-    let mut doc = manifest_content
-        .parse::<toml_edit::DocumentMut>()
-        .map_err(|err| DocumentError::IOError {
-            path: manifest_path.to_string_lossy().to_string(),
-            error: format!("Failed to parse TOML: {}", err),
-        })?;
+    /// Read the contents of the files from `document_store` when converting spans, if they are
+    /// open in it, instead of from disk. The unsaved edits of the editor's buffers are then taken
+    /// into account, e.g. by [SyncWorkspace::temp_to_workspace_spans].
+    pub fn set_document_store(&self, document_store: Arc<dyn DocumentStore>) {
+        *self.document_store.write() = Some(document_store);
+        self.workspace_sources.clear();
+    }
 
-    let manifest =
-        ManifestFile::from_file(manifest_path).map_err(|err| DocumentError::IOError {
-            path: manifest_path.to_string_lossy().to_string(),
-            error: err.to_string(),
-        })?;
+    /// Make the watchers drop the events they receive until [SyncWorkspace::resume_watcher] is
+    /// called, e.g. while the server performs bulk writes of its own.
+    ///
+    /// The events delivered while paused are dropped for good, so the changes made in the meantime
+    /// are never synced by the watchers. Callers have to [SyncWorkspace::resync] after
+    /// [SyncWorkspace::resume_watcher] if the workspace could have been edited externally.
+    pub fn pause_watcher(&self) {
+        self.watcher_paused.store(true, Ordering::SeqCst);
+    }
 
-    if let ManifestFile::Package(package) = manifest {
-        // Process dependencies if they exist
-        if let Some(deps) = &package.dependencies {
-            if let Some(deps_table) = doc.get_mut("dependencies").and_then(|v| v.as_table_mut()) {
-                process_dependencies(manifset_dir, deps, deps_table)?;
-            }
-        }
+    /// Make the watchers handle the events they receive again, see [SyncWorkspace::pause_watcher].
+    pub fn resume_watcher(&self) {
+        self.watcher_paused.store(false, Ordering::SeqCst);
     }
 
-    // Write the updated manifest to the temp file
-    std::fs::write(temp_manifest_path, doc.to_string()).map_err(|err| {
-        DocumentError::UnableToWriteFile {
-            path: temp_manifest_path.to_string_lossy().to_string(),
-            err: err.to_string(),
+    /// Returns true if the watchers drop their events, see [SyncWorkspace::pause_watcher].
+    pub fn is_watcher_paused(&self) -> bool {
+        self.watcher_paused.load(Ordering::SeqCst)
+    }
+
+    /// The options of the project's [ProjectConfig::FILE_NAME], or the default ones until the
+    /// temp directory is created.
+    pub fn project_config(&self) -> ProjectConfig {
+        self.project_config.read().clone()
+    }
+
+    /// `synced_files` along with the extensions of the [ProjectConfig].
+    fn effective_synced_files(&self) -> SyncedFiles {
+        let mut synced_files = self.synced_files.clone();
+        synced_files
+            .extensions
+            .extend(self.project_config.read().synced_extensions.iter().cloned());
+        synced_files
+    }
+
+    /// `excluded_patterns` followed by the ignore patterns of the [ProjectConfig].
+    fn configured_excluded_patterns(&self) -> Vec<String> {
+        let project_config = self.project_config.read();
+        self.excluded_patterns
+            .iter()
+            .chain(&project_config.ignore)
+            .cloned()
+            .collect()
+    }
+
+    /// The [SyncWorkspace::configured_excluded_patterns] followed by those leaving out everything
+    /// but the source directories if [SyncWorkspace::source_dirs_only] is set.
+    fn effective_excluded_patterns(&self) -> Vec<String> {
+        let patterns = self.configured_excluded_patterns();
+        match self.manifest_dir() {
+            Ok(manifest_dir) => with_source_dir_patterns(
+                patterns,
+                self.source_dirs_only,
+                self.manifest.read().as_ref(),
+                &manifest_dir,
+            ),
+            Err(_) => patterns,
         }
-    })?;
+    }
 
-    Ok(())
-}
+    fn effective_resync_debounce(&self) -> Duration {
+        self.project_config
+            .read()
+            .resync_debounce_ms
+            .map_or(self.resync_debounce, Duration::from_millis)
+    }
 
-/// Process dependencies and convert relative paths to absolute
-fn process_dependencies(
-    manifest_dir: &Path,
-    deps: &std::collections::BTreeMap<String, forc_pkg::manifest::Dependency>,
-    deps_table: &mut toml_edit::Table,
-) -> Result<(), LanguageServerError> {
-    for (name, dependency) in deps {
-        if let forc_pkg::manifest::Dependency::Detailed(details) = dependency {
-            if let Some(rel_path) = &details.path {
-                // Convert relative path to absolute
-                let abs_path = manifest_dir
-                    .join(rel_path)
-                    .canonicalize()
-                    .map_err(|_| DirectoryError::CanonicalizeFailed)?
-                    .to_string_lossy()
-                    .to_string();
+    fn effective_manifest_watch_debounce(&self) -> Duration {
+        self.project_config
+            .read()
+            .manifest_watch_debounce_ms
+            .map_or(self.manifest_watch_debounce, Duration::from_millis)
+    }
 
-                // Update the path in the TOML document
-                if let Some(dep_item) = deps_table.get_mut(name) {
-                    let path_value = toml_edit::Value::from(abs_path);
-                    if let Some(table) = dep_item.as_inline_table_mut() {
-                        table.insert("path", path_value);
-                    }
-                }
-            }
+    /// Bring the contents of the tmp/folder up to date with
+    /// the current workspace.
+    pub fn resync(&self) -> Result<(), LanguageServerError> {
+        self.resync_changed().map(|_| ())
+    }
+
+    /// Wait for `resync_debounce`, then [SyncWorkspace::resync] unless this was called again
+    /// in the meantime. A burst of requests, e.g. from rapid saves, is then coalesced into
+    /// a single resync by the latest request.
+    ///
+    /// Returns false if the resync was left to a later request.
+    pub async fn resync_debounced(&self) -> Result<bool, LanguageServerError> {
+        let request = self.resync_requests.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::sleep(self.effective_resync_debounce()).await;
+        if self.resync_requests.load(Ordering::SeqCst) != request {
+            return Ok(false);
         }
+        self.resync()?;
+        Ok(true)
     }
-    Ok(())
-}
 
-/// Copies only the specified files from the source directory to the target directory.
-/// This function targets files ending with `.sw`, and the specific files `Forc.toml` and `Forc.lock`.
-/// It returns `Ok(true)` if any relevant files were copied over, and `Ok(false)` if no such files were found.
-fn copy_dir_contents(
-    src_dir: impl AsRef<Path>,
-    target_dir: impl AsRef<Path>,
-) -> std::io::Result<bool> {
-    let mut has_relevant_files = false;
-    for entry in fs::read_dir(&src_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let ty = entry.file_type()?;
-        if ty.is_dir() {
-            // Recursively check the directory; if it has relevant files, create the target directory
-            if copy_dir_contents(&path, target_dir.as_ref().join(entry.file_name()))? {
-                has_relevant_files = true;
+    /// Incrementally sync the tmp/folder with the current workspace, only copying the files
+    /// that changed since the last sync and removing the ones that were deleted.
+    ///
+    /// Returns the paths in the temp directory that were copied or removed, so that the server
+    /// can invalidate only the affected modules.
+    ///
+    /// Once synced, the temp `Forc.lock` is checked against the dependencies of the temp manifests
+    /// and [LanguageServerError::LockFileOutOfDate] is returned if they disagree.
+    pub fn resync_changed(&self) -> Result<Vec<PathBuf>, LanguageServerError> {
+        self.resync_job()?.run(None)
+    }
+
+    /// Run [SyncWorkspace::resync] on a blocking task, e.g. to cancel it once a newer resync
+    /// supersedes it during a burst of edits. It has to be called within a tokio runtime.
+    ///
+    /// `cancel` is checked before each file is copied, and the resync then fails with
+    /// [LanguageServerError::ResyncCancelled]. The temp directory is left consistent: each
+    /// file is either synced or left as it was, and the manifests copied before the cancellation
+    /// still have their dependency paths rewritten.
+    pub fn resync_async(
+        &self,
+        cancel: CancellationToken,
+    ) -> JoinHandle<Result<(), LanguageServerError>> {
+        let job = self.resync_job();
+        tokio::task::spawn_blocking(move || job?.run(Some(&cancel)).map(|_| ()))
+    }
+
+    /// Detach the state of a resync from the workspace, see [ResyncJob].
+    fn resync_job(&self) -> Result<ResyncJob, LanguageServerError> {
+        Ok(ResyncJob {
+            tracker: self.sync_tracker(),
+            copy_file_system: self.copy_file_system(self.file_system.clone()),
+            manifest_file_system: self.manifest_file_system(),
+            copied_dirs: self.copied_package_dirs()?,
+            package_dirs: self.package_dirs(),
+            ignore: self.ignore_patterns.read().clone(),
+            synced_files: self.effective_synced_files(),
+            dependency_root: self.dependency_root.clone(),
+            temp_dir: self.temp_dir()?,
+            last_sync: self.last_sync.clone(),
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// Work out what [SyncWorkspace::resync] would do without touching the filesystem.
+    ///
+    /// Dependency paths that fail to canonicalize are reported as warnings instead of being logged.
+    pub fn resync_plan(&self) -> Result<ResyncPlan, LanguageServerError> {
+        let mut plan = ResyncPlan::default();
+        let ignore = self.ignore_patterns.read();
+        let synced_files = self.effective_synced_files();
+        let copy_file_system = self.copy_file_system(self.file_system.clone());
+        for dirs in self.copied_package_dirs()? {
+            plan.changed_files.extend(sync_dir_contents(
+                &*copy_file_system,
+                &dirs.manifest_dir,
+                &dirs.temp_dir,
+                &ignore,
+                &synced_files,
+                true,
+            )?);
+        }
+        let manifest_dirs = copied_manifest_dirs(&*self.file_system, &self.package_dirs());
+        for dirs in &manifest_dirs {
+            let doc = manifest_with_temp_dependency_paths(
+                &*self.manifest_file_system(),
+                &dirs.manifest_dir,
+                &dirs.manifest_dir.join(MANIFEST_FILE_NAME),
+                &manifest_dirs,
+                Some(&mut plan.warnings),
+                self.dependency_root.as_deref(),
+            )?;
+            let temp_manifest_path = dirs.temp_dir.join(MANIFEST_FILE_NAME);
+            if self.file_system.read_to_string(&temp_manifest_path).ok() != Some(doc.to_string()) {
+                plan.rewritten_manifests.push(temp_manifest_path);
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Clean up the temp directory that was created once the
+    /// server closes down.
+    pub(crate) fn remove_temp_dir(&self) {
+        for root in self.roots.iter() {
+            root.remove_temp_dir();
+        }
+        if let Ok(temp_root) = self.temp_root() {
+            self.remove_owned_temp_root(&temp_root);
+        }
+    }
+
+    /// Remove `temp_root`, leaving the temp directories of the registered roots alone.
+    fn remove_owned_temp_root(&self, temp_root: &Path) {
+        // Only remove the directories created by `create_temp_dir_from_workspace`, and not
+        // the parent of a temp directory given to `with_directories`.
+        if !self.owns_temp_root(temp_root) {
+            return;
+        }
+        if let Err(err) = self.file_system.remove_dir_all(temp_root) {
+            error!("Failed to remove temp directory {:?}: {}", temp_root, err);
+        }
+    }
+
+    /// Returns true if `temp_root` was created by [SyncWorkspace::create_temp_dir_from_workspace].
+    fn owns_temp_root(&self, temp_root: &Path) -> bool {
+        let prefix = format!("{}_{}_", SyncWorkspace::LSP_TEMP_PREFIX, self.session_id);
+        let Some(name) = temp_root.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy();
+        name.starts_with(&prefix)
+            || (self.deterministic_temp_dir
+                && self
+                    .manifest_dir()
+                    .is_ok_and(|dir| name == deterministic_temp_root_name(&dir)))
+    }
+
+    /// Return the number of successful [SyncWorkspace::clone_manifest_dir_to_temp] and
+    /// [SyncWorkspace::resync] calls, for caches of the temp files to know when to invalidate.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Return the [SyncWorkspace::generation], and whether any sync is running.
+    pub fn sync_generation(&self) -> SyncGeneration {
+        let running = self.running_syncs.lock();
+        SyncGeneration {
+            generation: self.generation(),
+            syncing: *running > 0,
+        }
+    }
+
+    /// Return the manifest of the manifest directory, as parsed by
+    /// [SyncWorkspace::create_temp_dir_from_workspace] or since reloaded by
+    /// [SyncWorkspace::reload_manifest].
+    pub fn manifest(&self) -> Option<ManifestFile> {
+        self.manifest.read().clone()
+    }
+
+    /// Parse the manifest of the manifest directory again, e.g. after the user edited `Forc.toml`,
+    /// replacing the cached [SyncWorkspace::manifest], and rewrite the temp manifests with it.
+    /// A manifest with unsaved changes is parsed from its overlay, see
+    /// [SyncWorkspace::set_manifest_overlay], and the source directories left out of the temp
+    /// workspace by [SyncWorkspace::source_dirs_only] follow the reloaded manifest.
+    ///
+    /// The manifest watcher reloads the manifest whenever it changes.
+    pub fn reload_manifest(&self) -> Result<(), LanguageServerError> {
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        reload_manifest(
+            &self.manifest,
+            &SourceDirsIgnore {
+                ignore: &self.ignore_patterns,
+                excluded_patterns: &self.configured_excluded_patterns(),
+                source_dirs_only: self.source_dirs_only,
+            },
+            &*self.manifest_file_system(),
+            &self.package_dirs(),
+            self.dependency_root.as_deref(),
+        )
+    }
+
+    fn sync_tracker(&self) -> SyncTracker {
+        let file = self
+            .temp_root()
+            .ok()
+            .filter(|temp_root| self.owns_temp_root(temp_root))
+            .map(|temp_root| temp_root.join(Self::SYNC_GENERATION_FILE_NAME));
+        SyncTracker {
+            generation: self.generation.clone(),
+            running: self.running_syncs.clone(),
+            file_system: self.file_system.clone(),
+            file,
+        }
+    }
+
+    pub(crate) fn create_temp_dir_from_workspace(
+        &self,
+        manifest_dir: &Path,
+    ) -> Result<(), LanguageServerError> {
+        let _p = tracing::debug_span!("create_temp_dir_from_workspace", ?manifest_dir).entered();
+        let start = Instant::now();
+        let manifest = read_project_manifest(manifest_dir)?;
+
+        // strip Forc.toml from the path to get the manifest directory. It is canonicalized as the
+        // client sends canonical paths, which have to be found under it when converting urls.
+        let manifest_dir = manifest
+            .path()
+            .parent()
+            .ok_or(DirectoryError::ManifestDirNotFound)?
+            .canonicalize()
+            .map_err(|_| DirectoryError::CanonicalizeFailed)?;
+        let manifest_dir = manifest_dir.as_path();
+
+        // extract the project name from the path
+        let project_name = manifest_dir.file_name().map(temp_project_name).ok_or(
+            DirectoryError::CantExtractProjectName {
+                dir: manifest_dir.to_string_lossy().to_string(),
+            },
+        )?;
+
+        let project_config = ProjectConfig::from_dir(&*self.file_system, manifest_dir);
+        let base_dir = project_config
+            .temp_root
+            .as_ref()
+            .map(|temp_root| manifest_dir.join(temp_root))
+            .or_else(|| self.temp_root.clone())
+            .unwrap_or_else(std::env::temp_dir);
+        *self.project_config.write() = project_config;
+
+        // Create a new temporary directory that we can clone the current workspace into.
+        let temp_root = if self.deterministic_temp_dir {
+            create_deterministic_temp_root(
+                &*self.file_system,
+                &base_dir,
+                &deterministic_temp_root_name(manifest_dir),
+            )
+        } else {
+            let prefix = format!("{}_{}_", SyncWorkspace::LSP_TEMP_PREFIX, self.session_id);
+            let mut builder = Builder::new();
+            builder.prefix(&prefix);
+            builder.tempdir_in(&base_dir).map(TempDir::into_path)
+        };
+        let temp_root = match temp_root {
+            Ok(temp_root) => temp_root,
+            Err(err) if self.temp_dir_fallback => {
+                warn!(
+                    "Failed to create temp directory in {:?}, operating on {:?} in place: {}",
+                    base_dir, manifest_dir, err
+                );
+                self.use_manifest_dir_in_place(&manifest, manifest_dir)?;
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(DirectoryError::TempDirFailed {
+                    path: base_dir.to_string_lossy().to_string(),
+                    err: err.to_string(),
+                }
+                .into())
+            }
+        };
+
+        let temp_root = temp_root
+            .canonicalize()
+            .map_err(|_| DirectoryError::CanonicalizeFailed)?;
+        let temp_path = temp_root.join(project_name);
+
+        // A workspace root gets one temp subtree per member package. Members living under the
+        // workspace root keep their relative location, others are placed next to the project.
+        if let ManifestFile::Workspace(workspace) = &manifest {
+            let members =
+                workspace
+                    .member_manifests()
+                    .map_err(|_| DocumentError::MemberManifestsFailed {
+                        dir: manifest_dir.to_string_lossy().to_string(),
+                    })?;
+            for (name, member) in members {
+                let member_dir = member
+                    .dir()
+                    .canonicalize()
+                    .map_err(|_| DirectoryError::CanonicalizeFailed)?;
+                let member_temp_dir = match member_dir.strip_prefix(manifest_dir) {
+                    Ok(relative) => temp_path.join(relative),
+                    Err(_) => temp_root.join(&name),
+                };
+                self.insert_directory(Directory::MemberManifest(name.clone()), member_dir);
+                self.insert_directory(Directory::MemberTemp(name), member_temp_dir);
+            }
+        }
+
+        debug!(temp_dir = ?temp_path, elapsed = ?start.elapsed(), "Created temp directory");
+        self.insert_directory(Directory::Manifest, manifest_dir.to_path_buf());
+        self.insert_directory(Directory::Temp, temp_path);
+        *self.manifest.write() = Some(manifest);
+        *self.ignore_patterns.write() = ignore_patterns(
+            &*self.file_system,
+            manifest_dir,
+            &self.effective_excluded_patterns(),
+        );
+
+        Ok(())
+    }
+
+    /// Use the workspace directories as their own temp directories, for when the temp directory
+    /// can't be created and [SyncWorkspace::temp_dir_fallback] is set. Nothing is then copied and
+    /// the manifests aren't rewritten, so the server compiles the workspace as it is on disk.
+    fn use_manifest_dir_in_place(
+        &self,
+        manifest: &ManifestFile,
+        manifest_dir: &Path,
+    ) -> Result<(), LanguageServerError> {
+        if let ManifestFile::Workspace(workspace) = manifest {
+            let members =
+                workspace
+                    .member_manifests()
+                    .map_err(|_| DocumentError::MemberManifestsFailed {
+                        dir: manifest_dir.to_string_lossy().to_string(),
+                    })?;
+            for (name, member) in members {
+                let member_dir = member
+                    .dir()
+                    .canonicalize()
+                    .map_err(|_| DirectoryError::CanonicalizeFailed)?;
+                self.insert_directory(Directory::MemberManifest(name.clone()), member_dir.clone());
+                self.insert_directory(Directory::MemberTemp(name), member_dir);
+            }
+        }
+        self.insert_directory(Directory::Manifest, manifest_dir.to_path_buf());
+        self.insert_directory(Directory::Temp, manifest_dir.to_path_buf());
+        *self.manifest.write() = Some(manifest.clone());
+        *self.ignore_patterns.write() = ignore_patterns(
+            &*self.file_system,
+            manifest_dir,
+            &self.effective_excluded_patterns(),
+        );
+        Ok(())
+    }
+
+    /// Sync the project in `manifest_dir` into a temp directory of its own along with this one,
+    /// e.g. for an editor window spanning unrelated projects that aren't members of a Forc
+    /// workspace. The urls of its files are then converted by [SyncWorkspace::workspace_to_temp_url]
+    /// and [SyncWorkspace::temp_to_workspace_url] through its own directories.
+    ///
+    /// The project is synced with the synced files, excluded patterns, dependency root and copy
+    /// options of this workspace, and its temp directory is removed along with this one. It is kept up to date through the returned workspace, e.g.
+    /// with [SyncWorkspace::resync]. Registering a project again returns the same workspace.
+    pub fn register_root(
+        &self,
+        manifest_dir: &Path,
+    ) -> Result<Arc<SyncWorkspace>, LanguageServerError> {
+        let mut root = SyncWorkspace::with_temp_root(self.temp_root.clone())
+            .with_file_system(self.file_system.clone())
+            .with_synced_files(self.synced_files.clone())
+            .with_excluded_patterns(self.excluded_patterns.clone())
+            .with_normalize_line_endings(self.normalize_line_endings)
+            .with_verify_copies(self.verify_copies)
+            .with_source_dirs_only(self.source_dirs_only);
+        if let Some(dependency_root) = &self.dependency_root {
+            root = root.with_dependency_root(dependency_root.clone());
+        }
+        root.create_temp_dir_from_workspace(manifest_dir)?;
+        let root_dir = root.manifest_dir()?;
+        if let Some(existing) = self.roots.get(&root_dir) {
+            root.remove_temp_dir();
+            return Ok(existing.clone());
+        }
+        root.clone_manifest_dir_to_temp()?;
+        edit_manifests_dependency_paths(
+            &*root.manifest_file_system(),
+            &root.package_dirs(),
+            self.dependency_root.as_deref(),
+        )?;
+        let root = Arc::new(root);
+        self.roots.insert(root_dir, root.clone());
+        Ok(root)
+    }
+
+    /// Stop syncing the project registered with [SyncWorkspace::register_root] in `manifest_dir`
+    /// and remove its temp directory. Returns false if no project was registered there.
+    pub fn unregister_root(&self, manifest_dir: &Path) -> bool {
+        let manifest_dir = manifest_dir
+            .canonicalize()
+            .unwrap_or_else(|_| manifest_dir.to_path_buf());
+        match self.roots.remove(&manifest_dir) {
+            Some((_, root)) => {
+                root.remove_temp_dir();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the manifest directories of the projects registered with [SyncWorkspace::register_root].
+    pub fn root_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<_> = self.roots.iter().map(|root| root.key().clone()).collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// Return the registered root whose project contains `path`, the innermost one if they nest.
+    fn root_containing(&self, path: &Path) -> Option<Arc<SyncWorkspace>> {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(root.key()))
+            .max_by_key(|root| root.key().components().count())
+            .map(|root| root.value().clone())
+    }
+
+    /// Returns true if `path` is in the project of this workspace, or one of its members.
+    fn contains_workspace_path(&self, path: &Path) -> bool {
+        self.manifest_dir()
+            .is_ok_and(|manifest_dir| path.starts_with(manifest_dir))
+            || self
+                .member_containing(path, |dirs| &dirs.manifest_dir)
+                .is_some()
+    }
+
+    /// Returns true if the workspace is used as its own temp directory, see
+    /// [SyncWorkspace::temp_dir_fallback].
+    pub fn is_in_place(&self) -> bool {
+        let directories = self.cached_directories.load();
+        directories.manifest_dir.is_some() && directories.manifest_dir == directories.temp_dir
+    }
+
+    /// Return the sorted paths of the `.sw` files of the workspace, in workspace coordinates.
+    /// The directories are walked like when copying them into the temp directory, skipping
+    /// the ignored paths, but nothing is copied.
+    pub fn source_files(&self) -> Result<Vec<PathBuf>, DirectoryError> {
+        let ignore = self.ignore_patterns.read();
+        let mut source_files = BTreeSet::new();
+        let mut visited = HashSet::new();
+        for dirs in self.copied_package_dirs()? {
+            collect_source_files(
+                &*self.file_system,
+                &dirs.manifest_dir,
+                &ignore,
+                &mut visited,
+                &mut source_files,
+            )?;
+        }
+        Ok(source_files.into_iter().collect())
+    }
+
+    /// Copy the relevant files of the workspace into the temp directory.
+    /// Returns the temp paths of the files that were copied.
+    pub(crate) fn clone_manifest_dir_to_temp(&self) -> Result<Vec<PathBuf>, DirectoryError> {
+        let manifest_dir = self.manifest_dir().ok();
+        let _p = tracing::debug_span!("clone_manifest_dir_to_temp", ?manifest_dir).entered();
+        let start = Instant::now();
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        let ignore = self.ignore_patterns.read();
+        let synced_files = self.effective_synced_files();
+        let mut copied_files = Vec::new();
+        let copy_file_system =
+            MeteredFileSystem::new(self.copy_file_system(self.file_system.clone()));
+        for dirs in self.copied_package_dirs()? {
+            copied_files.extend(copy_dir_contents(
+                &copy_file_system,
+                &dirs.manifest_dir,
+                &dirs.temp_dir,
+                &ignore,
+                &synced_files,
+                self.verify_copies,
+            )?);
+        }
+        debug!(
+            file_count = copied_files.len(),
+            elapsed = ?start.elapsed(),
+            "Cloned workspace into temp directory"
+        );
+        *self.last_sync.write() = Some(Instant::now());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.initial_sync.send_replace(true);
+        let _ = self
+            .metrics
+            .send(ResyncMetrics::new(&copy_file_system, start));
+
+        Ok(copied_files)
+    }
+
+    /// Wait until the workspace was first copied into the temp directory by
+    /// [SyncWorkspace::clone_manifest_dir_to_temp]. Returns immediately if it already was.
+    pub async fn wait_for_initial_sync(&self) {
+        let mut receiver = self.initial_sync.subscribe();
+        // The sender is owned by `self`, so it can't be dropped while waiting.
+        let _ = receiver.wait_for(|synced| *synced).await;
+    }
+
+    /// Recreate the temp directory if it was deleted externally, e.g. by a temp directory cleaner,
+    /// by copying the workspace into it again and rewriting the manifests' dependency paths.
+    /// This should be called before compiling.
+    ///
+    /// Returns true if the temp directory had to be recreated.
+    pub(crate) fn ensure_temp_dir(&self) -> Result<bool, LanguageServerError> {
+        let temp_dir = self.temp_dir()?;
+        if entry_kind(&*self.file_system, &temp_dir).is_some() {
+            return Ok(false);
+        }
+        warn!(
+            "Temp directory {:?} was deleted, recreating it from the workspace",
+            temp_dir
+        );
+        let tracker = self.sync_tracker();
+        let _sync = tracker.begin();
+        self.clone_manifest_dir_to_temp()?;
+        edit_manifests_dependency_paths(
+            &*self.manifest_file_system(),
+            &self.package_dirs(),
+            self.dependency_root.as_deref(),
+        )?;
+        Ok(true)
+    }
+
+    /// Throw the temp directory away and rebuild it from scratch, as opposed to the incremental
+    /// [SyncWorkspace::resync]. This is an escape hatch for when the temp directory got into a bad state.
+    ///
+    /// The workspace members are rediscovered, and the watchers that were running are restarted
+    /// to watch the new temp directory. The new temp directory is built before the old one is
+    /// removed, and if that fails the workspace is left with its old directories. The temp
+    /// directories of the roots registered with [SyncWorkspace::register_root] are left alone.
+    pub fn hard_resync(&self) -> Result<(), LanguageServerError> {
+        let manifest_dir = self.manifest_dir()?;
+        let watching_manifest = self.notify_join_handle.read().is_some();
+        let source_changes = self.source_changes.read().clone();
+        self.stop_watchers();
+
+        let old_temp_root = self.temp_root().ok();
+        let old_directories: Vec<_> = self
+            .directories
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        let old_manifest = self.manifest();
+        let old_ignore = self.ignore_patterns.read().clone();
+        let old_project_config = self.project_config.read().clone();
+        self.directories.clear();
+        self.refresh_cached_directories();
+        let rebuilt = self
+            .create_temp_dir_from_workspace(&manifest_dir)
+            .and_then(|()| {
+                let tracker = self.sync_tracker();
+                let _sync = tracker.begin();
+                self.clone_manifest_dir_to_temp()?;
+                edit_manifests_dependency_paths(
+                    &*self.manifest_file_system(),
+                    &self.package_dirs(),
+                    self.dependency_root.as_deref(),
+                )
+            });
+        let new_temp_root = self.temp_root().ok();
+        match rebuilt {
+            Ok(()) => {
+                // A deterministic temp root was rebuilt in place.
+                if let Some(old_temp_root) =
+                    old_temp_root.filter(|old| Some(old) != new_temp_root.as_ref())
+                {
+                    self.remove_owned_temp_root(&old_temp_root);
+                }
             }
-        } else if let Some(file_name_os) = path.file_name() {
-            if let Some(file_name) = file_name_os.to_str() {
-                if file_name.ends_with(&format!(".{SWAY_EXTENSION}"))
-                    || file_name == MANIFEST_FILE_NAME
-                    || file_name == LOCK_FILE_NAME
+            Err(err) => {
+                if let Some(new_temp_root) =
+                    new_temp_root.filter(|new| Some(new) != old_temp_root.as_ref())
                 {
-                    if !has_relevant_files {
-                        fs::create_dir_all(&target_dir)?;
-                        has_relevant_files = true;
+                    self.remove_owned_temp_root(&new_temp_root);
+                }
+                self.directories.clear();
+                for (directory, path) in old_directories {
+                    self.directories.insert(directory, path);
+                }
+                self.refresh_cached_directories();
+                *self.manifest.write() = old_manifest;
+                *self.ignore_patterns.write() = old_ignore;
+                *self.project_config.write() = old_project_config;
+                self.restart_watchers(watching_manifest, source_changes)?;
+                return Err(err);
+            }
+        }
+        self.restart_watchers(watching_manifest, source_changes)
+    }
+
+    /// Start the watchers [SyncWorkspace::hard_resync] stopped again.
+    fn restart_watchers(
+        &self,
+        watching_manifest: bool,
+        source_changes: Option<UnboundedSender<Vec<PathBuf>>>,
+    ) -> Result<(), LanguageServerError> {
+        if watching_manifest {
+            self.watch_and_sync_manifest()?;
+        }
+        if let Some(changes) = source_changes {
+            self.watch_and_sync_sources(changes)?;
+        }
+        Ok(())
+    }
+
+    /// Return the directories that have to be copied to mirror the workspace in the temp directory.
+    /// This is the root directory, plus any workspace members living outside of it.
+    fn copied_package_dirs(&self) -> Result<Vec<PackageDirs>, DirectoryError> {
+        let manifest_dir = self.manifest_dir()?;
+        let mut package_dirs = vec![PackageDirs {
+            manifest_dir: manifest_dir.clone(),
+            temp_dir: self.temp_dir()?,
+        }];
+        package_dirs.extend(
+            self.member_package_dirs()
+                .into_iter()
+                .filter(|dirs| !dirs.manifest_dir.starts_with(&manifest_dir)),
+        );
+        Ok(package_dirs)
+    }
+
+    /// Convert the Url path from the client to point to the same file in our temp folder
+    ///
+    /// Directory urls are converted as well, and keep their trailing slash if they have one.
+    ///
+    /// Once projects are registered with [SyncWorkspace::register_root], urls in one of them are
+    /// converted through its directories, and urls in none of the projects fail with
+    /// [DirectoryError::NoRootForUrl].
+    ///
+    /// In [SyncWorkspace::direct_mode], the url is returned as-is.
+    pub(crate) fn workspace_to_temp_url(&self, uri: &Url) -> Result<Url, DirectoryError> {
+        if self.direct_mode() {
+            return Ok(uri.clone());
+        }
+        if !self.roots.is_empty() {
+            let path = get_path_from_url(uri)?;
+            if !self.contains_workspace_path(&path) {
+                return match self.root_containing(&path) {
+                    Some(root) => root.workspace_to_temp_url(uri),
+                    None => Err(DirectoryError::NoRootForUrl {
+                        url: uri.to_string(),
+                    }),
+                };
+            }
+        }
+        // The url path is percent-encoded, so it is decoded to find the member containing it.
+        match self.member_containing(&get_path_from_url(uri)?, |dirs| &dirs.manifest_dir) {
+            Some(dirs) => convert_url(uri, &dirs.temp_dir, &dirs.manifest_dir),
+            None => convert_url(uri, &self.temp_dir()?, &self.manifest_dir()?),
+        }
+    }
+
+    /// Convert the [Url] path from the temp folder to point to the same file in the users workspace.
+    ///
+    /// Urls into a stale temp folder, e.g. one thrown away by [SyncWorkspace::hard_resync] while the
+    /// client still holds its urls, are converted as if they pointed into the current one.
+    /// Directory urls keep their trailing slash, like in [SyncWorkspace::workspace_to_temp_url].
+    /// In [SyncWorkspace::direct_mode], the url is returned as-is.
+    pub(crate) fn temp_to_workspace_url(&self, uri: &Url) -> Result<Url, DirectoryError> {
+        if self.direct_mode() {
+            return Ok(uri.clone());
+        }
+        if let Some(root) = self
+            .roots
+            .iter()
+            .find(|root| root.owns_temp_url(uri))
+            .map(|root| root.value().clone())
+        {
+            return root.temp_to_workspace_url(uri);
+        }
+        match self.current_temp_to_workspace_url(uri) {
+            Err(err @ DirectoryError::StripPrefixError { .. }) => {
+                let Some(rebased) = get_path_from_url(uri)
+                    .ok()
+                    .and_then(|path| self.rebase_stale_temp_path(&path))
+                else {
+                    return Err(err);
+                };
+                self.current_temp_to_workspace_url(&url_like(uri, &rebased)?)
+            }
+            result => result,
+        }
+    }
+
+    fn current_temp_to_workspace_url(&self, uri: &Url) -> Result<Url, DirectoryError> {
+        match self.member_containing(&get_path_from_url(uri)?, |dirs| &dirs.temp_dir) {
+            Some(dirs) => convert_url(uri, &dirs.manifest_dir, &dirs.temp_dir),
+            None => convert_url(uri, &self.manifest_dir()?, &self.temp_dir()?),
+        }
+    }
+
+    /// Replace the `LSP_TEMP_PREFIX` directory `path` is in with the current temp root, keeping
+    /// the project subdirectory and the rest of the path. Returns `None` if `path` isn't in such a
+    /// directory, or is in the current one already.
+    fn rebase_stale_temp_path(&self, path: &Path) -> Option<PathBuf> {
+        let temp_root = self.temp_root().ok()?;
+        if path.starts_with(&temp_root) {
+            return None;
+        }
+        let stale_root = path.ancestors().find(|ancestor| {
+            ancestor
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(Self::LSP_TEMP_PREFIX))
+        })?;
+        Some(temp_root.join(path.strip_prefix(stale_root).ok()?))
+    }
+
+    /// If it is a path to a temp directory, convert the path in the [Span] to the same file in the user's
+    /// workspace. Otherwise, return the span as-is.
+    pub(crate) fn temp_to_workspace_span(
+        &self,
+        source_engine: &SourceEngine,
+        span: &Span,
+    ) -> Result<Span, DirectoryError> {
+        let mut spans = self.temp_to_workspace_spans(source_engine, std::slice::from_ref(span))?;
+        Ok(spans.remove(0))
+    }
+
+    /// Convert the path in the [Span] from the user's workspace to the same file in the temp directory.
+    /// If it is already a path to the temp directory, or in [SyncWorkspace::direct_mode], return
+    /// the span as-is.
+    pub fn workspace_to_temp_span(
+        &self,
+        source_engine: &SourceEngine,
+        span: &Span,
+    ) -> Result<Span, DirectoryError> {
+        if self.direct_mode() {
+            return Ok(span.clone());
+        }
+        let url = get_url_from_span(source_engine, span)?;
+        if self.is_path_in_temp_workspace(&url) {
+            return Ok(span.clone());
+        }
+        let converted_url = self.workspace_to_temp_url(&url)?;
+        let converted_path = get_path_from_url(&converted_url)?;
+        let source_id = source_engine.get_source_id(&converted_path);
+        let text = span.src().text.as_ref();
+        let converted_span = if self.normalize_line_endings
+            && converted_path.extension() == Some(OsStr::new(SWAY_EXTENSION))
+            && text.contains("\r\n")
+        {
+            // The temp copy has no CRs, so the offsets are moved back by those before them.
+            let offset = |position: usize| {
+                let before = &text[..position];
+                let split = before.ends_with('\r') && text[position..].starts_with('\n');
+                position - before.matches("\r\n").count() - usize::from(split)
+            };
+            Span::new(
+                normalize_line_endings(text).as_str().into(),
+                offset(span.start()),
+                offset(span.end()),
+                Some(source_id),
+            )
+        } else {
+            Span::new(
+                span.src().clone(),
+                span.start(),
+                span.end(),
+                Some(source_id),
+            )
+        };
+        converted_span.ok_or_else(|| DirectoryError::SpanFromPathFailed {
+            path: converted_path.to_string_lossy().to_string(),
+        })
+    }
+
+    /// Convert each of the [Span]s the same way as [SyncWorkspace::temp_to_workspace_span].
+    ///
+    /// The directories are only resolved once and each source is only converted once,
+    /// which makes this cheaper than converting the spans one by one.
+    ///
+    /// The offsets are only reused if the workspace file is identical to the text the span is in.
+    /// If they only differ by their line endings or trailing newlines, the span is moved to the same
+    /// lines and columns of the workspace file, so that its LSP range stays the same. Any other
+    /// difference means that the span is in newer contents than the workspace file, e.g. unsaved
+    /// edits, in which case its offsets and text are kept.
+    ///
+    /// The comparison of each workspace file is cached for as long as its spans are in the same
+    /// temp text, i.e. until the file is compiled again.
+    pub(crate) fn temp_to_workspace_spans(
+        &self,
+        source_engine: &SourceEngine,
+        spans: &[Span],
+    ) -> Result<Vec<Span>, DirectoryError> {
+        if self.direct_mode() {
+            return Ok(spans.to_vec());
+        }
+        let root = PackageDirs {
+            manifest_dir: self.manifest_dir()?,
+            temp_dir: self.temp_dir()?,
+        };
+        let temp_root = self.temp_root()?;
+        let members = self.member_package_dirs();
+        let mut converted_sources: HashMap<SourceId, Option<(SourceId, Option<Source>)>> =
+            HashMap::new();
+        spans
+            .iter()
+            .map(|span| {
+                let source_id =
+                    span.source_id()
+                        .ok_or_else(|| DirectoryError::UrlFromSpanFailed {
+                            span: span.as_str().to_string(),
+                        })?;
+                let converted_source = match converted_sources.get(source_id) {
+                    Some(converted_source) => converted_source.clone(),
+                    None => {
+                        let converted_source = temp_to_workspace_source_id(
+                            source_engine,
+                            source_id,
+                            &temp_root,
+                            &root,
+                            &members,
+                        )?
+                        .map(|converted_source_id| {
+                            let workspace_src = self.differing_workspace_source(
+                                source_engine,
+                                source_id,
+                                &converted_source_id,
+                                span.src(),
+                            );
+                            (converted_source_id, workspace_src)
+                        });
+                        converted_sources.insert(*source_id, converted_source.clone());
+                        converted_source
+                    }
+                };
+                let Some((converted_source_id, workspace_src)) = converted_source else {
+                    return Ok(span.clone());
+                };
+                let converted_span = match workspace_src {
+                    Some(workspace_src) => {
+                        let offset = |position: usize| {
+                            offset_at_line_col(
+                                &workspace_src,
+                                span.src().line_col_zero_index(position),
+                            )
+                        };
+                        let (start, end) = (offset(span.start()), offset(span.end()));
+                        Span::new(workspace_src, start, end, Some(converted_source_id))
+                    }
+                    None => Span::new(
+                        span.src().clone(),
+                        span.start(),
+                        span.end(),
+                        Some(converted_source_id),
+                    ),
+                };
+                converted_span.ok_or_else(|| DirectoryError::SpanFromPathFailed {
+                    path: source_engine
+                        .get_path(&converted_source_id)
+                        .to_string_lossy()
+                        .to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Return the contents of the workspace file `source_id` if they only differ from `temp_src`, the
+    /// text the span of its temp copy `temp_source_id` is in, by their line endings or trailing
+    /// newlines. Returns `None` if they are identical, differ otherwise, or can't be read.
+    ///
+    /// The contents of the document open in the [SyncWorkspace::set_document_store], under either
+    /// path, are preferred over the file on disk, which doesn't have the unsaved edits.
+    fn differing_workspace_source(
+        &self,
+        source_engine: &SourceEngine,
+        temp_source_id: &SourceId,
+        source_id: &SourceId,
+        temp_src: &Source,
+    ) -> Option<Source> {
+        let path = source_engine.get_path(source_id);
+        if let Some(cached) = self.workspace_sources.get(&path) {
+            if Arc::ptr_eq(&cached.temp_text, &temp_src.text) {
+                return cached.differing.clone();
+            }
+        }
+        let document_text = self.document_store.read().as_ref().and_then(|store| {
+            store
+                .document_text(&path)
+                .or_else(|| store.document_text(&source_engine.get_path(temp_source_id)))
+        });
+        let contents = match document_text {
+            Some(text) => Some(text),
+            None => self.file_system.read_to_string(&path).ok(),
+        };
+        let temp_text = temp_src.text.as_ref();
+        let differing = contents
+            .filter(|contents| contents != temp_text && same_lines(contents, temp_text))
+            .map(|contents| Source::new(&contents));
+        self.workspace_sources.insert(
+            path,
+            WorkspaceSource {
+                temp_text: temp_src.text.clone(),
+                differing: differing.clone(),
+            },
+        );
+        differing
+    }
+
+    /// If path is part of the users workspace, then convert URL from temp to workspace dir.
+    /// Otherwise, pass through if it points to a dependency path
+    pub(crate) fn to_workspace_url(&self, url: Url) -> Option<Url> {
+        if self.is_path_in_temp_workspace(&url) {
+            Some(self.temp_to_workspace_url(&url).ok()?)
+        } else {
+            Some(url)
+        }
+    }
+
+    pub fn manifest_path(&self) -> Option<PathBuf> {
+        self.manifest_dir()
+            .map(|dir| dir.join(sway_utils::constants::MANIFEST_FILE_NAME))
+            .ok()
+    }
+
+    /// Watch the manifest directories and check for any save events on Forc.toml
+    ///
+    /// The `Forc.lock` files changed in the meantime are copied into the temp directory, and
+    /// published as [WatchEvent::DependenciesChanged].
+    ///
+    /// The watcher is set up before the task is spawned so that failing to watch a directory,
+    /// e.g. because it was removed, is returned as [DirectoryError::WatchFailed].
+    pub(crate) fn watch_and_sync_manifest(&self) -> Result<(), LanguageServerError> {
+        let package_dirs = self.package_dirs();
+        let Some(root_dirs) = package_dirs.first() else {
+            return Ok(());
+        };
+        let file_system = self.manifest_file_system();
+        let tracker = self.sync_tracker();
+        if let Err(err) = edit_manifests_dependency_paths(
+            &*file_system,
+            &package_dirs,
+            self.dependency_root.as_deref(),
+        ) {
+            error!("Failed to edit manifest dependency paths: {}", err);
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        // Setup debouncer. No specific tickrate, max debounce time `manifest_watch_debounce`
+        let manifest_watch_debounce = self.effective_manifest_watch_debounce();
+        let mut debouncer = new_debouncer(manifest_watch_debounce, move |event| match event {
+            Ok(e) => {
+                let _ = tx.blocking_send(e);
+            }
+            Err(err) => error!("Failed to watch manifest files: {}", err),
+        })
+        .map_err(|err| DirectoryError::WatchFailed {
+            path: root_dirs.manifest_dir.to_string_lossy().to_string(),
+            err: err.to_string(),
+        })?;
+        for dirs in &package_dirs {
+            debouncer
+                .watcher()
+                .watch(&dirs.manifest_dir, RecursiveMode::NonRecursive)
+                .map_err(|err| DirectoryError::WatchFailed {
+                    path: dirs.manifest_dir.to_string_lossy().to_string(),
+                    err: err.to_string(),
+                })?;
+        }
+
+        let resync_debounce = self.effective_resync_debounce();
+        let dependency_root = self.dependency_root.clone();
+        let watch_events = self.watch_events.clone();
+        let manifest = self.manifest.clone();
+        let ignore = self.ignore_patterns.clone();
+        let excluded_patterns = self.configured_excluded_patterns();
+        let source_dirs_only = self.source_dirs_only;
+        let copied_dirs = self.copied_package_dirs()?;
+        let lock_synced_files = SyncedFiles {
+            file_names: BTreeSet::from([LOCK_FILE_NAME.to_string()]),
+            extensions: BTreeSet::new(),
+        };
+        let paused = self.watcher_paused.clone();
+        let handle = tokio::spawn(async move {
+            // Keep the debouncer alive for as long as the task runs
+            let _debouncer = debouncer;
+            while let Some(mut events) = rx.recv().await {
+                // Coalesce the events that keep arriving in the meantime into a single rewrite.
+                tokio::time::sleep(resync_debounce).await;
+                while let Ok(more_events) = rx.try_recv() {
+                    events.extend(more_events);
+                }
+                if paused.load(Ordering::SeqCst) {
+                    trace!("Watcher paused, dropping {} manifest events", events.len());
+                    continue;
+                }
+                // Reparse the Forc.toml and convert relative paths
+                // to absolute. Save into our temp directory.
+                let sync = tracker.begin();
+                if let Err(err) = reload_manifest(
+                    &manifest,
+                    &SourceDirsIgnore {
+                        ignore: &ignore,
+                        excluded_patterns: &excluded_patterns,
+                        source_dirs_only,
+                    },
+                    &*file_system,
+                    &package_dirs,
+                    dependency_root.as_deref(),
+                ) {
+                    error!("Failed to reload manifest: {}", err);
+                }
+                drop(sync);
+                let paths: BTreeSet<PathBuf> = events.into_iter().map(|event| event.path).collect();
+                let named = |file_name: &str| -> Vec<PathBuf> {
+                    paths
+                        .iter()
+                        .filter(|path| path.file_name() == Some(OsStr::new(file_name)))
+                        .cloned()
+                        .collect()
+                };
+                let (manifests, lock_files) = (named(MANIFEST_FILE_NAME), named(LOCK_FILE_NAME));
+                if !manifests.is_empty() {
+                    // Sending only fails if there are no subscribers.
+                    let _ = watch_events.send(WatchEvent::ManifestChanged(manifests));
+                }
+                if lock_files.is_empty() {
+                    continue;
+                }
+                // Copy the lock files updated by e.g. `forc update`, so that the dependencies
+                // are resolved again with the versions they pin.
+                let sync = tracker.begin();
+                match sync_changed_files(
+                    &*file_system,
+                    &copied_dirs,
+                    &package_dirs,
+                    &Gitignore::empty(),
+                    &lock_synced_files,
+                    &lock_files,
+                    dependency_root.as_deref(),
+                ) {
+                    Ok(synced) if !synced.is_empty() => {
+                        let _ = watch_events.send(WatchEvent::DependenciesChanged(synced));
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("Failed to sync lock files: {}", err),
+                }
+                drop(sync);
+            }
+        });
+
+        // Store the join handle so we can clean up the thread on shutdown
+        {
+            let mut join_handle = self.notify_join_handle.write();
+            *join_handle = Some(handle);
+        }
+        Ok(())
+    }
+
+    /// Recursively watch the manifest directory for changes made to `.sw`, `Forc.toml` and `Forc.lock`
+    /// files outside of the editor, e.g. by a codegen script or `git checkout`, and copy them into the
+    /// temp directory.
+    ///
+    /// The temp paths of the synced files are sent over `changes` so that the server can recompile.
+    /// Fails if one of the directories can't be watched, e.g. as the watch limit was reached.
+    pub(crate) fn watch_and_sync_sources(
+        &self,
+        changes: UnboundedSender<Vec<PathBuf>>,
+    ) -> Result<(), DirectoryError> {
+        let copied_dirs = self.copied_package_dirs()?;
+        let Some(root_dirs) = copied_dirs.first() else {
+            return Ok(());
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        // Setup debouncer. No specific tickrate, max debounce time the same as the manifest watcher
+        let watch_debounce = self.effective_manifest_watch_debounce();
+        let mut debouncer = new_debouncer(watch_debounce, move |event| match event {
+            Ok(e) => {
+                let _ = tx.blocking_send(e);
+            }
+            Err(err) => error!("Failed to watch source files: {}", err),
+        })
+        .map_err(|err| DirectoryError::WatchFailed {
+            path: root_dirs.manifest_dir.to_string_lossy().to_string(),
+            err: err.to_string(),
+        })?;
+        for dirs in &copied_dirs {
+            debouncer
+                .watcher()
+                .watch(&dirs.manifest_dir, RecursiveMode::Recursive)
+                .map_err(|err| DirectoryError::WatchFailed {
+                    path: dirs.manifest_dir.to_string_lossy().to_string(),
+                    err: err.to_string(),
+                })?;
+        }
+
+        *self.source_changes.write() = Some(changes.clone());
+        let package_dirs = self.package_dirs();
+        let ignore = self.ignore_patterns.clone();
+        let synced_files = self.effective_synced_files();
+        let file_system = self.copy_file_system(self.manifest_file_system());
+        let watch_events = self.watch_events.clone();
+        let tracker = self.sync_tracker();
+        let dependency_root = self.dependency_root.clone();
+        let paused = self.watcher_paused.clone();
+
+        let handle = tokio::spawn(async move {
+            // Keep the debouncer alive for as long as the task runs
+            let _debouncer = debouncer;
+            while let Some(events) = rx.recv().await {
+                if paused.load(Ordering::SeqCst) {
+                    trace!("Watcher paused, dropping {} source events", events.len());
+                    continue;
+                }
+                let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+                // Read for each batch, as they follow the source directories of reloaded manifests.
+                let ignore = ignore.read().clone();
+                let _sync = tracker.begin();
+                match sync_changed_files(
+                    &*file_system,
+                    &copied_dirs,
+                    &package_dirs,
+                    &ignore,
+                    &synced_files,
+                    &paths,
+                    dependency_root.as_deref(),
+                ) {
+                    Ok(synced) if !synced.is_empty() => {
+                        // Sending only fails if there are no subscribers.
+                        let _ = watch_events.send(WatchEvent::SourceChanged(synced.clone()));
+                        let _ = changes.send(synced);
                     }
-                    fs::copy(&path, target_dir.as_ref().join(file_name))?;
+                    Ok(_) => {}
+                    Err(err) => error!("Failed to sync changed files: {}", err),
+                }
+            }
+        });
+
+        // Store the join handle so we can clean up the thread on shutdown
+        {
+            let mut join_handle = self.source_watch_join_handle.write();
+            *join_handle = Some(handle);
+        }
+        Ok(())
+    }
+
+    /// Stop the threads watching the manifest and source files.
+    pub(crate) fn stop_watchers(&self) {
+        for handle in [&self.notify_join_handle, &self.source_watch_join_handle] {
+            if let Some(join_handle) = handle.write().take() {
+                join_handle.abort();
+            }
+        }
+    }
+
+    /// Stop the threads watching the manifest and source files, wait for them to finish and then
+    /// delete the temp directory.
+    ///
+    /// Unlike [SyncWorkspace::stop_watchers] followed by [SyncWorkspace::remove_temp_dir], a watcher
+    /// can't still be handling an event against the temp directory once it was removed.
+    pub async fn close(&self) {
+        let handles: Vec<_> = [&self.notify_join_handle, &self.source_watch_join_handle]
+            .into_iter()
+            .filter_map(|handle| handle.write().take())
+            .collect();
+        for handle in handles {
+            handle.abort();
+            // A cancelled task is expected, only a panic in the watcher is worth reporting.
+            if let Err(err) = handle.await {
+                if err.is_panic() {
+                    error!("Watcher thread panicked: {}", err);
                 }
             }
         }
+        self.remove_temp_dir();
+    }
+
+    /// Check if the current path is part of this session's temp workspace, see [SyncWorkspace::owns_temp_url].
+    pub(crate) fn is_path_in_temp_workspace(&self, uri: &Url) -> bool {
+        self.owns_temp_url(uri)
+    }
+
+    /// Returns true if `url` points into this session's temp workspace, i.e. under its temp root.
+    /// Returns false if it is from a dependency, or from the temp workspace of another session.
+    pub fn owns_temp_url(&self, url: &Url) -> bool {
+        // A workspace used in place has no temp workspace, and its parent isn't a temp root.
+        !self.is_in_place()
+            && self
+                .temp_root()
+                .is_ok_and(|temp_root| is_path_in_temp_root(url, &temp_root))
+    }
+
+    /// Return the directory created with `LSP_TEMP_PREFIX`. The project is copied into a
+    /// subdirectory of it, and the workspace members living outside the project next to it.
+    ///
+    /// It is safe for external tools to read while the server runs, see [SyncWorkspace::temp_dir].
+    pub fn temp_root(&self) -> Result<PathBuf, DirectoryError> {
+        self.temp_dir()?
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or(DirectoryError::TempDirNotFound)
+    }
+
+    /// Return the current state of the sync, e.g. for a status bar.
+    pub fn status(&self) -> SyncStatus {
+        let watcher_active = [&self.notify_join_handle, &self.source_watch_join_handle]
+            .iter()
+            .any(|handle| {
+                handle
+                    .read()
+                    .as_ref()
+                    .is_some_and(|handle| !handle.is_finished())
+            });
+        SyncStatus {
+            manifest_dir: self.manifest_dir().ok(),
+            temp_dir: self.temp_dir().ok(),
+            watcher_active,
+            last_sync: *self.last_sync.read(),
+        }
+    }
+
+    /// Return the path to the projects manifest directory.
+    pub(crate) fn manifest_dir(&self) -> Result<PathBuf, DirectoryError> {
+        self.cached_directories
+            .load()
+            .manifest_dir
+            .clone()
+            .ok_or(DirectoryError::ManifestDirNotFound)
+    }
+
+    /// Return the path to the temporary directory that was created for the current session,
+    /// the project's subdirectory of [SyncWorkspace::temp_root].
+    ///
+    /// External tools may read it while the server runs, e.g. to run `forc test` against it,
+    /// but shouldn't write to it as it is overwritten by the syncs. A sync may be running while
+    /// it is read, which can be detected with the [SyncGeneration] written to the temp root.
+    pub fn temp_dir(&self) -> Result<PathBuf, DirectoryError> {
+        self.cached_directories
+            .load()
+            .temp_dir
+            .clone()
+            .ok_or(DirectoryError::TempDirNotFound)
+    }
+
+    /// Return the path to the manifest directory of the given workspace member.
+    pub(crate) fn member_manifest_dir(&self, member: &str) -> Result<PathBuf, DirectoryError> {
+        self.cached_directories
+            .load()
+            .member_manifest_dirs
+            .get(member)
+            .cloned()
+            .ok_or(DirectoryError::ManifestDirNotFound)
+    }
+
+    /// Return the path to the temp directory of the given workspace member.
+    pub(crate) fn member_temp_dir(&self, member: &str) -> Result<PathBuf, DirectoryError> {
+        self.cached_directories
+            .load()
+            .member_temp_dirs
+            .get(member)
+            .cloned()
+            .ok_or(DirectoryError::TempDirNotFound)
+    }
+
+    /// Return the names of the workspace members being synced, in sorted order.
+    /// This is empty if the session was started from a package rather than a workspace.
+    pub fn members(&self) -> Vec<String> {
+        self.cached_directories
+            .load()
+            .member_manifest_dirs
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Return the root directories followed by the directories of every workspace member.
+    pub(crate) fn package_dirs(&self) -> Vec<PackageDirs> {
+        let mut package_dirs = Vec::new();
+        if let (Ok(manifest_dir), Ok(temp_dir)) = (self.manifest_dir(), self.temp_dir()) {
+            package_dirs.push(PackageDirs {
+                manifest_dir,
+                temp_dir,
+            });
+            package_dirs.extend(self.member_package_dirs());
+        }
+        package_dirs
+    }
+
+    fn member_package_dirs(&self) -> Vec<PackageDirs> {
+        self.members()
+            .iter()
+            .filter_map(|member| {
+                Some(PackageDirs {
+                    manifest_dir: self.member_manifest_dir(member).ok()?,
+                    temp_dir: self.member_temp_dir(member).ok()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Find the workspace member whose directory, as selected by `dir`, is the closest ancestor of `path`.
+    fn member_containing(
+        &self,
+        path: &Path,
+        dir: impl Fn(&PackageDirs) -> &PathBuf,
+    ) -> Option<PackageDirs> {
+        closest_package_dirs(&self.member_package_dirs(), path, dir).cloned()
+    }
+
+    /// Insert the path of the directory, refreshing the cached directories.
+    pub(crate) fn insert_directory(&self, directory: Directory, path: PathBuf) {
+        self.directories.insert(directory, path);
+        self.refresh_cached_directories();
+    }
+
+    /// Rebuild the cached directories from `directories`, which is the source of truth.
+    pub(crate) fn refresh_cached_directories(&self) {
+        let mut cached = Directories::default();
+        for item in self.directories.iter() {
+            let path = item.value().clone();
+            match item.key() {
+                Directory::Manifest => cached.manifest_dir = Some(path),
+                Directory::Temp => cached.temp_dir = Some(path),
+                Directory::MemberManifest(name) => {
+                    cached.member_manifest_dirs.insert(name.clone(), path);
+                }
+                Directory::MemberTemp(name) => {
+                    cached.member_temp_dirs.insert(name.clone(), path);
+                }
+            }
+        }
+        self.cached_directories.store(Arc::new(cached));
+    }
+}
+
+/// A snapshot of the [SyncWorkspace] directories, which can be read without probing the
+/// [DashMap] they are stored in.
+#[derive(Debug, Default)]
+struct Directories {
+    manifest_dir: Option<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    /// The manifest directories of the workspace members, keyed by package name.
+    member_manifest_dirs: BTreeMap<String, PathBuf>,
+    /// The temp directories of the workspace members, keyed by package name.
+    member_temp_dirs: BTreeMap<String, PathBuf>,
+}
+
+/// Return the name of the project's directory in the temp directory.
+/// Names that aren't valid UTF-8 are sanitized, with a hash of the original name appended
+/// so that the sanitized name stays unique and stable for a given directory.
+fn temp_project_name(dir_name: &OsStr) -> String {
+    if let Some(name) = dir_name.to_str() {
+        return name.to_string();
+    }
+    let sanitized: String = dir_name
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c == char::REPLACEMENT_CHARACTER {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let mut hasher = DefaultHasher::new();
+    dir_name.hash(&mut hasher);
+    format!("{sanitized}-{:016x}", hasher.finish())
+}
+
+/// The name of the temp root of the project in `manifest_dir` with
+/// [SyncWorkspace::deterministic_temp_dir], from a hash of its path. It shares the
+/// `LSP_TEMP_PREFIX` of the random names but never the session id prefix of those, as the hash
+/// has no `_`.
+fn deterministic_temp_root_name(manifest_dir: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    manifest_dir.hash(&mut hasher);
+    format!(
+        "{}_{:016x}",
+        SyncWorkspace::LSP_TEMP_PREFIX,
+        hasher.finish()
+    )
+}
+
+/// Create the directory `name` in the existing `base_dir`, removing the stale one of a previous
+/// session first.
+fn create_deterministic_temp_root(
+    file_system: &dyn FileSystem,
+    base_dir: &Path,
+    name: &str,
+) -> std::io::Result<PathBuf> {
+    if entry_kind(file_system, base_dir) != Some(EntryKind::Dir) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{:?} is not a directory", base_dir),
+        ));
+    }
+    let temp_root = base_dir.join(name);
+    if entry_kind(file_system, &temp_root).is_some() {
+        debug!(?temp_root, "Removing stale temp directory");
+        file_system.remove_dir_all(&temp_root)?;
+    }
+    file_system.create_dir_all(&temp_root)?;
+    Ok(temp_root)
+}
+
+/// Map the source of a temp directory file to the same file in the user's workspace, using the
+/// closest of the member directories containing it or the root directories otherwise.
+/// Returns `None` if the source isn't a project file of the temp directory, e.g. if it is the
+/// source of a dependency that was copied into the temp directory and only lives there.
+fn temp_to_workspace_source_id(
+    source_engine: &SourceEngine,
+    source_id: &SourceId,
+    temp_root: &Path,
+    root: &PackageDirs,
+    members: &[PackageDirs],
+) -> Result<Option<SourceId>, DirectoryError> {
+    let url = get_url_from_path(&source_engine.get_path(source_id))?;
+    if !is_path_in_temp_root(&url, temp_root) {
+        return Ok(None);
+    }
+    let path = get_path_from_url(&url)?;
+    let Some(dirs) = closest_package_dirs(members, &path, |dirs| &dirs.temp_dir)
+        .or_else(|| path_starts_with(&path, &root.temp_dir).then_some(root))
+    else {
+        return Ok(None);
+    };
+    let converted_url = convert_url(&url, &dirs.manifest_dir, &dirs.temp_dir)?;
+    let converted_path = get_path_from_url(&converted_url)?;
+    Ok(Some(source_engine.get_source_id(&converted_path)))
+}
+
+/// Returns true if `a` and `b` have the same lines, regardless of their line endings and trailing
+/// newlines.
+fn same_lines(a: &str, b: &str) -> bool {
+    fn lines(text: &str) -> impl Iterator<Item = &str> {
+        text.trim_end_matches(['\r', '\n'])
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r'))
+    }
+    lines(a).eq(lines(b))
+}
+
+/// Return the offset of the zero-indexed `line_col` in `src`. The column is clamped to the end of
+/// its line, ignoring a trailing `\r`, and the offset to the end of `src` if it has fewer lines.
+fn offset_at_line_col(src: &Source, line_col: LineCol) -> usize {
+    let text = src.text.as_ref();
+    let mut line_start = 0;
+    for _ in 0..line_col.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+    let line = text[line_start..].split('\n').next().unwrap_or_default();
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut col = line_col.col.min(line.len());
+    while !line.is_char_boundary(col) {
+        col -= 1;
+    }
+    line_start + col
+}
+
+/// Find the package whose directory, as selected by `dir`, is the closest ancestor of `path`.
+fn closest_package_dirs<'a>(
+    package_dirs: &'a [PackageDirs],
+    path: &Path,
+    dir: impl Fn(&PackageDirs) -> &PathBuf,
+) -> Option<&'a PackageDirs> {
+    package_dirs
+        .iter()
+        .filter(|dirs| path_starts_with(path, dir(dirs)))
+        .max_by_key(|dirs| dir(dirs).components().count())
+}
+
+/// Check if the current path is within the given temp root directory.
+fn is_path_in_temp_root(uri: &Url, temp_root: &Path) -> bool {
+    get_path_from_url(uri).is_ok_and(|path| path_starts_with(&path, temp_root))
+}
+
+/// Whether paths are compared ignoring their casing, as the default filesystems of macOS
+/// and Windows are case-insensitive.
+const CASE_INSENSITIVE_PATHS: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+fn path_starts_with(path: &Path, prefix: &Path) -> bool {
+    strip_path_prefix(path, prefix, CASE_INSENSITIVE_PATHS).is_some()
+}
+
+/// Strip `prefix` from `path`. If `case_insensitive` is set, the components of the prefix
+/// are matched ignoring ASCII casing, and the remaining components are kept as-is.
+fn strip_path_prefix<'a>(
+    path: &'a Path,
+    prefix: &Path,
+    case_insensitive: bool,
+) -> Option<&'a Path> {
+    if !case_insensitive {
+        return path.strip_prefix(prefix).ok();
+    }
+    let mut components = path.components();
+    for prefix_component in prefix.components() {
+        let component = components.next()?;
+        if !component
+            .as_os_str()
+            .eq_ignore_ascii_case(prefix_component.as_os_str())
+        {
+            return None;
+        }
+    }
+    Some(components.as_path())
+}
+
+fn convert_url(uri: &Url, from: &Path, to: &Path) -> Result<Url, DirectoryError> {
+    // Trailing separators would otherwise make equal directories look different.
+    let (from, to) = (normalize_separators(from), normalize_separators(to));
+    if from == to {
+        return Ok(uri.clone());
+    }
+    // The url path is percent-encoded, so it is decoded to find it under `to`.
+    let path = get_path_from_url(uri)?;
+    let relative = strip_path_prefix(&path, &to, CASE_INSENSITIVE_PATHS).ok_or_else(|| {
+        DirectoryError::StripPrefixError {
+            path: path.to_string_lossy().to_string(),
+            prefix: to.to_string_lossy().to_string(),
+        }
+    })?;
+    // Joining the empty path of the directory itself would add a trailing separator.
+    let path = if relative.as_os_str().is_empty() {
+        from
+    } else {
+        from.join(relative)
+    };
+    url_like(uri, &path)
+}
+
+/// Create the [Url] of `path`, ending with a slash if `uri` does, e.g. for directory urls.
+fn url_like(uri: &Url, path: &Path) -> Result<Url, DirectoryError> {
+    let url = if uri.path().ends_with('/') {
+        Url::from_directory_path(path)
+    } else {
+        Url::from_file_path(path)
+    };
+    url.map_err(|()| DirectoryError::UrlFromPathFailed {
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Rebuild the path from its components, dropping trailing and repeated separators.
+fn normalize_separators(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+/// The ignore patterns rebuilt by [reload_manifest] for the source directories of the reloaded
+/// manifest, see [SyncWorkspace::with_source_dirs_only].
+struct SourceDirsIgnore<'a> {
+    ignore: &'a RwLock<Gitignore>,
+    /// The patterns excluded regardless of the manifest.
+    excluded_patterns: &'a [String],
+    source_dirs_only: bool,
+}
+
+/// Parse the manifest of the root package of `package_dirs` through `file_system` into
+/// `manifest`, rebuild the ignore patterns of `source_dirs` with it, then rewrite the dependency
+/// paths of the synced manifests. The cached manifest is cleared if it fails to parse, so that a
+/// stale one isn't used.
+fn reload_manifest(
+    manifest: &RwLock<Option<ManifestFile>>,
+    source_dirs: &SourceDirsIgnore,
+    file_system: &dyn FileSystem,
+    package_dirs: &[PackageDirs],
+    dependency_root: Option<&Path>,
+) -> Result<(), LanguageServerError> {
+    let Some(root_dirs) = package_dirs.first() else {
+        return Err(DirectoryError::ManifestDirNotFound.into());
+    };
+    let manifest_path = root_dirs.manifest_dir.join(MANIFEST_FILE_NAME);
+    let parsed = file_system
+        .read_to_string(&manifest_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| ManifestFile::from_string(contents, &manifest_path));
+    match parsed {
+        Ok(parsed) => *manifest.write() = Some(parsed),
+        Err(_) => {
+            *manifest.write() = None;
+            return Err(DocumentError::ManifestFileNotFound {
+                dir: root_dirs.manifest_dir.to_string_lossy().to_string(),
+            }
+            .into());
+        }
+    }
+    let patterns = with_source_dir_patterns(
+        source_dirs.excluded_patterns.to_vec(),
+        source_dirs.source_dirs_only,
+        manifest.read().as_ref(),
+        &root_dirs.manifest_dir,
+    );
+    *source_dirs.ignore.write() = ignore_patterns(file_system, &root_dirs.manifest_dir, &patterns);
+    edit_manifests_dependency_paths(file_system, package_dirs, dependency_root)
+}
+
+/// Rewrite the dependency paths of every synced package manifest into its temp directory.
+/// This includes the manifests of nested packages that were copied along with their parent,
+/// whose paths are resolved relative to their own directory.
+///
+/// Only the `path` strings of the synced manifests are rewritten: the dependencies are never
+/// followed into to rewrite their own manifests, so that cycles of path dependencies, e.g.
+/// packages depending on each other during development, can't make it loop.
+pub(crate) fn edit_manifests_dependency_paths(
+    file_system: &dyn FileSystem,
+    package_dirs: &[PackageDirs],
+    dependency_root: Option<&Path>,
+) -> Result<(), LanguageServerError> {
+    let manifest_dirs = copied_manifest_dirs(file_system, package_dirs);
+    for dirs in &manifest_dirs {
+        edit_manifest_dependency_paths(
+            file_system,
+            &dirs.manifest_dir,
+            &dirs.manifest_dir.join(MANIFEST_FILE_NAME),
+            &dirs.temp_dir.join(MANIFEST_FILE_NAME),
+            &manifest_dirs,
+            dependency_root,
+        )?;
+    }
+    Ok(())
+}
+
+/// Return `package_dirs` followed by the directories of every other `Forc.toml` that was copied
+/// into their temp directories, e.g. the manifests of nested packages that aren't workspace members.
+fn copied_manifest_dirs(
+    file_system: &dyn FileSystem,
+    package_dirs: &[PackageDirs],
+) -> Vec<PackageDirs> {
+    let mut manifest_dirs = package_dirs.to_vec();
+    for dirs in package_dirs {
+        collect_nested_manifest_dirs(
+            file_system,
+            &dirs.manifest_dir,
+            &dirs.temp_dir,
+            &mut manifest_dirs,
+        );
+    }
+    // Members nested in the root package are found again while walking its temp directory.
+    // The manifests of a workspace used in place are left as they are.
+    let mut seen = HashSet::new();
+    manifest_dirs
+        .retain(|dirs| dirs.manifest_dir != dirs.temp_dir && seen.insert(dirs.temp_dir.clone()));
+    manifest_dirs
+}
+
+fn collect_nested_manifest_dirs(
+    file_system: &dyn FileSystem,
+    manifest_dir: &Path,
+    temp_dir: &Path,
+    manifest_dirs: &mut Vec<PackageDirs>,
+) {
+    let Ok(entries) = file_system.read_dir(temp_dir) else {
+        return;
+    };
+    // Symlinked directories are skipped, so that manifests outside of the temp directory
+    // are never written to.
+    for entry in entries.iter().filter(|entry| entry.kind == EntryKind::Dir) {
+        let dirs = PackageDirs {
+            manifest_dir: manifest_dir.join(entry.file_name()),
+            temp_dir: entry.path.clone(),
+        };
+        let has_manifest = |dir: &Path| {
+            file_system.read_dir(dir).is_ok_and(|entries| {
+                entries.iter().any(|entry| {
+                    entry.kind == EntryKind::File && entry.file_name() == MANIFEST_FILE_NAME
+                })
+            })
+        };
+        if has_manifest(&dirs.temp_dir) && has_manifest(&dirs.manifest_dir) {
+            manifest_dirs.push(dirs.clone());
+        }
+        collect_nested_manifest_dirs(
+            file_system,
+            &dirs.manifest_dir,
+            &dirs.temp_dir,
+            manifest_dirs,
+        );
+    }
+}
+
+/// The sections of a manifest holding its dependencies, apart from the patches.
+const DEPENDENCY_SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Deserialize the manifest file and loop through the dependencies, dev-dependencies,
+/// build-dependencies and patches.
+/// Check if the dependency is specifying a 'path'.
+/// If so, check if the path is relative and convert the relative path to an absolute path.
+/// Dependencies on other synced packages are pointed at their copy in the temp directory.
+/// Edit the toml entry using toml_edit with the absolute path.
+/// Save the manifest to temp_dir/Forc.toml, replacing it atomically.
+pub(crate) fn edit_manifest_dependency_paths(
+    file_system: &dyn FileSystem,
+    manifset_dir: &Path,
+    manifest_path: &Path,
+    temp_manifest_path: &Path,
+    package_dirs: &[PackageDirs],
+    dependency_root: Option<&Path>,
+) -> Result<(), LanguageServerError> {
+    let _p = tracing::trace_span!("edit_manifest_dependency_paths", ?manifest_path).entered();
+    let start = Instant::now();
+    let doc = manifest_with_temp_dependency_paths(
+        file_system,
+        manifset_dir,
+        manifest_path,
+        package_dirs,
+        None,
+        dependency_root,
+    )?;
+
+    // Write the updated manifest to the temp file, unless it is already up to date. Rewriting it
+    // regardless would be seen by the watchers of the temp directory and trigger another sync.
+    let contents = doc.to_string();
+    if file_system
+        .read_to_string(temp_manifest_path)
+        .is_ok_and(|existing| existing == contents)
+    {
+        trace!(elapsed = ?start.elapsed(), "Temp manifest is up to date");
+        return Ok(());
+    }
+    file_system
+        .write(temp_manifest_path, &contents)
+        .map_err(|err| DocumentError::UnableToWriteFile {
+            path: temp_manifest_path.to_string_lossy().to_string(),
+            err: err.to_string(),
+        })?;
+    trace!(
+        dependency_count = doc.get("dependencies").and_then(|deps| deps.as_table_like()).map_or(0, |deps| deps.len()),
+        elapsed = ?start.elapsed(),
+        "Rewrote manifest dependency paths"
+    );
+
+    Ok(())
+}
+
+/// Parse the manifest and rewrite its dependency paths, as described in [edit_manifest_dependency_paths],
+/// returning the edited document.
+///
+/// If `warnings` is given, dependency paths that fail to canonicalize are pushed to it instead of
+/// being logged.
+fn manifest_with_temp_dependency_paths(
+    file_system: &dyn FileSystem,
+    manifset_dir: &Path,
+    manifest_path: &Path,
+    package_dirs: &[PackageDirs],
+    mut warnings: Option<&mut Vec<DependencyPathWarning>>,
+    dependency_root: Option<&Path>,
+) -> Result<toml_edit::DocumentMut, LanguageServerError> {
+    // Read and parse the original manifest
+    let manifest_content =
+        file_system
+            .read_to_string(manifest_path)
+            .map_err(|err| DocumentError::IOError {
+                path: manifest_path.to_string_lossy().to_string(),
+                error: err.to_string(),
+            })?;
+
+    // The document keeps the spans of its keys until it is made mutable, to point at invalid dependencies.
+    let spanned_doc = toml_edit::ImDocument::parse(manifest_content.as_str()).map_err(|err| {
+        DocumentError::IOError {
+            path: manifest_path.to_string_lossy().to_string(),
+            error: format!("Failed to parse TOML: {}", err),
+        }
+    })?;
+
+    let manifest = parse_manifest(&manifest_content).map_err(|err| {
+        match invalid_dependency(&manifest_content, &spanned_doc) {
+            Some((key, line, error)) => DocumentError::InvalidManifestKey {
+                path: manifest_path.to_string_lossy().to_string(),
+                key,
+                line,
+                error,
+            },
+            None => DocumentError::IOError {
+                path: manifest_path.to_string_lossy().to_string(),
+                error: err.to_string(),
+            },
+        }
+    })?;
+    let mut doc = spanned_doc.into_mut();
+
+    let patches: Vec<_> = match &manifest {
+        ParsedManifest::Package(package) => {
+            let mismatch = process_package_dependencies(
+                manifset_dir,
+                package.dependencies.as_ref(),
+                &mut doc,
+                package_dirs,
+                warnings.as_deref_mut(),
+                dependency_root,
+            );
+            if let Some(mismatch) = mismatch {
+                warn!(
+                    "Not rewriting the dependencies of manifest {:?}: {}",
+                    manifest_path, mismatch
+                );
+            }
+            package.patches().collect()
+        }
+        ParsedManifest::Workspace(workspace) => workspace.patches().collect(),
+    };
+
+    // Process every `[patch.'<source>']` table
+    for (patch_name, patch) in patches {
+        if let Some(patch_table) = doc
+            .get_mut("patch")
+            .and_then(|v| v.as_table_mut())
+            .and_then(|patches| patches.get_mut(patch_name))
+            .and_then(|v| v.as_table_mut())
+        {
+            process_dependencies(
+                manifset_dir,
+                patch,
+                patch_table,
+                package_dirs,
+                warnings.as_deref_mut(),
+                dependency_root,
+            );
+        }
+    }
+
+    // These sections aren't part of the parsed manifest, so their dependencies
+    // are deserialized from the TOML document itself.
+    for section in ["dev-dependencies", "build-dependencies"] {
+        if let Some(deps_table) = doc.get_mut(section).and_then(|v| v.as_table_mut()) {
+            let deps =
+                toml_edit::de::from_document(toml_edit::DocumentMut::from(deps_table.clone()))
+                    .map_err(|err| DocumentError::IOError {
+                        path: manifest_path.to_string_lossy().to_string(),
+                        error: format!("Failed to parse [{section}]: {err}"),
+                    })?;
+            process_dependencies(
+                manifset_dir,
+                &deps,
+                deps_table,
+                package_dirs,
+                warnings.as_deref_mut(),
+                dependency_root,
+            );
+        }
+    }
+
+    Ok(doc)
+}
+
+/// How the dependencies of a parsed package manifest disagree with its TOML document, in which
+/// case they can't be rewritten.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DependenciesMismatch {
+    /// The parsed manifest has path dependencies, but the document has no `[dependencies]` table.
+    ParsedOnly,
+    /// The document has dependencies, but the parsed manifest has none.
+    RawOnly,
+}
+
+impl fmt::Display for DependenciesMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParsedOnly => {
+                write!(f, "it has path dependencies but no [dependencies] table")
+            }
+            Self::RawOnly => write!(f, "its [dependencies] table wasn't parsed"),
+        }
+    }
+}
+
+/// Rewrite the paths of the `[dependencies]` of a package manifest, given the dependencies it
+/// was parsed with.
+///
+/// The parsed dependencies include the implicit `std`, which isn't in the document but isn't
+/// a path dependency either. Any other disagreement is returned so that it isn't silently ignored.
+fn process_package_dependencies(
+    manifest_dir: &Path,
+    deps: Option<&BTreeMap<String, forc_pkg::manifest::Dependency>>,
+    doc: &mut toml_edit::DocumentMut,
+    package_dirs: &[PackageDirs],
+    warnings: Option<&mut Vec<DependencyPathWarning>>,
+    dependency_root: Option<&Path>,
+) -> Option<DependenciesMismatch> {
+    let deps_table = doc.get_mut("dependencies").and_then(|v| v.as_table_mut());
+    match (deps, deps_table) {
+        (Some(deps), Some(deps_table)) => {
+            process_dependencies(
+                manifest_dir,
+                deps,
+                deps_table,
+                package_dirs,
+                warnings,
+                dependency_root,
+            );
+            None
+        }
+        (Some(deps), None) => deps
+            .values()
+            .any(|dependency| DependencyKind::of(dependency) == DependencyKind::Path)
+            .then_some(DependenciesMismatch::ParsedOnly),
+        (None, Some(deps_table)) => {
+            (!deps_table.is_empty()).then_some(DependenciesMismatch::RawOnly)
+        }
+        (None, None) => None,
+    }
+}
+
+/// Find the first dependency of the manifest that is invalid on its own, to point at it when the
+/// manifest fails to parse. Returns its dotted key, the 1-based line it is declared at, and the error.
+///
+/// Only the sections the manifest is parsed from are checked, and the patches are only checked
+/// to deserialize, as they aren't validated when parsing the manifest.
+fn invalid_dependency(
+    contents: &str,
+    doc: &toml_edit::ImDocument<&str>,
+) -> Option<(String, usize, String)> {
+    let mut tables: Vec<(String, &toml_edit::Table, bool)> = doc
+        .get("dependencies")
+        .and_then(|v| v.as_table())
+        .map(|deps| ("dependencies".to_string(), deps, true))
+        .into_iter()
+        .collect();
+    if let Some(patches) = doc.get("patch").and_then(|v| v.as_table()) {
+        tables.extend(patches.iter().filter_map(|(patch_name, patch)| {
+            Some((format!("patch.'{patch_name}'"), patch.as_table()?, false))
+        }));
+    }
+    tables.into_iter().find_map(|(section, table, validate)| {
+        table.iter().find_map(|(name, item)| {
+            let mut dependency = toml_edit::Table::new();
+            dependency.insert(name, item.clone());
+            let error = match toml_edit::de::from_document::<
+                BTreeMap<String, forc_pkg::manifest::Dependency>,
+            >(toml_edit::DocumentMut::from(dependency))
+            {
+                Ok(deps) => match deps.get(name)? {
+                    forc_pkg::manifest::Dependency::Detailed(details) if validate => {
+                        details.validate().err()?.to_string()
+                    }
+                    _ => return None,
+                },
+                Err(err) => err.message().to_string(),
+            };
+            let start = table.key(name)?.span()?.start;
+            let line = contents[..start].matches('\n').count() + 1;
+            Some((format!("{section}.{name}"), line, error))
+        })
+    })
+}
+
+/// A manifest parsed by [parse_manifest].
+enum ParsedManifest {
+    Package(Box<PackageManifest>),
+    Workspace(WorkspaceManifest),
+}
+
+/// Parse the contents of a `Forc.toml`, telling package and workspace manifests apart the same way
+/// [ManifestFile::from_file] does. As the manifest isn't read from the real filesystem, the checks
+/// relative to its directory are skipped.
+fn parse_manifest(contents: &str) -> anyhow::Result<ParsedManifest> {
+    match PackageManifest::from_string(contents.to_string()) {
+        Ok(package) => Ok(ParsedManifest::Package(Box::new(package))),
+        Err(err) if err.to_string().contains("missing field `project`") => {
+            WorkspaceManifest::from_string(contents).map(ParsedManifest::Workspace)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Process dependencies and convert relative paths to absolute.
+/// Paths that fail to canonicalize are only joined to the manifest directory, and are pushed to
+/// `warnings` if given or logged otherwise.
+///
+/// In a git worktree or submodule the paths are resolved lexically instead, see
+/// [is_linked_git_checkout].
+fn process_dependencies(
+    manifest_dir: &Path,
+    deps: &std::collections::BTreeMap<String, forc_pkg::manifest::Dependency>,
+    deps_table: &mut toml_edit::Table,
+    package_dirs: &[PackageDirs],
+    mut warnings: Option<&mut Vec<DependencyPathWarning>>,
+    dependency_root: Option<&Path>,
+) {
+    let resolve_lexically = is_linked_git_checkout(manifest_dir);
+    if resolve_lexically {
+        trace!(
+            ?manifest_dir,
+            "Resolving the dependency paths of a linked git checkout lexically"
+        );
+    }
+    for (name, dependency) in deps {
+        let kind = DependencyKind::of(dependency);
+        if kind != DependencyKind::Path {
+            trace!(
+                "Not rewriting {:?} dependency {} of the manifest in {:?}",
+                kind,
+                name,
+                manifest_dir
+            );
+            continue;
+        }
+        if let forc_pkg::manifest::Dependency::Detailed(details) = dependency {
+            if let Some(rel_path) = &details.path {
+                // Convert relative path to absolute
+                let joined_path = manifest_dir.join(rel_path);
+                let resolved_path = if resolve_lexically {
+                    let path = normalize_lexically(&joined_path);
+                    fs::metadata(&path).map(|_| path)
+                } else {
+                    joined_path.canonicalize()
+                };
+                let abs_path = match resolved_path {
+                    Ok(abs_path) => abs_path,
+                    Err(err) => {
+                        // The path may not exist yet, e.g. if the dependency was just added to
+                        // the manifest. Fall back to the joined path instead of failing the whole manifest.
+                        match warnings.as_deref_mut() {
+                            Some(warnings) => warnings.push(DependencyPathWarning {
+                                manifest_dir: manifest_dir.to_path_buf(),
+                                dependency: name.clone(),
+                                path: joined_path.clone(),
+                            }),
+                            None => warn!(
+                                "Failed to canonicalize path {:?} of dependency {}: {}",
+                                joined_path, name, err
+                            ),
+                        }
+                        joined_path
+                    }
+                };
+                if let Err(err) =
+                    check_dependency_root(dependency_root, manifest_dir, name, &abs_path)
+                {
+                    warn!("{}", err);
+                }
+                let abs_path = package_dirs
+                    .iter()
+                    .find(|dirs| dirs.manifest_dir == abs_path)
+                    .map_or(abs_path.clone(), |dirs| dirs.temp_dir.clone())
+                    .to_string_lossy()
+                    .to_string();
+
+                // Update the path in the TOML document. The dependency is either declared inline
+                // as `dep = { path = "..." }` or as a full `[dependencies.dep]` table.
+                if let Some(dep_item) = deps_table.get_mut(name) {
+                    if let Some(table) = dep_item.as_inline_table_mut() {
+                        table.insert("path", toml_edit::Value::from(abs_path));
+                    } else if let Some(table) = dep_item.as_table_mut() {
+                        table.insert("path", toml_edit::value(abs_path));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if `dir` is in a git worktree or submodule, i.e. if the closest `.git` above it is
+/// a file pointing at the git directory of another checkout rather than a directory.
+///
+/// The `..` of dependency paths are meant to stay within such checkouts, but canonicalizing
+/// them follows the symlinks that commonly point back into the main checkout.
+fn is_linked_git_checkout(dir: &Path) -> bool {
+    dir.ancestors()
+        .map(|dir| dir.join(".git"))
+        .find_map(|git| {
+            fs::symlink_metadata(&git)
+                .ok()
+                .map(|metadata| (git, metadata))
+        })
+        .is_some_and(|(git, metadata)| {
+            metadata.is_file()
+                && fs::read_to_string(git).is_ok_and(|contents| contents.starts_with("gitdir:"))
+        })
+}
+
+/// Resolve the `.` and `..` components of `path` without touching the filesystem, so that
+/// symlinks are not followed.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Check that the canonical `path` of the dependency `name` of the manifest in `manifest_dir`
+/// is under `dependency_root`, if given.
+fn check_dependency_root(
+    dependency_root: Option<&Path>,
+    manifest_dir: &Path,
+    name: &str,
+    path: &Path,
+) -> Result<(), DirectoryError> {
+    match dependency_root {
+        Some(root) if !path.starts_with(root) => Err(DirectoryError::DependencyOutsideWorkspace {
+            dependency: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+            manifest_dir: manifest_dir.to_string_lossy().to_string(),
+            root: root.to_string_lossy().to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Where a dependency is fetched from. Only path dependencies get their path rewritten.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DependencyKind {
+    Path,
+    Git,
+    Ipfs,
+    /// Versioned dependencies, fetched from the registry.
+    Registry,
+}
+
+impl DependencyKind {
+    fn of(dependency: &forc_pkg::manifest::Dependency) -> Self {
+        match dependency {
+            forc_pkg::manifest::Dependency::Detailed(details) if details.path.is_some() => {
+                Self::Path
+            }
+            _ if dependency.git().is_some() => Self::Git,
+            _ if dependency.ipfs().is_some() => Self::Ipfs,
+            _ => Self::Registry,
+        }
+    }
+}
+
+/// Compare the `Forc.lock` of the temp directory against the dependencies declared by each package
+/// of the temp manifest at `temp_dir`.
+///
+/// Returns the sorted names of the dependencies that are declared but not locked, or locked but no
+/// longer declared. No dependencies are returned if there is no lock file in `file_system`, which
+/// is only parsed from the real filesystem otherwise.
+fn lock_file_mismatches(
+    file_system: &dyn FileSystem,
+    temp_dir: &Path,
+) -> Result<Vec<String>, LanguageServerError> {
+    if entry_kind(file_system, &temp_dir.join(LOCK_FILE_NAME)).is_none() {
+        return Ok(vec![]);
+    }
+    let io_error = |path: &Path, err: anyhow::Error| DocumentError::IOError {
+        path: path.to_string_lossy().to_string(),
+        error: err.to_string(),
+    };
+    let manifest = ManifestFile::from_dir(temp_dir).map_err(|err| io_error(temp_dir, err))?;
+    let lock_path = manifest
+        .lock_path()
+        .map_err(|err| io_error(temp_dir, err))?;
+    if !lock_path.exists() {
+        return Ok(vec![]);
+    }
+    let graph = Lock::from_path(&lock_path)
+        .and_then(|lock| lock.to_graph())
+        .map_err(|err| io_error(&lock_path, err))?;
+    let members = manifest
+        .member_manifests()
+        .map_err(|err| io_error(temp_dir, err))?;
+
+    let mut mismatches = BTreeSet::new();
+    for (name, member) in &members {
+        let declared: BTreeSet<&str> = member
+            .deps()
+            .map(|(dep_name, _)| dep_name.as_str())
+            .chain(
+                member
+                    .contract_deps()
+                    .map(|(dep_name, _)| dep_name.as_str()),
+            )
+            .collect();
+        let locked: BTreeSet<&str> = graph
+            .node_indices()
+            .find(|&node| {
+                graph[node].name == *name && matches!(graph[node].source, source::Pinned::Member(_))
+            })
+            .map(|node| {
+                graph
+                    .edge_indices()
+                    .filter(|&edge| {
+                        graph
+                            .edge_endpoints(edge)
+                            .is_some_and(|(from, _)| from == node)
+                    })
+                    .map(|edge| graph[edge].name.as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        mismatches.extend(
+            declared
+                .symmetric_difference(&locked)
+                .map(|dep_name| dep_name.to_string()),
+        );
+    }
+    Ok(mismatches.into_iter().collect())
+}
+
+/// Map a path in the user's workspace to the same path in the temp directory, using the
+/// closest of the given package directories containing it.
+fn to_temp_path(package_dirs: &[PackageDirs], path: &Path) -> Option<PathBuf> {
+    closest_package_dirs(package_dirs, path, |dirs| &dirs.manifest_dir).and_then(|dirs| {
+        let relative = path.strip_prefix(&dirs.manifest_dir).ok()?;
+        Some(dirs.temp_dir.join(relative))
+    })
+}
+
+/// Copy the given changed files into the temp directory, or remove their copy if they were deleted.
+/// Files that don't get synced into the temp directory are ignored. If any `Forc.toml` changed,
+/// the dependency paths of the synced manifests are rewritten.
+///
+/// Returns the temp paths that were synced.
+fn sync_changed_files(
+    file_system: &dyn FileSystem,
+    copied_dirs: &[PackageDirs],
+    package_dirs: &[PackageDirs],
+    ignore: &Gitignore,
+    synced_files: &SyncedFiles,
+    paths: &[PathBuf],
+    dependency_root: Option<&Path>,
+) -> Result<Vec<PathBuf>, LanguageServerError> {
+    let mut synced = Vec::new();
+    // Renaming a directory is only reported for the directory itself, as a removal of its old
+    // path and a creation of its new one, so its contents are synced along with it.
+    for path in paths.iter().filter(|path| !synced_files.contains(path)) {
+        if copied_dirs.iter().any(|dirs| dirs.manifest_dir == **path) {
+            continue;
+        }
+        let Some(temp_path) = to_temp_path(copied_dirs, path) else {
+            continue;
+        };
+        let (kind, temp_kind) = (
+            entry_kind(file_system, path),
+            entry_kind(file_system, &temp_path),
+        );
+        if kind == Some(EntryKind::Dir) && temp_kind.is_none() && !is_ignored(ignore, path, true) {
+            synced.extend(copy_dir_contents(
+                file_system,
+                path,
+                &temp_path,
+                ignore,
+                synced_files,
+                false,
+            )?);
+        } else if kind.is_none() && temp_kind == Some(EntryKind::Dir) {
+            file_system.remove_dir_all(&temp_path).map_err(|err| {
+                DocumentError::UnableToRemoveFile {
+                    path: temp_path.to_string_lossy().to_string(),
+                    err: err.to_string(),
+                }
+            })?;
+            synced.push(temp_path);
+        }
+    }
+    for path in paths
+        .iter()
+        .filter(|path| synced_files.contains(path) && !is_ignored(ignore, path, false))
+    {
+        let Some(temp_path) = to_temp_path(copied_dirs, path).filter(|temp_path| temp_path != path)
+        else {
+            continue;
+        };
+        if entry_kind(file_system, path).is_some() && !is_orphan_lock_file(file_system, path) {
+            if let Some(parent) = temp_path.parent() {
+                file_system
+                    .create_dir_all(parent)
+                    .map_err(sync_path_failed(parent))?;
+            }
+            file_system
+                .copy(path, &temp_path)
+                .map_err(copy_file_failed(path, &temp_path))?;
+        } else if entry_kind(file_system, &temp_path).is_some() {
+            file_system.remove_file(&temp_path).map_err(|err| {
+                DocumentError::UnableToRemoveFile {
+                    path: temp_path.to_string_lossy().to_string(),
+                    err: err.to_string(),
+                }
+            })?;
+        } else {
+            continue;
+        }
+        synced.push(temp_path);
+    }
+    synced.sort();
+    synced.dedup();
+
+    if synced.iter().any(|path| path.ends_with(MANIFEST_FILE_NAME)) {
+        edit_manifests_dependency_paths(file_system, package_dirs, dependency_root)?;
+    }
+    Ok(synced)
+}
+
+/// Parse the diagnostics rendered by `forc build` out of its `output`, see [SyncWorkspace::build_temp].
+/// Diagnostics without a `-->` line, i.e. not in a source file, are skipped.
+fn parse_build_diagnostics(output: &str) -> Vec<BuildDiagnostic> {
+    struct Pending {
+        severity: lsp_types::DiagnosticSeverity,
+        message: String,
+        location: Option<(PathBuf, lsp_types::Position)>,
+    }
+    fn finish(pending: Option<Pending>, diagnostics: &mut Vec<BuildDiagnostic>) {
+        let Some(Pending {
+            severity,
+            message,
+            location: Some((path, position)),
+        }) = pending
+        else {
+            return;
+        };
+        let Ok(uri) = get_url_from_path(&path) else {
+            return;
+        };
+        diagnostics.push(BuildDiagnostic {
+            uri,
+            diagnostic: lsp_types::Diagnostic {
+                range: lsp_types::Range::new(position, position),
+                severity: Some(severity),
+                source: Some("forc build".to_string()),
+                message,
+                ..Default::default()
+            },
+        });
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut pending: Option<Pending> = None;
+    for line in output.lines().map(strip_ansi_escapes) {
+        let trimmed = line.trim();
+        if let Some(severity) = build_diagnostic_severity(&line) {
+            finish(pending.take(), &mut diagnostics);
+            let message = line
+                .split_once(':')
+                .map(|(_, message)| message.trim().to_string())
+                .unwrap_or_default();
+            pending = Some(Pending {
+                severity,
+                message,
+                location: None,
+            });
+        } else if let Some(location) = trimmed.strip_prefix("-->") {
+            let Some(pending) = pending
+                .as_mut()
+                .filter(|pending| pending.location.is_none())
+            else {
+                continue;
+            };
+            let mut parts = location.trim().rsplitn(3, ':');
+            let (Some(column), Some(line), Some(path)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let (Ok(line), Ok(column)) = (line.parse::<u32>(), column.parse::<u32>()) else {
+                continue;
+            };
+            pending.location = Some((
+                PathBuf::from(path),
+                lsp_types::Position::new(line.saturating_sub(1), column.saturating_sub(1)),
+            ));
+        } else if let Some(pending) = pending
+            .as_mut()
+            .filter(|pending| pending.message.is_empty())
+        {
+            // Old style diagnostics have no title, only the label under the carets.
+            let label = trimmed.split_once('|').map(|(_, rest)| rest.trim_start());
+            if let Some(label) = label.filter(|label| label.starts_with('^')) {
+                let label = label.trim_start_matches('^').trim();
+                if !label.is_empty() {
+                    pending.message = label.to_string();
+                }
+            }
+        }
+    }
+    finish(pending, &mut diagnostics);
+    diagnostics
+}
+
+/// The severity of the diagnostic whose title is `line`, e.g. `error: ...` or `warning`.
+fn build_diagnostic_severity(line: &str) -> Option<lsp_types::DiagnosticSeverity> {
+    [
+        ("error", lsp_types::DiagnosticSeverity::ERROR),
+        ("warning", lsp_types::DiagnosticSeverity::WARNING),
+    ]
+    .into_iter()
+    .find_map(|(level, severity)| {
+        let rest = line.strip_prefix(level)?;
+        (rest.is_empty() || rest.starts_with([':', '['])).then_some(severity)
+    })
+}
+
+/// Remove the ANSI escape sequences coloring `line`.
+fn strip_ansi_escapes(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip up to and including the final byte of the sequence, e.g. `m`.
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Read the manifest of the project `manifest_dir` belongs to, telling a missing directory,
+/// a missing `Forc.toml` and an invalid one apart. `manifest_dir` can also be a file of the
+/// project, e.g. the document opened in the editor.
+fn read_project_manifest(manifest_dir: &Path) -> Result<ManifestFile, DocumentError> {
+    let dir = manifest_dir.to_string_lossy().to_string();
+    if !manifest_dir.exists() {
+        return Err(DocumentError::ProjectDirNotFound { dir });
+    }
+    if find_parent_manifest_dir(manifest_dir).is_none() {
+        return Err(DocumentError::ManifestFileNotFound { dir });
+    }
+    ManifestFile::from_dir(manifest_dir).map_err(|err| DocumentError::InvalidManifestFile {
+        dir,
+        err: err.to_string(),
+    })
+}
+
+/// `patterns` followed by the [source_dir_patterns] of `manifest` if `source_dirs_only` is set.
+fn with_source_dir_patterns(
+    mut patterns: Vec<String>,
+    source_dirs_only: bool,
+    manifest: Option<&ManifestFile>,
+    manifest_dir: &Path,
+) -> Vec<String> {
+    if let Some(manifest) = manifest.filter(|_| source_dirs_only) {
+        patterns.extend(source_dir_patterns(manifest, manifest_dir));
+    }
+    patterns
+}
+
+/// The patterns in the `.gitignore` format of `manifest_dir` excluding everything but the source
+/// directories of the packages of `manifest`, i.e. the directories of their entry points, see
+/// [SyncWorkspace::with_source_dirs_only]. Packages outside of `manifest_dir` aren't restricted.
+///
+/// Each directory on the way to a source directory has its entries excluded, except for the
+/// next directory on the way, e.g. `/*`, `!/src/` for a package at the root.
+fn source_dir_patterns(manifest: &ManifestFile, manifest_dir: &Path) -> Vec<String> {
+    let packages = match manifest {
+        ManifestFile::Package(package) => vec![(**package).clone()],
+        ManifestFile::Workspace(workspace) => match workspace.member_manifests() {
+            Ok(members) => members.into_values().collect(),
+            Err(err) => {
+                warn!(
+                    "Failed to read the member manifests of {:?}: {}",
+                    manifest_dir, err
+                );
+                return Vec::new();
+            }
+        },
+    };
+    let source_dirs: Vec<PathBuf> = packages
+        .iter()
+        .filter_map(|package| {
+            let entry_path = package.entry_path();
+            let source_dir = entry_path.parent()?.strip_prefix(manifest_dir).ok()?;
+            Some(source_dir.to_path_buf())
+        })
+        .collect();
+    // The directories whose entries are restricted, along with the entries kept in each.
+    let mut kept: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    for source_dir in &source_dirs {
+        for dir in source_dir.ancestors().skip(1) {
+            if source_dirs
+                .iter()
+                .any(|source_dir| dir.starts_with(source_dir))
+            {
+                continue;
+            }
+            let child = source_dir
+                .strip_prefix(dir)
+                .ok()
+                .and_then(|relative| relative.components().next())
+                .map(|component| dir.join(component));
+            kept.entry(dir.to_path_buf()).or_default().extend(child);
+        }
+    }
+    let pattern_path = |path: &Path| {
+        path.components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    let mut patterns = Vec::new();
+    for (dir, children) in kept {
+        let dir = pattern_path(&dir);
+        patterns.push(if dir.is_empty() {
+            "/*".to_string()
+        } else {
+            format!("/{dir}/*")
+        });
+        patterns.extend(
+            children
+                .iter()
+                .map(|child| format!("!/{}/", pattern_path(child))),
+        );
+    }
+    patterns
+}
+
+/// The ignore files whose patterns exclude paths from the temp workspace, in increasing order of precedence.
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".swayignore"];
+
+/// Compile the patterns of the ignore files found in `manifest_dir`, read through `file_system`,
+/// followed by the `excluded_patterns`. The lines of ignore files that fail to parse are logged
+/// and skipped.
+fn ignore_patterns(
+    file_system: &dyn FileSystem,
+    manifest_dir: &Path,
+    excluded_patterns: &[String],
+) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(manifest_dir);
+    for file_name in IGNORE_FILE_NAMES {
+        let path = manifest_dir.join(file_name);
+        if !matches!(
+            entry_kind(file_system, &path),
+            Some(EntryKind::File | EntryKind::Symlink)
+        ) {
+            continue;
+        }
+        let contents = match file_system.read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!("Failed to read ignore file {:?}: {}", path, err);
+                continue;
+            }
+        };
+        for line in contents.lines() {
+            if let Err(err) = builder.add_line(Some(path.clone()), line) {
+                warn!("Failed to parse ignore file {:?}: {}", path, err);
+            }
+        }
+    }
+    for pattern in excluded_patterns {
+        if let Err(err) = builder.add_line(None, pattern) {
+            warn!("Failed to parse excluded pattern {:?}: {}", pattern, err);
+        }
+    }
+    builder.build().unwrap_or_else(|err| {
+        warn!(
+            "Failed to compile ignore patterns of {:?}: {}",
+            manifest_dir, err
+        );
+        Gitignore::empty()
+    })
+}
+
+/// Returns true if `path`, or one of its parents, is excluded by the ignore patterns.
+/// Manifests and lock files are always synced, and paths outside of the directory the
+/// patterns were compiled for are never ignored.
+fn is_ignored(ignore: &Gitignore, path: &Path, is_dir: bool) -> bool {
+    let is_manifest_or_lock = path
+        .file_name()
+        .is_some_and(|file_name| file_name == MANIFEST_FILE_NAME || file_name == LOCK_FILE_NAME);
+    !ignore.is_empty()
+        && !is_manifest_or_lock
+        && path.starts_with(ignore.path())
+        && ignore.matched_path_or_any_parents(path, is_dir).is_ignore()
+}
+
+/// Returns true if `path` is a `Forc.lock` without a `Forc.toml` next to it, e.g. left over from
+/// a removed package. It isn't synced as it would confuse the package resolution of the temp build.
+fn is_orphan_lock_file(file_system: &dyn FileSystem, path: &Path) -> bool {
+    path.file_name() == Some(OsStr::new(LOCK_FILE_NAME))
+        && !entry_kind(file_system, &path.with_file_name(MANIFEST_FILE_NAME))
+            .is_some_and(|kind| kind != EntryKind::Dir)
+}
+
+/// Returns what the entry at `path` is, or `None` if it doesn't exist.
+fn entry_kind(file_system: &dyn FileSystem, path: &Path) -> Option<EntryKind> {
+    file_system
+        .metadata(path)
+        .ok()
+        .map(|metadata| metadata.kind)
+}
+
+/// The number of files a copy must reach to be done in parallel, below which the pool overhead isn't worth it.
+const PARALLEL_COPY_THRESHOLD: usize = 64;
+
+/// How long to wait before each retry of a failed copy. Copies can fail transiently, e.g. on
+/// Windows with sharing violations while another process has the file open.
+const COPY_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(200),
+];
+
+/// Check that the copy at `target` has the same size and hash as the file at `src`, or as its
+/// contents with normalized line endings, to catch copies that were silently truncated.
+fn verify_copy(
+    file_system: &dyn FileSystem,
+    src: &Path,
+    target: &Path,
+) -> Result<(), DirectoryError> {
+    let hash = |contents: &str| {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        hasher.finish()
+    };
+    let failed = || DirectoryError::CopyVerificationFailed {
+        path: target.to_string_lossy().to_string(),
+    };
+    let source = file_system.read_to_string(src).map_err(|_| failed())?;
+    let copy = file_system.read_to_string(target).map_err(|_| failed())?;
+    let matches = |expected: &str| expected.len() == copy.len() && hash(expected) == hash(&copy);
+    if matches(&source) || matches(&normalize_line_endings(&source)) {
+        Ok(())
+    } else {
+        Err(failed())
+    }
+}
+
+/// Copy `src` to `target`, retrying after each of the [COPY_RETRY_DELAYS] if it fails.
+/// Files that don't exist, e.g. as they were removed in the meantime, aren't retried.
+fn copy_with_retry(
+    file_system: &dyn FileSystem,
+    src: &Path,
+    target: &Path,
+) -> std::io::Result<u64> {
+    let mut delays = COPY_RETRY_DELAYS.iter();
+    loop {
+        match file_system.copy(src, target) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                let Some(delay) = delays.next() else {
+                    return Err(err);
+                };
+                trace!("Retrying to copy {:?} in {:?}: {}", src, delay, err);
+                std::thread::sleep(*delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Collect the `.sw` files under `dir` the way [collect_dir_copies] walks it. The symlinks to
+/// directories are walked into as their contents end up in the temp directory through the link.
+fn collect_source_files(
+    file_system: &dyn FileSystem,
+    dir: &Path,
+    ignore: &Gitignore,
+    visited: &mut HashSet<PathBuf>,
+    source_files: &mut BTreeSet<PathBuf>,
+) -> Result<(), DirectoryError> {
+    let canonical_dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    if !visited.insert(canonical_dir) {
+        return Ok(());
+    }
+    let sources = SyncedFiles {
+        file_names: BTreeSet::new(),
+        extensions: BTreeSet::from([SWAY_EXTENSION.to_string()]),
+    };
+    for entry in file_system.read_dir(dir).map_err(sync_path_failed(dir))? {
+        if is_ignored(ignore, &entry.path, entry.kind == EntryKind::Dir) {
+            continue;
+        }
+        let is_dir = match entry.kind {
+            EntryKind::Symlink => match symlink_target(&entry.path, &sources) {
+                Some(link_target) => link_target.is_dir(),
+                None => continue,
+            },
+            kind => kind == EntryKind::Dir,
+        };
+        if is_dir {
+            collect_source_files(file_system, &entry.path, ignore, visited, source_files)?;
+        } else if sources.contains(&entry.path) {
+            source_files.insert(entry.path);
+        }
+    }
+    Ok(())
+}
+
+/// Copies only the `synced_files` from the source directory to the target directory.
+/// Symlinks are recreated as links instead of being followed, see [recreate_symlink].
+/// Paths excluded by the `ignore` patterns are skipped, and excluded directories aren't recursed into.
+/// Files are copied in parallel once there are at least [PARALLEL_COPY_THRESHOLD] of them, and the
+/// errors of all the copies that failed are aggregated into a single error.
+/// It returns the target paths of the files that were copied over, which is empty if no such files were found.
+/// If `verify` is set, the copies of the `.sw` files are checked against their source.
+fn copy_dir_contents(
+    file_system: &dyn FileSystem,
+    src_dir: impl AsRef<Path>,
+    target_dir: impl AsRef<Path>,
+    ignore: &Gitignore,
+    synced_files: &SyncedFiles,
+    verify: bool,
+) -> Result<Vec<PathBuf>, DirectoryError> {
+    // A workspace used in place is never copied onto itself.
+    if src_dir.as_ref() == target_dir.as_ref() {
+        return Ok(Vec::new());
+    }
+    let mut copies = Vec::new();
+    let copied_files = collect_dir_copies(
+        file_system,
+        src_dir.as_ref(),
+        target_dir.as_ref(),
+        ignore,
+        synced_files,
+        &mut HashSet::new(),
+        &mut copies,
+    )?;
+
+    let use_parallel = copies.len() >= PARALLEL_COPY_THRESHOLD;
+    let mut failures: Vec<DirectoryError> = CondIterator::new(&copies, use_parallel)
+        .filter_map(|(src, target)| {
+            if let Err(err) = copy_with_retry(file_system, src, target) {
+                return Some(copy_file_failed(src, target)(err));
+            }
+            let is_source = src.extension() == Some(OsStr::new(SWAY_EXTENSION));
+            (verify && is_source)
+                .then(|| verify_copy(file_system, src, target).err())
+                .flatten()
+        })
+        .collect();
+    // Every copy is attempted, the failures after the first one are only logged.
+    if !failures.is_empty() {
+        for failure in &failures[1..] {
+            error!("{}", failure);
+        }
+        return Err(failures.swap_remove(0));
+    }
+    Ok(copied_files)
+}
+
+/// Walks the source directory for [copy_dir_contents], pushing the files to copy to `copies`.
+/// The target directories are created and the symlinks recreated along the way, a target directory
+/// only being created if it has relevant files. Any directory whose canonical path was already
+/// visited is skipped so that the walk terminates even if the source contains a cycle.
+/// It returns the target paths of the files and symlinks, in the order they were found.
+fn collect_dir_copies(
+    file_system: &dyn FileSystem,
+    src_dir: &Path,
+    target_dir: &Path,
+    ignore: &Gitignore,
+    synced_files: &SyncedFiles,
+    visited: &mut HashSet<PathBuf>,
+    copies: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Vec<PathBuf>, DirectoryError> {
+    let mut copied_files = Vec::new();
+    // Directories that aren't on the real filesystem can't be canonicalized, but they also can't be
+    // reached through a symlink, so their path is good enough.
+    let canonical_dir = fs::canonicalize(src_dir).unwrap_or_else(|_| src_dir.to_path_buf());
+    if !visited.insert(canonical_dir) {
+        return Ok(copied_files);
+    }
+    let mut has_relevant_files = false;
+    let entries = file_system
+        .read_dir(src_dir)
+        .map_err(sync_path_failed(src_dir))?;
+    let has_manifest = entries
+        .iter()
+        .any(|entry| entry.kind == EntryKind::File && entry.file_name() == MANIFEST_FILE_NAME);
+    for entry in entries {
+        let path = entry.path.clone();
+        let target = target_dir.join(entry.file_name());
+        if is_ignored(ignore, &path, entry.kind == EntryKind::Dir) {
+            continue;
+        }
+        if entry.kind == EntryKind::Symlink {
+            let Some(link_target) = symlink_target(&path, synced_files) else {
+                continue;
+            };
+            if !has_relevant_files {
+                file_system
+                    .create_dir_all(target_dir)
+                    .map_err(sync_path_failed(target_dir))?;
+                has_relevant_files = true;
+            }
+            if recreate_symlink(&link_target, &target).map_err(copy_file_failed(&path, &target))? {
+                copied_files.push(target);
+            }
+        } else if entry.kind == EntryKind::Dir {
+            // Recursively walk the directory; the target directory is only created if it has relevant files
+            copied_files.extend(collect_dir_copies(
+                file_system,
+                &path,
+                &target,
+                ignore,
+                synced_files,
+                visited,
+                copies,
+            )?);
+        } else if synced_files.contains(&path)
+            && (has_manifest || entry.file_name() != LOCK_FILE_NAME)
+        {
+            if !has_relevant_files {
+                file_system
+                    .create_dir_all(target_dir)
+                    .map_err(sync_path_failed(target_dir))?;
+                has_relevant_files = true;
+            }
+            copies.push((path, target.clone()));
+            copied_files.push(target);
+        }
+    }
+    Ok(copied_files)
+}
+
+/// Returns the canonical path the symlink at `path` points to, if it should be recreated in the
+/// temp directory. Dangling links and links to files that aren't synced are ignored.
+fn symlink_target(path: &Path, synced_files: &SyncedFiles) -> Option<PathBuf> {
+    let link_target = fs::canonicalize(path).ok()?;
+    (link_target.is_dir() || synced_files.contains(path)).then_some(link_target)
+}
+
+/// Recreates a symlink at `target` pointing to `link_target`, replacing whatever is already there.
+/// The link points to the canonical path of the original target so that relative links still
+/// resolve from within the temp directory. Linked directories are never deep-copied, which also
+/// prevents infinite loops on links pointing back to an ancestor.
+///
+/// Returns false if symlinks aren't supported on this platform, in which case nothing is created.
+fn recreate_symlink(link_target: &Path, target: &Path) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        match fs::symlink_metadata(target) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(target)?,
+            Ok(_) => fs::remove_file(target)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        std::os::unix::fs::symlink(link_target, target)?;
+        Ok(true)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (link_target, target);
+        Ok(false)
+    }
+}
+
+/// Incrementally syncs the `synced_files` from the source directory to the target directory.
+/// A file is only copied if its copy is missing or the source was modified after it.
+/// Relevant files and directories of the target that no longer exist in the source, or that
+/// are now excluded by the `ignore` patterns, are removed.
+/// It returns the target paths that were copied or removed.
+///
+/// If `dry_run` is true, nothing is written and the paths that would change are returned instead.
+///
+/// The directories are walked, compared and cleaned up through `file_system`, which the files are
+/// also copied through, e.g. to normalize their line endings. Symlinks are only recreated on the
+/// real filesystem, see [recreate_symlink].
+fn sync_dir_contents(
+    file_system: &dyn FileSystem,
+    src_dir: impl AsRef<Path>,
+    target_dir: impl AsRef<Path>,
+    ignore: &Gitignore,
+    synced_files: &SyncedFiles,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, DirectoryError> {
+    let (src_dir, target_dir) = (src_dir.as_ref(), target_dir.as_ref());
+    // Syncing a workspace used in place would otherwise remove its ignored files.
+    if src_dir == target_dir {
+        return Ok(Vec::new());
+    }
+    let mut changed = Vec::new();
+    for entry in file_system
+        .read_dir(src_dir)
+        .map_err(sync_path_failed(src_dir))?
+    {
+        let path = entry.path.clone();
+        let target = target_dir.join(entry.file_name());
+        if is_ignored(ignore, &path, entry.kind == EntryKind::Dir) {
+            continue;
+        }
+        if entry.kind == EntryKind::Symlink {
+            let Some(link_target) = symlink_target(&path, synced_files) else {
+                continue;
+            };
+            if fs::read_link(&target).is_ok_and(|existing| existing == link_target) {
+                continue;
+            }
+            if dry_run {
+                changed.push(target);
+                continue;
+            }
+            file_system
+                .create_dir_all(target_dir)
+                .map_err(sync_path_failed(target_dir))?;
+            if recreate_symlink(&link_target, &target).map_err(copy_file_failed(&path, &target))? {
+                changed.push(target);
+            }
+        } else if entry.kind == EntryKind::Dir {
+            changed.extend(sync_dir_contents(
+                file_system,
+                &path,
+                &target,
+                ignore,
+                synced_files,
+                dry_run,
+            )?);
+        } else if synced_files.contains(&path)
+            && !is_orphan_lock_file(file_system, &path)
+            && is_modified_since_copy(file_system, &path, &target)
+                .map_err(copy_file_failed(&path, &target))?
+        {
+            if !dry_run {
+                // Lazily create the target directory, the same way `copy_dir_contents` does
+                file_system
+                    .create_dir_all(target_dir)
+                    .map_err(sync_path_failed(target_dir))?;
+                file_system
+                    .copy(&path, &target)
+                    .map_err(copy_file_failed(&path, &target))?;
+            }
+            changed.push(target);
+        }
+    }
+
+    // Mirror deletions from the source directory
+    if entry_kind(file_system, target_dir) == Some(EntryKind::Dir) {
+        for entry in file_system
+            .read_dir(target_dir)
+            .map_err(sync_path_failed(target_dir))?
+        {
+            let src = src_dir.join(entry.file_name());
+            if entry_kind(file_system, &src)
+                .is_some_and(|kind| !is_ignored(ignore, &src, kind == EntryKind::Dir))
+                && !is_orphan_lock_file(file_system, &src)
+            {
+                continue;
+            }
+            let target = entry.path;
+            if entry.kind == EntryKind::Dir {
+                if !dry_run {
+                    file_system
+                        .remove_dir_all(&target)
+                        .map_err(sync_path_failed(&target))?;
+                }
+                changed.push(target);
+            } else if entry.kind == EntryKind::Symlink || synced_files.contains(&target) {
+                if !dry_run {
+                    file_system
+                        .remove_file(&target)
+                        .map_err(sync_path_failed(&target))?;
+                }
+                changed.push(target);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// Map the failure to copy `src` to `dst` to a [DirectoryError::CopyFileFailed].
+fn copy_file_failed<'a>(
+    src: &'a Path,
+    dst: &'a Path,
+) -> impl FnOnce(std::io::Error) -> DirectoryError + 'a {
+    move |err| DirectoryError::CopyFileFailed {
+        src: src.to_string_lossy().to_string(),
+        dst: dst.to_string_lossy().to_string(),
+        error: err.to_string(),
+    }
+}
+
+/// Map the failure to read, create or remove `path` while syncing to a
+/// [DirectoryError::SyncPathFailed].
+fn sync_path_failed(path: &Path) -> impl FnOnce(std::io::Error) -> DirectoryError + '_ {
+    move |err| DirectoryError::SyncPathFailed {
+        path: path.to_string_lossy().to_string(),
+        err: err.to_string(),
+    }
+}
+
+/// Returns true if `target` doesn't exist or `src` was modified after it.
+fn is_modified_since_copy(
+    file_system: &dyn FileSystem,
+    src: &Path,
+    target: &Path,
+) -> std::io::Result<bool> {
+    let target_modified = match file_system.metadata(target) {
+        Ok(metadata) => metadata.modified,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+        Err(err) => return Err(err),
+    };
+    Ok(file_system.metadata(src)?.modified > target_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        document::{Documents, TextDocument},
+        file_system::{DirEntry, MemoryFileSystem, Metadata},
+    };
+
+    fn write_package(dir: &Path, name: &str, dependencies: &str) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE_NAME),
+            format!(
+                "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"{name}\"\nimplicit-std = false\n\n[dependencies]\n{dependencies}"
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("src").join("main.sw"), "library;\n").unwrap();
+    }
+
+    #[test]
+    fn syncs_each_workspace_member() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"member_a\", \"member_b\"]\n",
+        )
+        .unwrap();
+        write_package(
+            &root.join("member_a"),
+            "member_a",
+            "member_b = { path = \"../member_b\" }\n",
+        );
+        write_package(&root.join("member_b"), "member_b", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.resync().unwrap();
+
+        assert_eq!(sync.members(), vec!["member_a", "member_b"]);
+        let temp_dir = sync.temp_dir().unwrap();
+        let member_b_temp = sync.member_temp_dir("member_b").unwrap();
+        assert_eq!(member_b_temp, temp_dir.join("member_b"));
+        assert!(member_b_temp.join("src").join("main.sw").exists());
+
+        let uri = Url::from_file_path(root.join("member_b").join("src").join("main.sw")).unwrap();
+        let temp_uri = sync.workspace_to_temp_url(&uri).unwrap();
+        assert_eq!(
+            temp_uri.to_file_path().unwrap(),
+            member_b_temp.join("src").join("main.sw")
+        );
+        assert_eq!(sync.temp_to_workspace_url(&temp_uri).unwrap(), uri);
+
+        // Path dependencies between members point at the member's temp copy.
+        let temp_manifest =
+            fs::read_to_string(temp_dir.join("member_a").join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(temp_manifest.contains(&*member_b_temp.to_string_lossy()));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn resync_changed_only_syncs_modified_files() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(root.join("src").join("old.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(sync.resync_changed().unwrap().is_empty());
+
+        // Make sure the edit lands after the temp copy was made.
+        let main_path = root.join("src").join("main.sw");
+        fs::write(&main_path, "library;\n\nfn foo() {}\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&main_path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        fs::write(root.join("src").join("new.sw"), "library;\n").unwrap();
+        fs::remove_file(root.join("src").join("old.sw")).unwrap();
+
+        let mut changed = sync.resync_changed().unwrap();
+        changed.sort();
+        assert_eq!(
+            changed,
+            vec![
+                temp_dir.join("src").join("main.sw"),
+                temp_dir.join("src").join("new.sw"),
+                temp_dir.join("src").join("old.sw"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("src").join("main.sw")).unwrap(),
+            "library;\n\nfn foo() {}\n"
+        );
+        assert!(!temp_dir.join("src").join("old.sw").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn sync_changed_files_mirrors_renames() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(root.join("src").join("old.sw"), "library;\n").unwrap();
+        fs::create_dir_all(root.join("src").join("old_dir")).unwrap();
+        fs::write(
+            root.join("src").join("old_dir").join("lib.sw"),
+            "library;\n",
+        )
+        .unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_src = sync.temp_dir().unwrap().join("src");
+
+        // A rename is reported as an event for each of the old and the new path.
+        fs::rename(
+            root.join("src").join("old.sw"),
+            root.join("src").join("new.sw"),
+        )
+        .unwrap();
+        fs::rename(
+            root.join("src").join("old_dir"),
+            root.join("src").join("new_dir"),
+        )
+        .unwrap();
+        let synced = sync_changed_files(
+            &RealFileSystem,
+            &sync.copied_package_dirs().unwrap(),
+            &sync.package_dirs(),
+            &Gitignore::empty(),
+            &SyncedFiles::default(),
+            &[
+                root.join("src").join("old.sw"),
+                root.join("src").join("new.sw"),
+                root.join("src").join("old_dir"),
+                root.join("src").join("new_dir"),
+            ],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            synced,
+            vec![
+                temp_src.join("new.sw"),
+                temp_src.join("new_dir").join("lib.sw"),
+                temp_src.join("old.sw"),
+                temp_src.join("old_dir"),
+            ]
+        );
+        assert!(!temp_src.join("old.sw").exists());
+        assert!(temp_src.join("new.sw").exists());
+        assert!(!temp_src.join("old_dir").exists());
+        assert!(temp_src.join("new_dir").join("lib.sw").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn sync_changed_files_mirrors_external_edits() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(root.join("src").join("old.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        fs::create_dir_all(root.join("src").join("generated")).unwrap();
+        fs::write(
+            root.join("src").join("generated").join("new.sw"),
+            "library;\n",
+        )
+        .unwrap();
+        fs::remove_file(root.join("src").join("old.sw")).unwrap();
+        fs::write(root.join("notes.txt"), "not synced").unwrap();
+
+        let synced = sync_changed_files(
+            &RealFileSystem,
+            &sync.copied_package_dirs().unwrap(),
+            &sync.package_dirs(),
+            &Gitignore::empty(),
+            &SyncedFiles::default(),
+            &[
+                root.join("src").join("generated").join("new.sw"),
+                root.join("src").join("old.sw"),
+                root.join("notes.txt"),
+            ],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            synced,
+            vec![
+                temp_dir.join("src").join("generated").join("new.sw"),
+                temp_dir.join("src").join("old.sw"),
+            ]
+        );
+        assert!(temp_dir
+            .join("src")
+            .join("generated")
+            .join("new.sw")
+            .exists());
+        assert!(!temp_dir.join("src").join("old.sw").exists());
+        assert!(!temp_dir.join("notes.txt").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_rewrites_inline_and_full_tables() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("inline_dep"), "inline_dep", "");
+        write_package(&root.join("table_dep"), "table_dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "inline_dep = { path = \"../inline_dep\" }\n\n[dependencies.table_dep]\npath = \"../table_dep\"\n",
+        );
+        let temp_manifest_path = root.join("Forc.temp.toml");
+
+        edit_manifest_dependency_paths(
+            &RealFileSystem,
+            &root.join("project"),
+            &root.join("project").join(MANIFEST_FILE_NAME),
+            &temp_manifest_path,
+            &[],
+            None,
+        )
+        .unwrap();
+
+        let doc = fs::read_to_string(&temp_manifest_path)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        let deps = &doc["dependencies"];
+        assert_eq!(
+            deps["inline_dep"]["path"].as_str(),
+            Some(&*root.join("inline_dep").to_string_lossy())
+        );
+        assert_eq!(
+            deps["table_dep"]["path"].as_str(),
+            Some(&*root.join("table_dep").to_string_lossy())
+        );
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_replaces_temp_manifest() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep\" }\n",
+        );
+        let temp_dir = root.join("temp");
+        fs::create_dir(&temp_dir).unwrap();
+        let temp_manifest_path = temp_dir.join(MANIFEST_FILE_NAME);
+        fs::write(&temp_manifest_path, "[project").unwrap();
+
+        edit_manifest_dependency_paths(
+            &RealFileSystem,
+            &root.join("project"),
+            &root.join("project").join(MANIFEST_FILE_NAME),
+            &temp_manifest_path,
+            &[],
+            None,
+        )
+        .unwrap();
+
+        let doc = fs::read_to_string(&temp_manifest_path)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert_eq!(
+            doc["dependencies"]["dep"]["path"].as_str(),
+            Some(&*root.join("dep").to_string_lossy())
+        );
+        // The sibling file the manifest was written to was moved into place.
+        let entries: Vec<_> = fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, [MANIFEST_FILE_NAME]);
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_rewrites_patches_and_dev_dependencies() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("local_std"), "local_std", "");
+        write_package(&root.join("dev_dep"), "dev_dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "git_dep = { git = \"https://github.com/fuellabs/sway\", tag = \"v0.1.0\" }\n\n\
+             [dev-dependencies]\ndev_dep = { path = \"../dev_dep\" }\n\n\
+             [patch.'https://github.com/fuellabs/sway']\nstd = { path = \"../local_std\" }\n",
+        );
+        let temp_manifest_path = root.join("Forc.temp.toml");
+
+        edit_manifest_dependency_paths(
+            &RealFileSystem,
+            &root.join("project"),
+            &root.join("project").join(MANIFEST_FILE_NAME),
+            &temp_manifest_path,
+            &[],
+            None,
+        )
+        .unwrap();
+
+        let doc = fs::read_to_string(&temp_manifest_path)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert_eq!(
+            doc["patch"]["https://github.com/fuellabs/sway"]["std"]["path"].as_str(),
+            Some(&*root.join("local_std").to_string_lossy())
+        );
+        assert_eq!(
+            doc["dev-dependencies"]["dev_dep"]["path"].as_str(),
+            Some(&*root.join("dev_dep").to_string_lossy())
+        );
+        assert!(doc["dependencies"]["git_dep"].get("path").is_none());
+    }
+
+    #[test]
+    fn manifest_overlay_is_read_instead_of_disk() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"member_a\", \"member_b\"]\n",
+        )
+        .unwrap();
+        write_package(&root.join("member_a"), "member_a", "");
+        write_package(&root.join("member_b"), "member_b", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.resync().unwrap();
+        let manifest_path = root.join("member_a").join(MANIFEST_FILE_NAME);
+        let temp_manifest_path = sync
+            .member_temp_dir("member_a")
+            .unwrap()
+            .join(MANIFEST_FILE_NAME);
+        let member_b_temp = sync.member_temp_dir("member_b").unwrap();
+        let saved = fs::read_to_string(&manifest_path).unwrap();
+
+        // The unsaved dependency is rewritten like a saved one, and kept across resyncs.
+        let unsaved = format!("{saved}member_b = {{ path = \"../member_b\" }}\n");
+        sync.set_manifest_overlay(manifest_path.clone(), unsaved)
+            .unwrap();
+        let temp_manifest = fs::read_to_string(&temp_manifest_path).unwrap();
+        assert!(temp_manifest.contains(&*member_b_temp.to_string_lossy()));
+        sync.resync().unwrap();
+        assert_eq!(
+            fs::read_to_string(&temp_manifest_path).unwrap(),
+            temp_manifest
+        );
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), saved);
+
+        // A half-written edit keeps the previous overlay.
+        assert!(sync
+            .set_manifest_overlay(manifest_path.clone(), format!("{saved}member_b = {{"))
+            .is_err());
+        sync.resync().unwrap();
+        assert_eq!(
+            fs::read_to_string(&temp_manifest_path).unwrap(),
+            temp_manifest
+        );
+
+        sync.clear_manifest_overlay(&manifest_path).unwrap();
+        assert_eq!(fs::read_to_string(&temp_manifest_path).unwrap(), saved);
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn rewrites_dependency_paths_of_nested_manifests() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        // Only `app` is a workspace member, the packages under `libs` are copied along with the root.
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"app\"]\n",
+        )
+        .unwrap();
+        write_package(
+            &root.join("app"),
+            "app",
+            "inner = { path = \"../libs/inner\" }\n",
+        );
+        write_package(
+            &root.join("libs").join("inner"),
+            "inner",
+            "shared = { path = \"../shared\" }\n",
+        );
+        write_package(&root.join("libs").join("shared"), "shared", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        edit_manifests_dependency_paths(&RealFileSystem, &sync.package_dirs(), None).unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        let dependency_path = |dir: &Path, name: &str| {
+            let doc = fs::read_to_string(dir.join(MANIFEST_FILE_NAME))
+                .unwrap()
+                .parse::<toml_edit::DocumentMut>()
+                .unwrap();
+            doc["dependencies"][name]["path"]
+                .as_str()
+                .map(PathBuf::from)
+        };
+        assert_eq!(
+            dependency_path(&temp_dir.join("app"), "inner"),
+            Some(temp_dir.join("libs").join("inner"))
+        );
+        assert_eq!(
+            dependency_path(&temp_dir.join("libs").join("inner"), "shared"),
+            Some(temp_dir.join("libs").join("shared"))
+        );
+        // The workspace's own manifests are left untouched.
+        assert_eq!(
+            dependency_path(&root.join("libs").join("inner"), "shared"),
+            Some(PathBuf::from("../shared"))
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    /// A [FileSystem] whose first copies fail, as if another process had the files open.
+    #[derive(Debug)]
+    struct FlakyFileSystem {
+        file_system: MemoryFileSystem,
+        failures: AtomicUsize,
+    }
+
+    impl FileSystem for FlakyFileSystem {
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<DirEntry>> {
+            self.file_system.read_dir(path)
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "sharing violation",
+                ));
+            }
+            self.file_system.copy(from, to)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.create_dir_all(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            self.file_system.read_to_string(path)
+        }
+
+        fn write(&self, path: &Path, contents: &str) -> std::io::Result<()> {
+            self.file_system.write(path, contents)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.remove_dir_all(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.remove_file(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<Metadata> {
+            self.file_system.metadata(path)
+        }
+    }
+
+    #[test]
+    fn copy_dir_contents_retries_failed_copies() {
+        let flaky = |failures| FlakyFileSystem {
+            file_system: MemoryFileSystem::with_files([("/workspace/src/main.sw", "library;\n")]),
+            failures: AtomicUsize::new(failures),
+        };
+        let copy = |file_system: &FlakyFileSystem| {
+            copy_dir_contents(
+                file_system,
+                "/workspace",
+                "/temp",
+                &Gitignore::empty(),
+                &SyncedFiles::default(),
+                false,
+            )
+        };
+
+        let file_system = flaky(COPY_RETRY_DELAYS.len());
+        assert_eq!(
+            copy(&file_system).unwrap(),
+            [PathBuf::from("/temp/src/main.sw")]
+        );
+        assert_eq!(
+            file_system
+                .read_to_string(Path::new("/temp/src/main.sw"))
+                .unwrap(),
+            "library;\n"
+        );
+
+        // The error is returned once the retries are exhausted, along with the failing paths.
+        let file_system = flaky(COPY_RETRY_DELAYS.len() + 1);
+        assert!(matches!(
+            copy(&file_system),
+            Err(DirectoryError::CopyFileFailed { src, dst, .. })
+                if src == "/workspace/src/main.sw" && dst == "/temp/src/main.sw"
+        ));
+    }
+
+    #[test]
+    fn temp_root_and_ignore_files_go_through_the_file_system() {
+        let file_system = MemoryFileSystem::with_files([
+            ("/workspace/.gitignore", "# generated\nout/\n"),
+            ("/temp/forc_lsp_stale/project/src/main.sw", "library;\n"),
+        ]);
+        let ignore = ignore_patterns(&file_system, Path::new("/workspace"), &[]);
+        assert!(is_ignored(&ignore, Path::new("/workspace/out"), true));
+        assert!(!is_ignored(&ignore, Path::new("/workspace/src"), true));
+
+        // The stale temp root is replaced by an empty one.
+        let temp_root =
+            create_deterministic_temp_root(&file_system, Path::new("/temp"), "forc_lsp_stale")
+                .unwrap();
+        assert_eq!(temp_root, PathBuf::from("/temp/forc_lsp_stale"));
+        assert_eq!(entry_kind(&file_system, &temp_root), Some(EntryKind::Dir));
+        assert!(file_system.read_dir(&temp_root).unwrap().is_empty());
+        assert!(create_deterministic_temp_root(
+            &file_system,
+            Path::new("/missing"),
+            "forc_lsp_stale"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn sync_changed_files_reports_the_failing_copy() {
+        let file_system = FlakyFileSystem {
+            file_system: MemoryFileSystem::with_files([("/workspace/src/main.sw", "library;\n")]),
+            failures: AtomicUsize::new(usize::MAX),
+        };
+        let result = sync_changed_files(
+            &file_system,
+            &[PackageDirs {
+                manifest_dir: PathBuf::from("/workspace"),
+                temp_dir: PathBuf::from("/temp"),
+            }],
+            &[],
+            &Gitignore::empty(),
+            &SyncedFiles::default(),
+            &[PathBuf::from("/workspace/src/main.sw")],
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(LanguageServerError::DirectoryError(DirectoryError::CopyFileFailed { src, dst, error }))
+                if src == "/workspace/src/main.sw"
+                    && dst == "/temp/src/main.sw"
+                    && error == "sharing violation"
+        ));
+    }
+
+    #[test]
+    fn copy_dir_contents_verifies_copied_sources() {
+        let file_system = Arc::new(MemoryFileSystem::with_files([
+            ("/workspace/src/main.sw", "library;\r\n"),
+            ("/workspace/src/lib.sw", "library;\n"),
+        ]));
+        let normalizing = LineEndingsFileSystem {
+            file_system: file_system.clone(),
+        };
+        let copied = copy_dir_contents(
+            &normalizing,
+            "/workspace",
+            "/temp",
+            &Gitignore::empty(),
+            &SyncedFiles::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(copied.len(), 2);
+
+        // A truncated copy is caught.
+        let copy = Path::new("/temp/src/main.sw");
+        file_system.write(copy, "libr").unwrap();
+        assert_eq!(
+            verify_copy(&*file_system, Path::new("/workspace/src/main.sw"), copy),
+            Err(DirectoryError::CopyVerificationFailed {
+                path: "/temp/src/main.sw".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn syncs_through_the_given_file_system() {
+        let manifest = "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"project\"\nimplicit-std = false\n\n[dependencies]\ndep = { path = \"/deps/dep\" }\n";
+        let file_system = Arc::new(MemoryFileSystem::with_files([
+            ("/workspace/Forc.toml", manifest),
+            ("/workspace/src/main.sw", "library;\n"),
+            ("/workspace/notes.txt", "not synced"),
+        ]));
+        let sync = SyncWorkspace::new().with_file_system(file_system.clone());
+        let temp_dir = &PathBuf::from(format!(
+            "/{}_{}_test/project",
+            SyncWorkspace::LSP_TEMP_PREFIX,
+            sync.session_id
+        ));
+
+        let mut copied = copy_dir_contents(
+            &*file_system,
+            "/workspace",
+            temp_dir,
+            &Gitignore::empty(),
+            &SyncedFiles::default(),
+            false,
+        )
+        .unwrap();
+        copied.sort();
+        assert_eq!(
+            copied,
+            [
+                temp_dir.join(MANIFEST_FILE_NAME),
+                temp_dir.join("src/main.sw")
+            ]
+        );
+
+        let dep_dirs = PackageDirs {
+            manifest_dir: PathBuf::from("/deps/dep"),
+            temp_dir: PathBuf::from("/temp/dep"),
+        };
+        edit_manifest_dependency_paths(
+            &*file_system,
+            Path::new("/workspace"),
+            Path::new("/workspace/Forc.toml"),
+            &temp_dir.join(MANIFEST_FILE_NAME),
+            &[dep_dirs],
+            None,
+        )
+        .unwrap();
+        let doc = file_system
+            .read_to_string(&temp_dir.join(MANIFEST_FILE_NAME))
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert_eq!(
+            doc["dependencies"]["dep"]["path"].as_str(),
+            Some("/temp/dep")
+        );
+
+        sync.insert_directory(Directory::Temp, temp_dir.to_path_buf());
+        sync.remove_temp_dir();
+        let files: Vec<_> = file_system
+            .files()
+            .into_iter()
+            .map(|(path, _contents)| path)
+            .collect();
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("/workspace/Forc.toml"),
+                PathBuf::from("/workspace/notes.txt"),
+                PathBuf::from("/workspace/src/main.sw"),
+            ]
+        );
+    }
+
+    #[test]
+    fn resyncs_through_the_given_file_system() {
+        let manifest = "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"project\"\nimplicit-std = false\n";
+        let file_system = Arc::new(MemoryFileSystem::with_files([
+            ("/workspace/Forc.toml", manifest),
+            ("/workspace/src/main.sw", "library;\n"),
+            ("/workspace/src/old.sw", "library;\n"),
+        ]));
+        let sync = SyncWorkspace::new().with_file_system(file_system.clone());
+        let temp_dir = PathBuf::from("/temp/project");
+        sync.insert_directory(Directory::Manifest, PathBuf::from("/workspace"));
+        sync.insert_directory(Directory::Temp, temp_dir.clone());
+        sync.resync().unwrap();
+        assert_eq!(
+            file_system
+                .read_to_string(&temp_dir.join("src/old.sw"))
+                .unwrap(),
+            "library;\n"
+        );
+
+        file_system
+            .write(
+                Path::new("/workspace/src/main.sw"),
+                "library;\n\nfn foo() {}\n",
+            )
+            .unwrap();
+        file_system
+            .remove_file(Path::new("/workspace/src/old.sw"))
+            .unwrap();
+        sync.resync().unwrap();
+        let files: Vec<_> = file_system
+            .files()
+            .into_iter()
+            .filter(|(path, _contents)| path.starts_with(&temp_dir))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                (temp_dir.join(MANIFEST_FILE_NAME), manifest.to_string()),
+                (
+                    temp_dir.join("src/main.sw"),
+                    "library;\n\nfn foo() {}\n".to_string()
+                ),
+            ]
+        );
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_points_at_invalid_dependency() {
+        let project = "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"project\"\nimplicit-std = false\n\n";
+        let edit = |dependencies: &str| {
+            let file_system = MemoryFileSystem::with_files([
+                ("/workspace/Forc.toml", &*format!("{project}{dependencies}")),
+                ("/temp/.keep", ""),
+            ]);
+            edit_manifest_dependency_paths(
+                &file_system,
+                Path::new("/workspace"),
+                Path::new("/workspace/Forc.toml"),
+                Path::new("/temp/Forc.toml"),
+                &[],
+                None,
+            )
+            .unwrap_err()
+        };
+
+        let err = edit("[dependencies]\nvalid = { path = \"/valid\" }\ninvalid = { path = 1 }\n");
+        let LanguageServerError::DocumentError(DocumentError::InvalidManifestKey {
+            path,
+            key,
+            line,
+            ..
+        }) = err
+        else {
+            panic!("expected an invalid key error, got {err:?}");
+        };
+        assert_eq!(
+            (path.as_str(), key.as_str(), line),
+            ("/workspace/Forc.toml", "dependencies.invalid", 10)
+        );
+
+        // Dependencies that deserialize but fail validation are reported too.
+        let err = edit("[dependencies]\nstd = { path = \"/std\", tag = \"v0.1.0\" }\n");
+        let LanguageServerError::DocumentError(DocumentError::InvalidManifestKey {
+            key,
+            line,
+            error,
+            ..
+        }) = err
+        else {
+            panic!("expected an invalid key error, got {err:?}");
+        };
+        assert_eq!(key, "dependencies.std");
+        assert_eq!(line, 9);
+        assert_eq!(
+            error,
+            "Details reserved for git sources used without a git field"
+        );
+    }
+
+    #[test]
+    fn dependencies_outside_the_dependency_root_are_only_warned_about() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("outside"), "outside", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "outside = { path = \"../outside\" }\n",
+        );
+
+        let dependency = root.join("outside");
+        assert_eq!(
+            check_dependency_root(
+                Some(&root.join("project")),
+                &root.join("project"),
+                "outside",
+                &dependency
+            ),
+            Err(DirectoryError::DependencyOutsideWorkspace {
+                dependency: "outside".to_string(),
+                path: dependency.to_string_lossy().to_string(),
+                manifest_dir: root.join("project").to_string_lossy().to_string(),
+                root: root.join("project").to_string_lossy().to_string(),
+            })
+        );
+        assert_eq!(
+            check_dependency_root(Some(&root), &root.join("project"), "outside", &dependency),
+            Ok(())
+        );
+        assert_eq!(
+            check_dependency_root(None, &root.join("project"), "outside", &dependency),
+            Ok(())
+        );
+
+        // The dependency is still rewritten.
+        let sync = SyncWorkspace::new().with_dependency_root(root.join("project"));
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        sync.resync().unwrap();
+        let temp_manifest =
+            fs::read_to_string(sync.temp_dir().unwrap().join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(temp_manifest.contains(&*dependency.to_string_lossy()));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn dependency_kind_tells_sources_apart() {
+        let kinds: BTreeMap<String, DependencyKind> =
+            toml_edit::de::from_str::<BTreeMap<String, forc_pkg::manifest::Dependency>>(
+                "path_dep = { path = \"../dep\" }\n\
+             git_dep = { git = \"https://github.com/fuellabs/sway\", tag = \"v0.1.0\" }\n\
+             ipfs_dep = { ipfs = \"QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG\" }\n\
+             simple_dep = \"0.1.0\"\n\
+             versioned_dep = { version = \"0.1.0\" }\n",
+            )
+            .unwrap()
+            .into_iter()
+            .map(|(name, dependency)| (name, DependencyKind::of(&dependency)))
+            .collect();
+        assert_eq!(
+            kinds,
+            BTreeMap::from([
+                ("git_dep".to_string(), DependencyKind::Git),
+                ("ipfs_dep".to_string(), DependencyKind::Ipfs),
+                ("path_dep".to_string(), DependencyKind::Path),
+                ("simple_dep".to_string(), DependencyKind::Registry),
+                ("versioned_dep".to_string(), DependencyKind::Registry),
+            ])
+        );
+    }
+
+    #[test]
+    fn process_package_dependencies_reports_mismatched_dependencies() {
+        let deps = |toml: &str| {
+            toml_edit::de::from_str::<BTreeMap<String, forc_pkg::manifest::Dependency>>(toml)
+                .unwrap()
+        };
+        let process = |deps: Option<&BTreeMap<_, _>>, doc: &str| {
+            let mut doc: toml_edit::DocumentMut = doc.parse().unwrap();
+            let mismatch = process_package_dependencies(
+                Path::new("/project"),
+                deps,
+                &mut doc,
+                &[],
+                Some(&mut Vec::new()),
+                None,
+            );
+            (mismatch, doc.to_string())
+        };
+        let project = "[project]\nname = \"project\"\n";
+
+        // No dependencies at all.
+        assert_eq!(process(None, project), (None, project.to_string()));
+        assert_eq!(
+            process(Some(&BTreeMap::new()), project),
+            (None, project.to_string())
+        );
+
+        // Dependencies only in the parsed manifest. The implicit `std` has nothing to rewrite.
+        let std = deps("std = { git = \"https://github.com/fuellabs/sway\", tag = \"v0.1.0\" }");
+        assert_eq!(process(Some(&std), project), (None, project.to_string()));
+        let path_dep = deps("dep = { path = \"../dep\" }");
+        assert_eq!(
+            process(Some(&path_dep), project),
+            (Some(DependenciesMismatch::ParsedOnly), project.to_string())
+        );
+
+        // Dependencies only in the document.
+        let doc = format!("{project}\n[dependencies]\ndep = {{ path = \"../dep\" }}\n");
+        assert_eq!(
+            process(None, &doc),
+            (Some(DependenciesMismatch::RawOnly), doc.clone())
+        );
+        let empty = format!("{project}\n[dependencies]\n");
+        assert_eq!(process(None, &empty), (None, empty.clone()));
+
+        // Both agree, so the path is rewritten.
+        let (mismatch, rewritten) = process(Some(&path_dep), &doc);
+        assert_eq!(mismatch, None);
+        assert!(
+            rewritten.contains("dep = { path = \"/project/../dep\" }"),
+            "{rewritten}"
+        );
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_without_dependencies_table() {
+        let file_system = MemoryFileSystem::with_files([
+            (
+                "/project/Forc.toml",
+                "[project]\nname = \"project\"\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nimplicit-std = false\n",
+            ),
+            ("/temp/project/Forc.toml", ""),
+        ]);
+        edit_manifest_dependency_paths(
+            &file_system,
+            Path::new("/project"),
+            Path::new("/project/Forc.toml"),
+            Path::new("/temp/project/Forc.toml"),
+            &[],
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            file_system
+                .read_to_string(Path::new("/temp/project/Forc.toml"))
+                .unwrap(),
+            file_system
+                .read_to_string(Path::new("/project/Forc.toml"))
+                .unwrap()
+        );
+    }
+
+    /// A [FileSystem] counting the files written to it.
+    #[derive(Debug, Default)]
+    struct CountingFileSystem {
+        file_system: MemoryFileSystem,
+        writes: AtomicUsize,
+    }
+
+    impl FileSystem for CountingFileSystem {
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<DirEntry>> {
+            self.file_system.read_dir(path)
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+            self.file_system.copy(from, to)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.create_dir_all(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            self.file_system.read_to_string(path)
+        }
+
+        fn write(&self, path: &Path, contents: &str) -> std::io::Result<()> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            self.file_system.write(path, contents)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.remove_dir_all(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+            self.file_system.remove_file(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<Metadata> {
+            self.file_system.metadata(path)
+        }
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_skips_writing_unchanged_manifest() {
+        let file_system = CountingFileSystem {
+            file_system: MemoryFileSystem::with_files([
+                (
+                    "/project/Forc.toml",
+                    "[project]\nname = \"project\"\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nimplicit-std = false\n\n[dependencies]\ndep = { path = \"../dep\" }\n",
+                ),
+                ("/temp/project/.keep", ""),
+            ]),
+            writes: AtomicUsize::new(0),
+        };
+        let edit = || {
+            edit_manifest_dependency_paths(
+                &file_system,
+                Path::new("/project"),
+                Path::new("/project/Forc.toml"),
+                Path::new("/temp/project/Forc.toml"),
+                &[],
+                None,
+            )
+            .unwrap()
+        };
+
+        edit();
+        assert_eq!(file_system.writes.load(Ordering::SeqCst), 1);
+        let temp_manifest = file_system
+            .read_to_string(Path::new("/temp/project/Forc.toml"))
+            .unwrap();
+        edit();
+        assert_eq!(file_system.writes.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            file_system
+                .read_to_string(Path::new("/temp/project/Forc.toml"))
+                .unwrap(),
+            temp_manifest
+        );
+
+        // A changed manifest is written again.
+        file_system
+            .file_system
+            .write(
+                Path::new("/project/Forc.toml"),
+                "[project]\nname = \"project\"\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nimplicit-std = false\n",
+            )
+            .unwrap();
+        edit();
+        assert_eq!(file_system.writes.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn remove_temp_dir_removes_populated_temp_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.resync().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join("src").join("main.sw").exists());
+
+        sync.remove_temp_dir();
+        assert!(!temp_dir.parent().unwrap().exists());
+    }
+
+    #[test]
+    fn clone_manifest_dir_to_temp_returns_copied_files() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs").join("README.md"), "not synced").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let mut copied = sync.clone_manifest_dir_to_temp().unwrap();
+        copied.sort();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(
+            copied,
+            vec![
+                temp_dir.join(MANIFEST_FILE_NAME),
+                temp_dir.join("src").join("main.sw"),
+            ]
+        );
+        // Directories without relevant files aren't created.
+        assert!(!temp_dir.join("docs").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn clone_manifest_dir_to_temp_recreates_self_referential_symlink() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let src = root.join("src");
+        std::os::unix::fs::symlink(&src, src.join("loop")).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let copied = sync.clone_manifest_dir_to_temp().unwrap();
+
+        let temp_link = sync.temp_dir().unwrap().join("src").join("loop");
+        assert!(copied.contains(&temp_link));
+        assert!(fs::symlink_metadata(&temp_link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_link(&temp_link).unwrap(), src);
+
+        // Resyncing leaves the already recreated link untouched.
+        assert!(sync.resync_changed().unwrap().is_empty());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn syncs_bump_the_sync_generation_in_the_temp_root() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        assert_eq!(sync.sync_generation(), SyncGeneration::default());
+        let read_generation = || {
+            fs::read_to_string(
+                sync.temp_root()
+                    .unwrap()
+                    .join(SyncWorkspace::SYNC_GENERATION_FILE_NAME),
+            )
+            .unwrap()
+            .parse::<SyncGeneration>()
+            .unwrap()
+        };
+
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let synced = SyncGeneration {
+            generation: 2,
+            syncing: false,
+        };
+        assert_eq!(sync.sync_generation(), synced);
+        assert_eq!(read_generation(), synced);
+
+        let tracker = sync.sync_tracker();
+        let running = tracker.begin();
+        let syncing = SyncGeneration {
+            generation: 2,
+            syncing: true,
+        };
+        assert_eq!(sync.sync_generation(), syncing);
+        assert_eq!(read_generation(), syncing);
+        // A sync nested in a running one bumps the generation, but the temp root is marked as
+        // syncing until the outer one ends.
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let nested = SyncGeneration {
+            generation: 3,
+            syncing: true,
+        };
+        assert_eq!(sync.sync_generation(), nested);
+        assert_eq!(read_generation(), syncing);
+        drop(running);
+        let synced = SyncGeneration {
+            generation: 3,
+            syncing: false,
+        };
+        assert_eq!(read_generation(), synced);
+
+        // A failed sync leaves the generation as it was.
+        fs::write(root.join(MANIFEST_FILE_NAME), "[project").unwrap();
+        assert!(sync.resync().is_err());
+        assert_eq!(read_generation(), synced);
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn generation_is_bumped_by_successful_syncs() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        assert_eq!(sync.generation(), 0);
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert_eq!(sync.generation(), 1);
+        sync.resync().unwrap();
+        assert_eq!(sync.generation(), 2);
+
+        // A failed resync leaves the generation as it was.
+        fs::write(root.join(MANIFEST_FILE_NAME), "[project").unwrap();
+        assert!(sync.resync().is_err());
+        assert_eq!(sync.generation(), 2);
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn sync_generation_is_not_written_next_to_given_temp_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        let temp_dir = root.join("temp").join("project");
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let sync = SyncWorkspace::with_directories(root.join("project"), temp_dir);
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert_eq!(sync.sync_generation().generation, 1);
+        assert!(!root
+            .join("temp")
+            .join(SyncWorkspace::SYNC_GENERATION_FILE_NAME)
+            .exists());
+    }
+
+    #[test]
+    fn excluded_patterns_leave_tests_out_of_the_temp_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(root.join("tests").join("harness.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new().with_excluded_patterns(vec!["tests/".to_string()]);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join(MANIFEST_FILE_NAME).exists());
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        assert!(!temp_dir.join("tests").exists());
+        sync.remove_temp_dir();
+
+        // Nothing is excluded by default.
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert!(sync
+            .temp_dir()
+            .unwrap()
+            .join("tests")
+            .join("harness.sw")
+            .exists());
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn source_dirs_only_leaves_files_outside_src_out_of_the_temp_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let lock = "[[package]]\nname = \"project\"\nsource = \"member\"\n";
+        fs::write(root.join(LOCK_FILE_NAME), lock).unwrap();
+        fs::write(root.join("scratch.sw"), "library;\n").unwrap();
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("scripts").join("tool.sw"), "library;\n").unwrap();
+        fs::create_dir_all(root.join("src").join("nested")).unwrap();
+        fs::write(root.join("src").join("nested").join("lib.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new().with_source_dirs_only(true);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join(MANIFEST_FILE_NAME).exists());
+        assert!(temp_dir.join(LOCK_FILE_NAME).exists());
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        assert!(temp_dir.join("src").join("nested").join("lib.sw").exists());
+        assert!(!temp_dir.join("scratch.sw").exists());
+        assert!(!temp_dir.join("scripts").exists());
+        sync.remove_temp_dir();
+
+        // The whole directory is copied by default.
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert!(sync.temp_dir().unwrap().join("scratch.sw").exists());
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn source_dirs_only_keeps_the_source_dirs_of_workspace_members() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"member_a\", \"packages/member_b\"]\n",
+        )
+        .unwrap();
+        write_package(&root.join("member_a"), "member_a", "");
+        write_package(&root.join("packages").join("member_b"), "member_b", "");
+        fs::write(root.join("member_a").join("scratch.sw"), "library;\n").unwrap();
+        fs::write(root.join("packages").join("notes.sw"), "library;\n").unwrap();
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("scripts").join("tool.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new().with_source_dirs_only(true);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join(MANIFEST_FILE_NAME).exists());
+        for member in [
+            Path::new("member_a"),
+            &Path::new("packages").join("member_b"),
+        ] {
+            assert!(temp_dir.join(member).join(MANIFEST_FILE_NAME).exists());
+            assert!(temp_dir.join(member).join("src").join("main.sw").exists());
+        }
+        assert!(!temp_dir.join("member_a").join("scratch.sw").exists());
+        assert!(!temp_dir.join("packages").join("notes.sw").exists());
+        assert!(!temp_dir.join("scripts").exists());
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn source_files_lists_sway_files_without_copying() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::create_dir_all(root.join("src").join("nested")).unwrap();
+        fs::write(root.join("src").join("nested").join("lib.sw"), "library;\n").unwrap();
+        fs::write(root.join("src").join("notes.md"), "").unwrap();
+        fs::create_dir_all(root.join("generated")).unwrap();
+        fs::write(root.join("generated").join("gen.sw"), "library;\n").unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        assert_eq!(
+            sync.source_files().unwrap(),
+            vec![
+                root.join("src").join("main.sw"),
+                root.join("src").join("nested").join("lib.sw"),
+            ]
+        );
+        assert!(!sync.temp_dir().unwrap().exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn resync_plan_reports_changes_without_writing() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep\" }\nmissing = { path = \"../missing\" }\n",
+        );
+        let project = root.join("project");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&project).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        let temp_manifest = fs::read_to_string(temp_dir.join(MANIFEST_FILE_NAME)).unwrap();
+        fs::write(project.join("src").join("new.sw"), "library;\n").unwrap();
+
+        let plan = sync.resync_plan().unwrap();
+        assert_eq!(
+            plan.changed_files,
+            vec![temp_dir.join("src").join("new.sw")]
+        );
+        assert_eq!(
+            plan.rewritten_manifests,
+            vec![temp_dir.join(MANIFEST_FILE_NAME)]
+        );
+        assert_eq!(
+            plan.warnings,
+            vec![DependencyPathWarning {
+                manifest_dir: project.clone(),
+                dependency: "missing".to_string(),
+                path: project.join("../missing"),
+            }]
+        );
+
+        // Nothing was written to the temp directory.
+        assert!(!temp_dir.join("src").join("new.sw").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.join(MANIFEST_FILE_NAME)).unwrap(),
+            temp_manifest
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn temp_to_workspace_spans_converts_temp_spans_only() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let source_engine = SourceEngine::default();
+        let temp_source_id =
+            source_engine.get_source_id(&sync.temp_dir().unwrap().join("src").join("main.sw"));
+        let dependency_source_id = source_engine.get_source_id(&root.join("dep.sw"));
+        let src = sway_types::span::Source::new("library;\n");
+        let spans = [
+            Span::new(src.clone(), 0, 7, Some(temp_source_id)).unwrap(),
+            Span::new(src.clone(), 0, 8, Some(dependency_source_id)).unwrap(),
+            Span::new(src, 1, 3, Some(temp_source_id)).unwrap(),
+        ];
+
+        let converted = sync
+            .temp_to_workspace_spans(&source_engine, &spans)
+            .unwrap();
+        let workspace_source_id = source_engine.get_source_id(&root.join("src").join("main.sw"));
+        assert_eq!(converted.len(), 3);
+        assert_eq!(converted[0].source_id(), Some(&workspace_source_id));
+        assert_eq!((converted[0].start(), converted[0].end()), (0, 7));
+        assert_eq!(converted[1], spans[1]);
+        assert_eq!(converted[2].source_id(), Some(&workspace_source_id));
+        assert_eq!(
+            sync.temp_to_workspace_span(&source_engine, &spans[2])
+                .unwrap(),
+            converted[2]
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn temp_to_workspace_spans_recompute_offsets_if_the_contents_differ() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let workspace_text = "library;\n\nfn f() {}\n";
+        fs::write(root.join("src").join("main.sw"), workspace_text).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        // The temp copy was compiled with other line endings, shifting the offsets on later lines.
+        let source_engine = SourceEngine::default();
+        let temp_source_id =
+            source_engine.get_source_id(&sync.temp_dir().unwrap().join("src").join("main.sw"));
+        let temp_text = "library;\r\n\r\nfn f() {}";
+        let span = Span::new(temp_text.into(), 0, temp_text.len(), Some(temp_source_id)).unwrap();
+
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        let workspace_source_id = source_engine.get_source_id(&root.join("src").join("main.sw"));
+        assert_eq!(converted.source_id(), Some(&workspace_source_id));
+        assert_eq!(converted.src().text.as_ref(), workspace_text);
+        assert_eq!(converted.as_str(), "library;\n\nfn f() {}");
+        assert_eq!(
+            converted.line_col_one_index().to_string(),
+            span.line_col_one_index().to_string()
+        );
+
+        // Identical contents keep the offsets and the text of the span.
+        let span = Span::new(workspace_text.into(), 10, 14, Some(temp_source_id)).unwrap();
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!((converted.start(), converted.end()), (10, 14));
+        assert_eq!(converted.as_str(), "fn f");
+
+        // The comparison is cached for the spans in the same temp text.
+        fs::write(
+            root.join("src").join("main.sw"),
+            "library;\r\n\r\nfn f() {}",
+        )
+        .unwrap();
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!(converted.src().text.as_ref(), workspace_text);
+        let span = Span::new(workspace_text.into(), 10, 14, Some(temp_source_id)).unwrap();
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!((converted.start(), converted.end()), (12, 16));
+        assert_eq!(converted.as_str(), "fn f");
+
+        // Other differences mean the span is in newer contents, which it stays in.
+        let temp_text = "library;\n\nfn g() {}\n";
+        let span = Span::new(temp_text.into(), 10, 14, Some(temp_source_id)).unwrap();
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!(converted.source_id(), Some(&workspace_source_id));
+        assert_eq!(converted.src().text.as_ref(), temp_text);
+        assert_eq!(converted.as_str(), "fn g");
+
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn temp_to_workspace_spans_read_open_documents_from_the_document_store() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        // The unsaved edits are written to the temp copy only, and compiled from there.
+        let temp_path = sync.temp_dir().unwrap().join("src").join("main.sw");
+        let buffer = "library;\n\nfn f() {}\n";
+        fs::write(&temp_path, buffer).unwrap();
+        let source_engine = SourceEngine::default();
+        let temp_source_id = source_engine.get_source_id(&temp_path);
+        let span = Span::new(buffer.into(), 10, 14, Some(temp_source_id)).unwrap();
+
+        // Without the buffer, the span isn't moved into the saved file, which doesn't have it.
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!(converted.src().text.as_ref(), buffer);
+        assert_eq!(converted.as_str(), "fn f");
+
+        let documents = Arc::new(Documents::new());
+        documents
+            .store_document(
+                TextDocument::build_from_path(temp_path.to_str().unwrap())
+                    .await
+                    .unwrap(),
+            )
+            .unwrap();
+        sync.set_document_store(documents);
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        let workspace_source_id = source_engine.get_source_id(&root.join("src").join("main.sw"));
+        assert_eq!(converted.source_id(), Some(&workspace_source_id));
+        assert_eq!((converted.start(), converted.end()), (10, 14));
+        assert_eq!(converted.as_str(), "fn f");
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn direct_mode_makes_the_conversions_the_identity() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new().with_direct_mode(true);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let workspace_path = root.join("src").join("main.sw");
+        let workspace_url = get_url_from_path(&workspace_path).unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_url(&workspace_url).unwrap(),
+            workspace_url
+        );
+        assert_eq!(
+            sync.temp_to_workspace_url(&workspace_url).unwrap(),
+            workspace_url
+        );
+
+        let source_engine = SourceEngine::default();
+        let source_id = source_engine.get_source_id(&workspace_path);
+        let span = Span::new(
+            sway_types::span::Source::new("library;\n"),
+            0,
+            7,
+            Some(source_id),
+        )
+        .unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_span(&source_engine, &span).unwrap(),
+            span
+        );
+        assert_eq!(
+            sync.temp_to_workspace_span(&source_engine, &span).unwrap(),
+            span
+        );
+
+        // Turning it off converts through the temp directory again.
+        sync.set_direct_mode(false);
+        assert_eq!(
+            sync.workspace_to_temp_url(&workspace_url).unwrap(),
+            get_url_from_path(&sync.temp_dir().unwrap().join("src").join("main.sw")).unwrap()
+        );
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn temp_to_workspace_spans_passes_dependency_spans_through() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        // A path dependency whose sources live in the temp directory, next to the project.
+        let dependency_dir = sync.temp_root().unwrap().join("dep");
+        write_package(&dependency_dir, "dep", "");
+
+        let source_engine = SourceEngine::default();
+        let dependency_source_id =
+            source_engine.get_source_id(&dependency_dir.join("src").join("main.sw"));
+        let project_source_id =
+            source_engine.get_source_id(&sync.temp_dir().unwrap().join("src").join("main.sw"));
+        let src = sway_types::span::Source::new("library;\n");
+        let dependency_span = Span::new(src.clone(), 0, 7, Some(dependency_source_id)).unwrap();
+        let project_span = Span::new(src, 0, 7, Some(project_source_id)).unwrap();
+
+        assert_eq!(
+            sync.temp_to_workspace_span(&source_engine, &dependency_span)
+                .unwrap(),
+            dependency_span
+        );
+        assert_eq!(
+            sync.temp_to_workspace_span(&source_engine, &project_span)
+                .unwrap()
+                .source_id(),
+            Some(&source_engine.get_source_id(&root.join("src").join("main.sw")))
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn edit_manifest_dependency_paths_skips_missing_path_dependency() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "missing = { path = \"../missing\" }\ndep = { path = \"../dep\" }\n",
+        );
+        let temp_manifest_path = root.join("Forc.temp.toml");
+
+        edit_manifest_dependency_paths(
+            &RealFileSystem,
+            &root.join("project"),
+            &root.join("project").join(MANIFEST_FILE_NAME),
+            &temp_manifest_path,
+            &[],
+            None,
+        )
+        .unwrap();
+
+        let doc = fs::read_to_string(&temp_manifest_path)
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        let deps = &doc["dependencies"];
+        assert_eq!(
+            deps["dep"]["path"].as_str(),
+            Some(&*root.join("dep").to_string_lossy())
+        );
+        assert_eq!(
+            deps["missing"]["path"].as_str(),
+            Some(&*root.join("project").join("../missing").to_string_lossy())
+        );
+    }
+
+    #[test]
+    fn create_temp_dir_from_workspace_uses_temp_root() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        let temp_root = root.join("temp");
+        fs::create_dir_all(&temp_root).unwrap();
+
+        let sync = SyncWorkspace::with_temp_root(Some(temp_root.clone()));
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.ends_with("project"));
+        let lsp_temp_dir = temp_dir.parent().unwrap();
+        assert_eq!(lsp_temp_dir.parent(), Some(temp_root.as_path()));
+        assert!(lsp_temp_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(SyncWorkspace::LSP_TEMP_PREFIX));
+
+        sync.remove_temp_dir();
+        assert!(!lsp_temp_dir.exists());
+    }
+
+    /// Write `contents` to `path` and make sure it lands after the temp copy was made.
+    fn write_newer(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn resync_async_syncs_unless_cancelled() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let main_path = root.join("src").join("main.sw");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_main_path = sync.temp_dir().unwrap().join("src").join("main.sw");
+
+        write_newer(&main_path, "library;\n\nfn foo() {}\n");
+        sync.resync_async(CancellationToken::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&temp_main_path).unwrap(),
+            "library;\n\nfn foo() {}\n"
+        );
+        let generation = sync.generation();
+
+        write_newer(&main_path, "library;\n\nfn bar() {}\n");
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = sync.resync_async(cancel).await.unwrap();
+        assert!(matches!(result, Err(LanguageServerError::ResyncCancelled)));
+        assert_eq!(
+            fs::read_to_string(&temp_main_path).unwrap(),
+            "library;\n\nfn foo() {}\n"
+        );
+        assert_eq!(sync.generation(), generation);
+
+        sync.remove_temp_dir();
+    }
+
+    /// A [FileSystem] cancelling `cancel` once it copied a file.
+    #[derive(Debug)]
+    struct CancelOnCopyFileSystem {
+        cancel: CancellationToken,
+    }
+
+    impl FileSystem for CancelOnCopyFileSystem {
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<DirEntry>> {
+            RealFileSystem.read_dir(path)
+        }
+
+        fn copy(&self, from: &Path, to: &Path) -> std::io::Result<u64> {
+            let bytes = RealFileSystem.copy(from, to)?;
+            self.cancel.cancel();
+            Ok(bytes)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            RealFileSystem.create_dir_all(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            RealFileSystem.read_to_string(path)
+        }
+
+        fn write(&self, path: &Path, contents: &str) -> std::io::Result<()> {
+            RealFileSystem.write(path, contents)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> std::io::Result<()> {
+            RealFileSystem.remove_dir_all(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> std::io::Result<()> {
+            RealFileSystem.remove_file(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<Metadata> {
+            RealFileSystem.metadata(path)
+        }
+    }
+
+    #[test]
+    fn cancelled_resync_stops_between_files_and_rewrites_copied_manifests() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        let project = root.join("project");
+        write_package(&project, "project", "dep = { path = \"../dep\" }\n");
+        fs::write(project.join("src").join("other.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&project).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        let manifest_path = project.join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+        write_newer(&manifest_path, &format!("# Edited\n{manifest}"));
+        for name in ["main.sw", "other.sw"] {
+            write_newer(&project.join("src").join(name), "library;\n\nfn foo() {}\n");
+        }
+
+        let cancel = CancellationToken::new();
+        let mut job = sync.resync_job().unwrap();
+        job.copy_file_system = Arc::new(CancelOnCopyFileSystem {
+            cancel: cancel.clone(),
+        });
+        assert!(matches!(
+            job.run(Some(&cancel)),
+            Err(LanguageServerError::ResyncCancelled)
+        ));
+
+        // The walk stopped after the first file it copied, which may have been the manifest.
+        let synced_sources = ["main.sw", "other.sw"]
+            .into_iter()
+            .filter(|name| {
+                fs::read_to_string(temp_dir.join("src").join(name))
+                    .unwrap()
+                    .contains("fn foo")
+            })
+            .count();
+        assert!(synced_sources <= 1);
+        // The manifest is rewritten from the workspace either way, pointing at the dependency.
+        assert!(fs::read_to_string(temp_dir.join(MANIFEST_FILE_NAME))
+            .unwrap()
+            .starts_with("# Edited"));
+        assert_eq!(
+            sync.temp_dependency_paths().unwrap(),
+            [("dep".to_string(), root.join("dep"))]
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn resync_detects_out_of_date_lock_file() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        let project = root.join("project");
+        write_package(&project, "project", "dep = { path = \"../dep\" }\n");
+        let lock_file = |dependency: &str| {
+            format!(
+                "[[package]]\nname = \"{dependency}\"\nsource = \"path+from-root-0F8F2B0C982FD479\"\n\n[[package]]\nname = \"project\"\nsource = \"member\"\ndependencies = [\"{dependency}\"]\n"
+            )
+        };
+        let lock_path = project.join(LOCK_FILE_NAME);
+        fs::write(&lock_path, lock_file("old")).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&project).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        match sync.resync() {
+            Err(LanguageServerError::LockFileOutOfDate { dependencies }) => {
+                assert_eq!(dependencies, vec!["dep".to_string(), "old".to_string()]);
+            }
+            result => panic!("expected an out of date lock file, got {result:?}"),
+        }
+
+        // Make sure the updated lock file lands after the temp copy was made.
+        fs::write(&lock_path, lock_file("dep")).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&lock_path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        sync.resync().unwrap();
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn ignore_files_exclude_paths_from_temp() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(root.join(".gitignore"), "generated/\nForc.lock\n").unwrap();
+        fs::write(root.join(".swayignore"), "src/skipped.sw\n").unwrap();
+        fs::write(
+            root.join(LOCK_FILE_NAME),
+            "[[package]]\nname = \"project\"\nsource = \"member\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src").join("skipped.sw"), "library;\n").unwrap();
+        fs::write(root.join("src").join("other.sw"), "library;\n").unwrap();
+        fs::create_dir_all(root.join("src").join("generated")).unwrap();
+        fs::write(
+            root.join("src").join("generated").join("output.sw"),
+            "library;\n",
+        )
+        .unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let mut copied = sync.clone_manifest_dir_to_temp().unwrap();
+        copied.sort();
+
+        // Manifests and lock files are synced even if they are ignored.
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(
+            copied,
+            vec![
+                temp_dir.join(LOCK_FILE_NAME),
+                temp_dir.join(MANIFEST_FILE_NAME),
+                temp_dir.join("src").join("main.sw"),
+                temp_dir.join("src").join("other.sw"),
+            ]
+        );
+        assert!(!temp_dir.join("src").join("generated").exists());
+
+        // Files that become ignored are removed from temp when resyncing.
+        fs::write(root.join(".swayignore"), "src/skipped.sw\nsrc/other.sw\n").unwrap();
+        *sync.ignore_patterns.write() = ignore_patterns(&RealFileSystem, &root, &[]);
+        assert_eq!(
+            sync.resync_changed().unwrap(),
+            vec![temp_dir.join("src").join("other.sw")]
+        );
+        assert!(!temp_dir.join("src").join("other.sw").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn orphan_lock_files_are_not_synced() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(
+            root.join(LOCK_FILE_NAME),
+            "[[package]]\nname = \"project\"\nsource = \"member\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("stray")).unwrap();
+        fs::write(root.join("stray").join(LOCK_FILE_NAME), "").unwrap();
+        fs::write(root.join("stray").join("lib.sw"), "library;\n").unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join(LOCK_FILE_NAME).exists());
+        assert!(temp_dir.join("stray").join("lib.sw").exists());
+        assert!(!temp_dir.join("stray").join(LOCK_FILE_NAME).exists());
+        assert!(sync.resync_changed().unwrap().is_empty());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn clone_manifest_dir_to_temp_copies_large_workspaces_in_parallel() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let modules: Vec<_> = (0..PARALLEL_COPY_THRESHOLD * 2)
+            .map(|i| root.join("src").join(format!("module_{i}")).join("lib.sw"))
+            .collect();
+        for module in &modules {
+            fs::create_dir_all(module.parent().unwrap()).unwrap();
+            fs::write(module, "library;\n").unwrap();
+        }
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let copied = sync.clone_manifest_dir_to_temp().unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(copied.len(), modules.len() + 2);
+        for module in &modules {
+            let temp_module = temp_dir.join(module.strip_prefix(&root).unwrap());
+            assert!(copied.contains(&temp_module));
+            assert_eq!(fs::read_to_string(temp_module).unwrap(), "library;\n");
+        }
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn concurrent_sessions_keep_their_temp_workspaces_apart() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let (sync_a, sync_b) = (SyncWorkspace::new(), SyncWorkspace::new());
+        for sync in [&sync_a, &sync_b] {
+            sync.create_temp_dir_from_workspace(&root).unwrap();
+            sync.clone_manifest_dir_to_temp().unwrap();
+        }
+        assert_ne!(sync_a.session_id, sync_b.session_id);
+        assert_ne!(sync_a.temp_dir().unwrap(), sync_b.temp_dir().unwrap());
+
+        let main_url = |sync: &SyncWorkspace| {
+            get_url_from_path(&sync.temp_dir().unwrap().join("src").join("main.sw")).unwrap()
+        };
+        let (url_a, url_b) = (main_url(&sync_a), main_url(&sync_b));
+        assert!(sync_a.is_path_in_temp_workspace(&url_a));
+        assert!(!sync_a.is_path_in_temp_workspace(&url_b));
+        assert!(sync_b.is_path_in_temp_workspace(&url_b));
+        assert!(!sync_b.is_path_in_temp_workspace(&url_a));
+        assert!(sync_a.owns_temp_url(&url_a));
+        assert!(!sync_a.owns_temp_url(&url_b));
+        assert!(
+            !sync_a.owns_temp_url(&get_url_from_path(&root.join("src").join("main.sw")).unwrap())
+        );
+
+        // A session converts its own temp urls, and passes through the ones of other sessions.
+        let workspace_url = get_url_from_path(&root.join("src").join("main.sw")).unwrap();
+        assert_eq!(sync_a.to_workspace_url(url_a), Some(workspace_url));
+        assert_eq!(sync_a.to_workspace_url(url_b.clone()), Some(url_b));
+
+        sync_a.remove_temp_dir();
+        sync_b.remove_temp_dir();
+    }
+
+    #[test]
+    fn ensure_temp_dir_recreates_deleted_temp_dir() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        let project = root.join("project");
+        write_package(&project, "project", "dep = { path = \"../dep\" }\n");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&project).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert!(!sync.ensure_temp_dir().unwrap());
+
+        sync.remove_temp_dir();
+        assert!(sync.ensure_temp_dir().unwrap());
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        let doc = fs::read_to_string(temp_dir.join(MANIFEST_FILE_NAME))
+            .unwrap()
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        assert_eq!(
+            doc["dependencies"]["dep"]["path"].as_str(),
+            Some(&*root.join("dep").to_string_lossy())
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn status_reports_directories_and_last_sync() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        assert_eq!(
+            sync.status(),
+            SyncStatus {
+                manifest_dir: None,
+                temp_dir: None,
+                watcher_active: false,
+                last_sync: None,
+            }
+        );
+
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let status = sync.status();
+        assert_eq!(status.manifest_dir, Some(root.clone()));
+        assert_eq!(status.temp_dir, sync.temp_dir().ok());
+        assert!(!status.watcher_active);
+        let cloned_at = status.last_sync.unwrap();
+
+        sync.resync().unwrap();
+        assert!(sync.status().last_sync.unwrap() >= cloned_at);
+
+        sync.remove_temp_dir();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dependency_paths_of_a_git_worktree_resolve_lexically() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        // The main checkout holds the dependency, which the worktree links to.
+        fs::create_dir_all(root.join("main").join(".git").join("worktrees").join("wt")).unwrap();
+        write_package(&root.join("main").join("deps").join("dep"), "dep", "");
+        fs::create_dir_all(root.join("wt").join("deps")).unwrap();
+        std::os::unix::fs::symlink(
+            root.join("main").join("deps").join("dep"),
+            root.join("wt").join("deps").join("dep"),
+        )
+        .unwrap();
+        write_package(
+            &root.join("wt").join("project"),
+            "project",
+            "dep = { path = \"../deps/./dep\" }\n",
+        );
+        let edit = || {
+            let temp_manifest_path = root.join("Forc.temp.toml");
+            edit_manifest_dependency_paths(
+                &RealFileSystem,
+                &root.join("wt").join("project"),
+                &root.join("wt").join("project").join(MANIFEST_FILE_NAME),
+                &temp_manifest_path,
+                &[],
+                None,
+            )
+            .unwrap();
+            let doc = fs::read_to_string(&temp_manifest_path)
+                .unwrap()
+                .parse::<toml_edit::DocumentMut>()
+                .unwrap();
+            doc["dependencies"]["dep"]["path"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // Outside of a worktree the symlink is followed into the main checkout.
+        assert_eq!(
+            edit(),
+            root.join("main").join("deps").join("dep").to_string_lossy()
+        );
+
+        fs::write(
+            root.join("wt").join(".git"),
+            format!(
+                "gitdir: {}\n",
+                root.join("main/.git/worktrees/wt").display()
+            ),
+        )
+        .unwrap();
+        assert!(is_linked_git_checkout(&root.join("wt").join("project")));
+        assert!(!is_linked_git_checkout(&root.join("main").join("deps")));
+        assert_eq!(
+            edit(),
+            root.join("wt").join("deps").join("dep").to_string_lossy()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_temp_dir_from_workspace_canonicalizes_manifest_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        std::os::unix::fs::symlink(root.join("project"), root.join("link")).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("link").join("src").join(".."))
+            .unwrap();
+        assert_eq!(sync.manifest_dir(), Ok(root.join("project")));
+
+        let main = root.join("project").join("src").join("main.sw");
+        let temp_url = sync
+            .workspace_to_temp_url(&Url::from_file_path(&main).unwrap())
+            .unwrap();
+        assert_eq!(
+            temp_url,
+            Url::from_file_path(sync.temp_dir().unwrap().join("src").join("main.sw")).unwrap()
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn temp_dependency_paths_lists_the_rewritten_paths() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(&root.join("dev_dep"), "dev_dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep\" }\nregistry = \"0.1.0\"\n\n\
+             [dev-dependencies]\ndev_dep = { path = \"../dev_dep\" }\n",
+        );
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let paths = sync.temp_dependency_paths().unwrap();
+        assert_eq!(
+            paths,
+            [
+                ("dep".to_string(), root.join("dep")),
+                ("dev_dep".to_string(), root.join("dev_dep")),
+            ]
+        );
+        assert!(paths
+            .iter()
+            .all(|(_name, path)| path.is_absolute() && path.exists()));
+
+        sync.remove_temp_dir();
+        assert!(sync.temp_dependency_paths().is_err());
+    }
+
+    #[test]
+    fn parse_build_diagnostics_reads_titles_labels_and_locations() {
+        let output = "\u{1b}[1m\u{1b}[91merror\u{1b}[0m: Constant \"X\" is already declared
+  --> /project/src/main.sw:4:7
+   |
+ 4 | const X: u64 = 2;
+   |       ^ Constant \"X\" has the same name as an already declared constant.
+   |
+____
+
+warning
+  --> /project/src/lib.sw:2:1
+   |
+ 2 | fn unused() {}
+   | ^^ This function is never called.
+   |
+____
+
+error: Failed to compile project
+";
+        let diagnostics = parse_build_diagnostics(output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].uri,
+            Url::from_file_path("/project/src/main.sw").unwrap()
+        );
+        assert_eq!(
+            diagnostics[0].diagnostic.severity,
+            Some(lsp_types::DiagnosticSeverity::ERROR)
+        );
+        assert_eq!(
+            diagnostics[0].diagnostic.message,
+            "Constant \"X\" is already declared"
+        );
+        assert_eq!(
+            diagnostics[0].diagnostic.range.start,
+            lsp_types::Position::new(3, 6)
+        );
+        assert_eq!(
+            diagnostics[1].diagnostic.severity,
+            Some(lsp_types::DiagnosticSeverity::WARNING)
+        );
+        assert_eq!(
+            diagnostics[1].diagnostic.message,
+            "This function is never called."
+        );
+    }
+
+    #[test]
+    fn build_temp_reports_a_missing_forc() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+
+        let forc = root.join("missing-forc");
+        assert!(matches!(
+            sync.build_temp_with(forc.as_os_str()),
+            Err(LanguageServerError::ForcNotFound)
+        ));
+        sync.remove_temp_dir();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn build_temp_converts_diagnostics_to_the_workspace() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        // A stand-in for forc printing an error in the temp file it was pointed at.
+        let forc = root.join("forc");
+        fs::write(
+            &forc,
+            "#!/bin/sh\nprintf 'error: Oops\\n  --> %s/src/main.sw:1:1\\n' \"$3\"\nexit 1\n",
+        )
+        .unwrap();
+        fs::set_permissions(&forc, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let diagnostics = sync.build_temp_with(forc.as_os_str()).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].uri,
+            Url::from_file_path(root.join("project").join("src").join("main.sw")).unwrap()
+        );
+        assert_eq!(diagnostics[0].diagnostic.message, "Oops");
+
+        // A failed build without diagnostics is an error.
+        fs::write(&forc, "#!/bin/sh\necho broken >&2\nexit 1\n").unwrap();
+        assert!(matches!(
+            sync.build_temp_with(forc.as_os_str()),
+            Err(LanguageServerError::ForcBuildFailed(err)) if err == "broken"
+        ));
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn rewrite_dependency_path_only_rewrites_the_given_dependency() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("dep_a"), "dep_a", "");
+        write_package(&root.join("dep_b"), "dep_b", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep_a\" }\nregistry = \"0.1.0\"\n",
+        );
+        let manifest_path = root.join("project").join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let temp_manifest_path = sync.temp_dir().unwrap().join(MANIFEST_FILE_NAME);
+        let temp_dependency = |name: &str| {
+            let doc = fs::read_to_string(&temp_manifest_path)
+                .unwrap()
+                .parse::<toml_edit::DocumentMut>()
+                .unwrap();
+            doc["dependencies"][name]["path"].as_str().map(String::from)
+        };
+        assert_eq!(
+            temp_dependency("dep"),
+            Some(root.join("dep_a").to_string_lossy().to_string())
+        );
+
+        sync.rewrite_dependency_path("dep", Path::new("../dep_b"))
+            .unwrap();
+        assert_eq!(
+            temp_dependency("dep"),
+            Some(root.join("dep_b").to_string_lossy().to_string())
+        );
+        sync.rewrite_dependency_path("registry", &root.join("dep_a"))
+            .unwrap();
+        assert_eq!(
+            temp_dependency("registry"),
+            Some(root.join("dep_a").to_string_lossy().to_string())
+        );
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), manifest);
+
+        let err = sync
+            .rewrite_dependency_path("missing", &root.join("dep_a"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            LanguageServerError::DocumentError(DocumentError::DependencyNotFound { name, .. })
+                if name == "missing"
+        ));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn reload_manifest_invalidates_the_cached_manifest() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(&root.join("project"), "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let dependencies = |sync: &SyncWorkspace| match sync.manifest() {
+            Some(ManifestFile::Package(package)) => package
+                .dependencies
+                .clone()
+                .unwrap_or_default()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            manifest => panic!("unexpected manifest {manifest:?}"),
+        };
+        assert!(dependencies(&sync).is_empty());
+
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep\" }\n",
+        );
+        // The cached manifest is only reparsed on reload.
+        assert!(dependencies(&sync).is_empty());
+        sync.reload_manifest().unwrap();
+        assert_eq!(dependencies(&sync), vec!["dep".to_string()]);
+        let temp_manifest =
+            fs::read_to_string(sync.temp_dir().unwrap().join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(temp_manifest.contains(&*root.join("dep").to_string_lossy()));
+
+        fs::write(root.join("project").join(MANIFEST_FILE_NAME), "[project\n").unwrap();
+        assert!(sync.reload_manifest().is_err());
+        assert!(sync.manifest().is_none());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn reload_manifest_reads_the_overlay_and_follows_the_source_dirs() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::create_dir_all(root.join("src").join("bin")).unwrap();
+        fs::write(root.join("src").join("bin").join("main.sw"), "script;\n").unwrap();
+
+        let sync = SyncWorkspace::new().with_source_dirs_only(true);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let entry = |sync: &SyncWorkspace| match sync.manifest() {
+            Some(ManifestFile::Package(package)) => package.project.entry.clone(),
+            manifest => panic!("unexpected manifest {manifest:?}"),
+        };
+        let ignored = |sync: &SyncWorkspace, path: &Path| {
+            is_ignored(&sync.ignore_patterns.read(), path, false)
+        };
+        assert!(!ignored(&sync, &root.join("src").join("main.sw")));
+
+        // The unsaved manifest is reloaded instead of the one on disk.
+        let manifest_path = root.join(MANIFEST_FILE_NAME);
+        let contents = fs::read_to_string(&manifest_path)
+            .unwrap()
+            .replace("entry = \"main.sw\"", "entry = \"bin/main.sw\"");
+        sync.set_manifest_overlay(manifest_path, contents).unwrap();
+        sync.reload_manifest().unwrap();
+        assert_eq!(entry(&sync), "bin/main.sw");
+        assert!(ignored(&sync, &root.join("src").join("main.sw")));
+        assert!(!ignored(
+            &sync,
+            &root.join("src").join("bin").join("main.sw")
+        ));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn create_temp_dir_from_workspace_reports_the_temp_root_it_failed_in() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        let missing = root.join("missing");
+
+        let sync = SyncWorkspace::with_temp_root(Some(missing.clone()));
+        let err = sync
+            .create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap_err();
+        match err {
+            LanguageServerError::DirectoryError(DirectoryError::TempDirFailed { path, err }) => {
+                assert_eq!(path, missing.to_string_lossy());
+                assert!(!err.is_empty());
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+        assert_eq!(sync.temp_dir(), Err(DirectoryError::TempDirNotFound));
+    }
+
+    #[test]
+    fn create_temp_dir_from_workspace_tells_why_the_dir_isnt_a_project() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        let dir = |path: &Path| path.to_string_lossy().to_string();
+        let create = |path: &Path| {
+            SyncWorkspace::new()
+                .create_temp_dir_from_workspace(path)
+                .unwrap_err()
+        };
+
+        let missing = root.join("missing");
+        assert!(matches!(
+            create(&missing),
+            LanguageServerError::DocumentError(DocumentError::ProjectDirNotFound { dir: err_dir })
+                if err_dir == dir(&missing)
+        ));
+
+        assert!(matches!(
+            create(&root),
+            LanguageServerError::DocumentError(DocumentError::ManifestFileNotFound { dir: err_dir })
+                if err_dir == dir(&root)
+        ));
+
+        fs::write(root.join(MANIFEST_FILE_NAME), "[project\n").unwrap();
+        match create(&root) {
+            LanguageServerError::DocumentError(DocumentError::InvalidManifestFile {
+                dir: err_dir,
+                err,
+            }) => {
+                assert_eq!(err_dir, dir(&root));
+                assert!(!err.is_empty());
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+
+        // A file of the project, e.g. the document opened in the editor, finds its manifest.
+        write_package(&root, "project", "");
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("src").join("main.sw"))
+            .unwrap();
+        assert_eq!(sync.manifest_dir().unwrap(), root);
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn deterministic_temp_dir_is_named_after_the_manifest_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("project"), "project", "");
+        write_package(&root.join("other"), "other", "");
+        let temp_root = root.join("temp");
+        fs::create_dir_all(&temp_root).unwrap();
+        let create = |dir: &str| {
+            let sync = SyncWorkspace::with_temp_root(Some(temp_root.clone()))
+                .with_deterministic_temp_dir(true);
+            sync.create_temp_dir_from_workspace(&root.join(dir))
+                .unwrap();
+            sync
+        };
+
+        let first = create("project");
+        let first_dir = first.temp_dir().unwrap();
+        fs::write(first.temp_root().unwrap().join("stale.sw"), "").unwrap();
+        let second = create("project");
+        let second_dir = second.temp_dir().unwrap();
+        assert_eq!(first_dir, second_dir);
+        assert!(!second.temp_root().unwrap().join("stale.sw").exists());
+        assert_ne!(create("other").temp_dir().unwrap(), second_dir);
+
+        let second_root = second.temp_root().unwrap();
+        second.remove_temp_dir();
+        assert!(!second_root.exists());
+    }
+
+    #[test]
+    fn temp_dir_fallback_operates_on_the_manifest_dir_in_place() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root.join("dep"), "dep", "");
+        write_package(
+            &root.join("project"),
+            "project",
+            "dep = { path = \"../dep\" }\n",
+        );
+        fs::write(root.join("project").join(".gitignore"), "out/\n").unwrap();
+        fs::create_dir_all(root.join("project").join("out")).unwrap();
+        fs::write(root.join("project").join("out").join("main.sw"), "").unwrap();
+        let manifest_path = root.join("project").join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path).unwrap();
+
+        let sync =
+            SyncWorkspace::with_temp_root(Some(root.join("missing"))).with_temp_dir_fallback(true);
+        sync.create_temp_dir_from_workspace(&root.join("project"))
+            .unwrap();
+        assert!(sync.is_in_place());
+        assert_eq!(sync.temp_dir(), Ok(root.join("project")));
+
+        assert_eq!(sync.clone_manifest_dir_to_temp(), Ok(Vec::new()));
+        assert_eq!(sync.resync_changed().unwrap(), Vec::<PathBuf>::new());
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), manifest);
+        assert!(root.join("project").join("out").join("main.sw").exists());
+        let synced = sync_changed_files(
+            &RealFileSystem,
+            &sync.copied_package_dirs().unwrap(),
+            &sync.package_dirs(),
+            &sync.ignore_patterns.read(),
+            &SyncedFiles::default(),
+            std::slice::from_ref(&manifest_path),
+            None,
+        )
+        .unwrap();
+        assert_eq!(synced, Vec::<PathBuf>::new());
+        assert_eq!(fs::read_to_string(&manifest_path).unwrap(), manifest);
+
+        let dep_url = Url::from_file_path(root.join("dep").join("src").join("main.sw")).unwrap();
+        assert!(!sync.owns_temp_url(&dep_url));
+        assert_eq!(sync.to_workspace_url(dep_url.clone()), Some(dep_url));
+        let main_url =
+            Url::from_file_path(root.join("project").join("src").join("main.sw")).unwrap();
+        assert_eq!(sync.workspace_to_temp_url(&main_url), Ok(main_url.clone()));
+
+        sync.remove_temp_dir();
+        assert!(manifest_path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_temp_dir_from_workspace_sanitizes_non_utf8_project_name() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        let project = root.join(OsStr::from_bytes(b"project\xff"));
+        write_package(&project, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&project).unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        let temp_name = temp_dir.file_name().unwrap().to_str().unwrap();
+        assert!(temp_name.starts_with("project_-"));
+        assert_eq!(temp_name, temp_project_name(project.file_name().unwrap()));
+        sync.clone_manifest_dir_to_temp().unwrap();
+        assert!(temp_dir.join("src").join("main.sw").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn workspace_to_temp_span_converts_workspace_spans() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let source_engine = SourceEngine::default();
+        let workspace_source_id = source_engine.get_source_id(&root.join("src").join("main.sw"));
+        let temp_source_id =
+            source_engine.get_source_id(&sync.temp_dir().unwrap().join("src").join("main.sw"));
+        let src = sway_types::span::Source::new("library;\n");
+        let workspace_span = Span::new(src.clone(), 0, 7, Some(workspace_source_id)).unwrap();
+        let temp_span = Span::new(src, 0, 7, Some(temp_source_id)).unwrap();
+
+        let converted = sync
+            .workspace_to_temp_span(&source_engine, &workspace_span)
+            .unwrap();
+        assert_eq!(converted, temp_span);
+        assert_eq!(
+            sync.workspace_to_temp_span(&source_engine, &temp_span)
+                .unwrap(),
+            temp_span
+        );
+        assert_eq!(
+            sync.temp_to_workspace_span(&source_engine, &converted)
+                .unwrap(),
+            workspace_span
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn normalize_line_endings_converts_sources_and_workspace_spans() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let text = "library;\r\n\r\nfn foo() {}\r\n";
+        fs::write(root.join("src").join("main.sw"), text).unwrap();
+        fs::write(root.join("src").join("lib.sw"), "library;\r\n").unwrap();
+
+        let sync = SyncWorkspace::new().with_normalize_line_endings(true);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("src").join("main.sw")).unwrap(),
+            "library;\n\nfn foo() {}\n"
+        );
+
+        // Resyncs copy the changed files with their line endings normalized as well.
+        std::thread::sleep(Duration::from_millis(10));
+        fs::write(root.join("src").join("lib.sw"), "library;\r\n\r\n").unwrap();
+        sync.resync().unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("src").join("lib.sw")).unwrap(),
+            "library;\n\n"
+        );
+
+        let source_engine = SourceEngine::default();
+        let workspace_source_id = source_engine.get_source_id(&root.join("src").join("main.sw"));
+        let temp_source_id = source_engine.get_source_id(&temp_dir.join("src").join("main.sw"));
+        let start = text.find("foo").unwrap();
+        let workspace_span = Span::new(
+            sway_types::span::Source::new(text),
+            start,
+            start + 3,
+            Some(workspace_source_id),
+        )
+        .unwrap();
+        let converted = sync
+            .workspace_to_temp_span(&source_engine, &workspace_span)
+            .unwrap();
+        assert_eq!(converted.source_id(), Some(&temp_source_id));
+        assert_eq!(converted.as_str(), "foo");
+        assert_eq!(converted.start(), start - 2);
+        let line_col = |span: &Span| {
+            let sway_types::LineCol { line, col } = span.start_line_col_one_index();
+            (line, col)
+        };
+        assert_eq!(line_col(&converted), line_col(&workspace_span));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn registered_roots_convert_urls_through_their_own_temp_dirs() {
+        let projects = tempfile::tempdir().unwrap();
+        let root = projects.path().canonicalize().unwrap();
+        write_package(&root.join("first"), "first", "");
+        write_package(&root.join("second"), "second", "");
+        fs::create_dir_all(root.join("other")).unwrap();
+        let main = Path::new("src").join("main.sw");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("first"))
+            .unwrap();
+        let second = sync.register_root(&root.join("second")).unwrap();
+        assert!(Arc::ptr_eq(
+            &second,
+            &sync.register_root(&root.join("second")).unwrap()
+        ));
+        assert_eq!(sync.root_dirs(), vec![root.join("second")]);
+
+        let second_temp_dir = second.temp_dir().unwrap();
+        assert!(second_temp_dir.join(&main).exists());
+        assert_ne!(second_temp_dir, sync.temp_dir().unwrap());
+        let second_url = get_url_from_path(&root.join("second").join(&main)).unwrap();
+        let second_temp_url = get_url_from_path(&second_temp_dir.join(&main)).unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_url(&second_url).unwrap(),
+            second_temp_url
+        );
+        assert_eq!(
+            sync.temp_to_workspace_url(&second_temp_url).unwrap(),
+            second_url
+        );
+        let first_url = get_url_from_path(&root.join("first").join(&main)).unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_url(&first_url).unwrap(),
+            get_url_from_path(&sync.temp_dir().unwrap().join(&main)).unwrap()
+        );
+
+        let other_url = get_url_from_path(&root.join("other").join("main.sw")).unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_url(&other_url),
+            Err(DirectoryError::NoRootForUrl {
+                url: other_url.to_string()
+            })
+        );
+
+        sync.remove_temp_dir();
+        assert!(!second_temp_dir.exists());
+        assert!(sync.unregister_root(&root.join("second")));
+        assert!(!sync.unregister_root(&root.join("second")));
+    }
+
+    #[test]
+    fn rewriting_mutual_path_dependencies_terminates() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"pkg_a\", \"pkg_b\"]\n",
+        )
+        .unwrap();
+        write_package(
+            &root.join("pkg_a"),
+            "pkg_a",
+            "pkg_b = { path = \"../pkg_b\" }\n",
+        );
+        write_package(
+            &root.join("pkg_b"),
+            "pkg_b",
+            "pkg_a = { path = \"../pkg_a\" }\n",
+        );
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        let dependency_path = |package: &str, dependency: &str| {
+            let contents =
+                fs::read_to_string(temp_dir.join(package).join(MANIFEST_FILE_NAME)).unwrap();
+            let doc = contents.parse::<toml_edit::DocumentMut>().unwrap();
+            PathBuf::from(doc["dependencies"][dependency]["path"].as_str().unwrap())
+        };
+        // Each member points at the copy of the other one.
+        assert_eq!(dependency_path("pkg_a", "pkg_b"), temp_dir.join("pkg_b"));
+        assert_eq!(dependency_path("pkg_b", "pkg_a"), temp_dir.join("pkg_a"));
+        sync.remove_temp_dir();
+
+        // A dependency that isn't synced is left as it is, even if it depends back on the package.
+        let sync = SyncWorkspace::new();
+        let b_manifest = fs::read_to_string(root.join("pkg_b").join(MANIFEST_FILE_NAME)).unwrap();
+        sync.create_temp_dir_from_workspace(&root.join("pkg_a"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.resync().unwrap();
+        assert_eq!(
+            sync.temp_dependency_paths().unwrap(),
+            vec![("pkg_b".to_string(), root.join("pkg_b"))]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("pkg_b").join(MANIFEST_FILE_NAME)).unwrap(),
+            b_manifest
+        );
+        assert!(!sync.temp_root().unwrap().join("pkg_b").exists());
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn convert_url_reports_path_and_prefix_on_failure() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+
+        let outside = tempfile::tempdir().unwrap();
+        let outside_path = outside.path().canonicalize().unwrap().join("main.sw");
+        let url = get_url_from_path(&outside_path).unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(
+            sync.temp_to_workspace_url(&url),
+            Err(DirectoryError::StripPrefixError {
+                path: url.path().to_string(),
+                prefix: temp_dir.to_string_lossy().to_string(),
+            })
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn strip_path_prefix_matches_mixed_case_prefixes() {
+        let path = Path::new("/Users/Dev/Project/src/Main.sw");
+        assert_eq!(
+            strip_path_prefix(path, Path::new("/users/dev/PROJECT"), true),
+            Some(Path::new("src/Main.sw"))
+        );
+        assert_eq!(
+            strip_path_prefix(path, Path::new("/users/dev/PROJECT"), false),
+            None
+        );
+        assert_eq!(
+            strip_path_prefix(path, Path::new("/Users/Dev/Project"), false),
+            Some(Path::new("src/Main.sw"))
+        );
+        assert_eq!(
+            strip_path_prefix(path, Path::new("/users/dev/other"), true),
+            None
+        );
+        assert_eq!(
+            strip_path_prefix(Path::new("/users"), Path::new("/users/dev"), true),
+            None
+        );
+    }
+
+    #[test]
+    fn with_directories_converts_urls_between_given_directories() {
+        let dirs = tempfile::tempdir().unwrap();
+        let root = dirs.path().canonicalize().unwrap();
+        let manifest_dir = root.join("project");
+        let temp_dir = root.join("temp").join("project");
+        write_package(&manifest_dir, "project", "");
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let sync = SyncWorkspace::with_directories(manifest_dir.clone(), temp_dir.clone());
+        let workspace_url = get_url_from_path(&manifest_dir.join("src").join("main.sw")).unwrap();
+        let temp_url = get_url_from_path(&temp_dir.join("src").join("main.sw")).unwrap();
+        assert_eq!(
+            sync.workspace_to_temp_url(&workspace_url).unwrap(),
+            temp_url
+        );
+        assert_eq!(
+            sync.temp_to_workspace_url(&temp_url).unwrap(),
+            workspace_url
+        );
+
+        // The directories belong to the caller.
+        sync.remove_temp_dir();
+        assert!(temp_dir.exists());
+    }
+
+    #[test]
+    fn urls_are_unchanged_if_temp_dir_is_manifest_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let temp_dir = PathBuf::from(format!("{}/", root.display()));
+        let sync = SyncWorkspace::with_directories(root.clone(), temp_dir);
+        let url = get_url_from_path(&root.join("src").join("main.sw")).unwrap();
+        assert_eq!(sync.workspace_to_temp_url(&url).unwrap(), url);
+        assert_eq!(sync.temp_to_workspace_url(&url).unwrap(), url);
+    }
+
+    #[test]
+    fn convert_url_ignores_trailing_separators() {
+        let url = Url::parse("file:///workspace/temp/src/main.sw").unwrap();
+        assert_eq!(
+            convert_url(
+                &url,
+                Path::new("/workspace/project/"),
+                Path::new("/workspace/temp//")
+            )
+            .unwrap()
+            .path(),
+            "/workspace/project/src/main.sw"
+        );
+    }
+
+    #[test]
+    fn converts_urls_of_paths_with_spaces() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap().join("my workspace");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"member a\", \"member b\"]\n",
+        )
+        .unwrap();
+        write_package(&root.join("member a"), "member_a", "");
+        write_package(&root.join("member b"), "member_b", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        for member in ["member a", "member b"] {
+            let main = Path::new(member).join("src").join("main.sw");
+            let workspace_url = get_url_from_path(&root.join(&main)).unwrap();
+            assert!(workspace_url.path().contains("my%20workspace"));
+            let temp_url = sync.workspace_to_temp_url(&workspace_url).unwrap();
+            assert_eq!(temp_url.to_file_path().unwrap(), temp_dir.join(&main));
+            assert!(sync.owns_temp_url(&temp_url));
+            assert_eq!(
+                sync.temp_to_workspace_url(&temp_url).unwrap(),
+                workspace_url
+            );
+        }
+
+        let source_engine = SourceEngine::default();
+        let main = Path::new("member b").join("src").join("main.sw");
+        let temp_source_id = source_engine.get_source_id(&temp_dir.join(&main));
+        let src = fs::read_to_string(temp_dir.join(&main)).unwrap();
+        let span = Span::new(src.as_str().into(), 0, 7, Some(temp_source_id)).unwrap();
+        let converted = sync.temp_to_workspace_span(&source_engine, &span).unwrap();
+        assert_eq!(
+            converted.source_id(),
+            Some(&source_engine.get_source_id(&root.join(&main)))
+        );
+        assert_eq!(converted.as_str(), span.as_str());
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn converts_file_and_directory_urls() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+        let file_url = |path: &Path| Url::from_file_path(path).unwrap();
+        let dir_url = |path: &Path| Url::from_directory_path(path).unwrap();
+
+        let main = Path::new("src").join("main.sw");
+        let cases = [
+            (file_url(&root.join(&main)), file_url(&temp_dir.join(&main))),
+            (file_url(&root.join("src")), file_url(&temp_dir.join("src"))),
+            (dir_url(&root.join("src")), dir_url(&temp_dir.join("src"))),
+            (file_url(&root), file_url(&temp_dir)),
+            (dir_url(&root), dir_url(&temp_dir)),
+        ];
+        for (workspace_url, temp_url) in cases {
+            assert_eq!(
+                sync.workspace_to_temp_url(&workspace_url),
+                Ok(temp_url.clone())
+            );
+            assert_eq!(sync.temp_to_workspace_url(&temp_url), Ok(workspace_url));
+        }
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn temp_to_workspace_url_converts_urls_of_stale_temp_dirs() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"member\"]\n",
+        )
+        .unwrap();
+        write_package(&root.join("member"), "member", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.resync().unwrap();
+        let member_main = Path::new("member").join("src").join("main.sw");
+        let stale_url = get_url_from_path(&sync.temp_dir().unwrap().join(&member_main)).unwrap();
+
+        sync.hard_resync().unwrap();
+        assert!(!stale_url.to_file_path().unwrap().exists());
+        assert_eq!(
+            sync.temp_to_workspace_url(&stale_url).unwrap(),
+            get_url_from_path(&root.join(&member_main)).unwrap()
+        );
+
+        // Urls outside of any temp directory still fail to convert.
+        let outside = get_url_from_path(&root.join(&member_main)).unwrap();
+        assert!(matches!(
+            sync.temp_to_workspace_url(&outside),
+            Err(DirectoryError::StripPrefixError { .. })
+        ));
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn mixed_case_temp_urls_match_on_case_insensitive_platforms() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+
+        let temp_path = sync.temp_dir().unwrap().join("src").join("main.sw");
+        let mixed_case_path = PathBuf::from(
+            temp_path
+                .to_string_lossy()
+                .replace(SyncWorkspace::LSP_TEMP_PREFIX, "sway_lsp_temp_dir"),
+        );
+        let url = get_url_from_path(&mixed_case_path).unwrap();
+        assert_eq!(sync.is_path_in_temp_workspace(&url), CASE_INSENSITIVE_PATHS);
+        assert_eq!(
+            sync.temp_to_workspace_url(&url).ok(),
+            CASE_INSENSITIVE_PATHS
+                .then(|| get_url_from_path(&root.join("src").join("main.sw")).unwrap())
+        );
+
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn manifest_watcher_uses_the_configured_debounce() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        assert_eq!(
+            SyncWorkspace::new().manifest_watch_debounce,
+            SyncWorkspace::DEFAULT_MANIFEST_WATCH_DEBOUNCE
+        );
+        let sync = SyncWorkspace::new()
+            .with_manifest_watch_debounce(Duration::from_millis(20))
+            .with_resync_debounce(Duration::ZERO);
+        assert_eq!(sync.manifest_watch_debounce, Duration::from_millis(20));
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let mut events = sync.subscribe();
+        sync.watch_and_sync_manifest().unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let manifest_path = root.join(MANIFEST_FILE_NAME);
+        fs::write(
+            &manifest_path,
+            fs::read_to_string(&manifest_path).unwrap() + "\n",
+        )
+        .unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event, WatchEvent::ManifestChanged(vec![manifest_path]));
+        sync.stop_watchers();
+        sync.remove_temp_dir();
+
+        // The source watcher is debounced for as long, so its changes only arrive once it elapsed.
+        let sync = SyncWorkspace::new().with_manifest_watch_debounce(Duration::from_secs(3));
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let (tx, mut changes) = tokio::sync::mpsc::unbounded_channel();
+        sync.watch_and_sync_sources(tx).unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        fs::write(root.join("src").join("new.sw"), "library;\n").unwrap();
+        assert!(tokio::time::timeout(Duration::from_secs(1), changes.recv())
+            .await
+            .is_err());
+        let synced = tokio::time::timeout(Duration::from_secs(10), changes.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            synced,
+            vec![sync.temp_dir().unwrap().join("src").join("new.sw")]
+        );
+
+        sync.stop_watchers();
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn resync_debounced_only_runs_the_latest_request() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new().with_resync_debounce(Duration::from_millis(50));
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let new_path = root.join("src").join("new.sw");
+        fs::write(&new_path, "library;\n").unwrap();
+        let (first, second) = tokio::join!(sync.resync_debounced(), sync.resync_debounced());
+        assert!(!first.unwrap());
+        assert!(second.unwrap());
+        assert!(sync.temp_dir().unwrap().join("src").join("new.sw").exists());
+
+        // Requests outside of the debounce window aren't coalesced.
+        assert!(sync.resync_debounced().await.unwrap());
+
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn wait_for_initial_sync_resolves_once_cloned() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = std::sync::Arc::new(SyncWorkspace::new());
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+
+        let waiter = tokio::spawn({
+            let sync = sync.clone();
+            async move { sync.wait_for_initial_sync().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        sync.clone_manifest_dir_to_temp().unwrap();
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        // Once synced, waiting resolves immediately.
+        sync.wait_for_initial_sync().await;
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn synced_files_can_be_overridden() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::create_dir_all(root.join("abi")).unwrap();
+        fs::write(root.join("abi").join("token.abi.json"), "{}").unwrap();
+        fs::write(root.join("notes.txt"), "not synced").unwrap();
+
+        let mut synced_files = SyncedFiles::default();
+        synced_files.extensions.insert("abi.json".to_string());
+        let sync = SyncWorkspace::new().with_synced_files(synced_files);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("abi").join("token.abi.json")).unwrap(),
+            "{}"
+        );
+        assert!(!temp_dir.join("notes.txt").exists());
+
+        fs::remove_file(root.join("abi").join("token.abi.json")).unwrap();
+        let changed = sync.resync_changed().unwrap();
+        assert_eq!(changed, vec![temp_dir.join("abi").join("token.abi.json")]);
+        assert!(!temp_dir.join("abi").join("token.abi.json").exists());
+
+        sync.remove_temp_dir();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn synced_scripts_keep_their_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let script = root.join("build.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut synced_files = SyncedFiles::default();
+        synced_files.extensions.insert("sh".to_string());
+        let sync = SyncWorkspace::new().with_synced_files(synced_files);
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let temp_script = sync.temp_dir().unwrap().join("build.sh");
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&temp_script), 0o755);
+
+        // Resyncing a changed script keeps the permissions of the workspace file too.
+        write_newer(&script, "#!/bin/sh\nexit 0\n");
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o700)).unwrap();
+        assert_eq!(sync.resync_changed().unwrap(), vec![temp_script.clone()]);
+        assert_eq!(mode(&temp_script), 0o700);
+
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn project_config_options_are_applied() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        let manifest_dir = root.join("project");
+        write_package(&manifest_dir, "project", "");
+        fs::create_dir_all(manifest_dir.join("tests")).unwrap();
+        fs::write(manifest_dir.join("tests").join("harness.sw"), "library;\n").unwrap();
+        fs::write(manifest_dir.join("token.abi.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("temp")).unwrap();
+        fs::write(
+            manifest_dir.join(ProjectConfig::FILE_NAME),
+            r#"
+ignore = ["tests/"]
+synced-extensions = ["abi.json"]
+temp-root = "../temp"
+resync-debounce-ms = 250
+"#,
+        )
+        .unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&manifest_dir).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_eq!(
+            sync.temp_root().unwrap().parent(),
+            Some(root.join("temp").as_path())
+        );
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        assert!(temp_dir.join("token.abi.json").exists());
+        assert!(!temp_dir.join("tests").exists());
+        assert_eq!(sync.effective_resync_debounce(), Duration::from_millis(250));
+        assert_eq!(
+            sync.effective_manifest_watch_debounce(),
+            SyncWorkspace::DEFAULT_MANIFEST_WATCH_DEBOUNCE
+        );
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn project_config_skips_unknown_keys_and_invalid_files() {
+        let config = ProjectConfig::parse("ignore = [\"out/\"]\nunknown-option = true\n").unwrap();
+        assert_eq!(config.ignore, vec!["out/".to_string()]);
+
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        fs::write(root.join(ProjectConfig::FILE_NAME), "ignore = 1").unwrap();
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        assert_eq!(sync.project_config(), ProjectConfig::default());
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn syncs_workspace_members_matched_by_globs() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        write_package(&root.join("packages").join("member_a"), "member_a", "");
+        write_package(&root.join("packages").join("member_b"), "member_b", "");
+        fs::create_dir_all(root.join("packages").join("not_a_package")).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+
+        assert_eq!(sync.members(), vec!["member_a", "member_b"]);
+        let temp_dir = sync.temp_dir().unwrap();
+        for member in ["member_a", "member_b"] {
+            let member_temp = sync.member_temp_dir(member).unwrap();
+            assert_eq!(member_temp, temp_dir.join("packages").join(member));
+            assert!(member_temp.join("src").join("main.sw").exists());
+        }
+
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn manifest_watcher_syncs_changed_lock_files() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let lock = "[[package]]\nname = \"project\"\nsource = \"member\"\n";
+        fs::write(root.join(LOCK_FILE_NAME), lock).unwrap();
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_lock = sync.temp_dir().unwrap().join(LOCK_FILE_NAME);
+        assert_eq!(fs::read_to_string(&temp_lock).unwrap(), lock);
+        let mut events = sync.subscribe();
+        sync.watch_and_sync_manifest().unwrap();
+
+        // Let the watcher start watching before changing the lock file.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let updated = format!("{lock}\n# updated\n");
+        fs::write(root.join(LOCK_FILE_NAME), &updated).unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                match events.recv().await.unwrap() {
+                    event @ WatchEvent::DependenciesChanged(_) => return event,
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            event,
+            WatchEvent::DependenciesChanged(vec![temp_lock.clone()])
+        );
+        assert_eq!(fs::read_to_string(&temp_lock).unwrap(), updated);
+
+        sync.stop_watchers();
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn syncs_publish_their_metrics_to_subscribers() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+        let manifest_len = fs::metadata(root.join(MANIFEST_FILE_NAME)).unwrap().len();
+
+        let sync = SyncWorkspace::new();
+        let mut metrics = sync.subscribe_metrics();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let cloned = metrics.try_recv().unwrap();
+        assert_eq!(cloned.files_copied, 2);
+        assert_eq!(
+            cloned.bytes_copied,
+            manifest_len + "library;\n".len() as u64
+        );
+
+        write_newer(&root.join("src").join("main.sw"), "library;\n\nfn f() {}\n");
+        sync.resync().unwrap();
+        let resynced = metrics.try_recv().unwrap();
+        assert_eq!(resynced.files_copied, 1);
+        assert_eq!(
+            resynced.bytes_copied,
+            "library;\n\nfn f() {}\n".len() as u64
+        );
+        assert!(metrics.try_recv().is_err());
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn watchers_publish_changes_to_subscribers() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let mut events = sync.subscribe();
+        sync.watch_and_sync_manifest().unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        sync.watch_and_sync_sources(tx).unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        // Wait for the event, skipping the ones published by the other watcher.
+        async fn next_event(
+            events: &mut broadcast::Receiver<WatchEvent>,
+            is_expected: impl Fn(&WatchEvent) -> bool,
+        ) -> WatchEvent {
+            tokio::time::timeout(Duration::from_secs(10), async {
+                loop {
+                    let event = events.recv().await.unwrap();
+                    if is_expected(&event) {
+                        return event;
+                    }
+                }
+            })
+            .await
+            .unwrap()
+        }
+
+        // Let the source watcher start watching before changing the workspace.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        fs::write(root.join("src").join("lib.sw"), "library;\n").unwrap();
+        let event = next_event(&mut events, |event| {
+            matches!(event, WatchEvent::SourceChanged(_))
+        })
+        .await;
+        assert_eq!(
+            event,
+            WatchEvent::SourceChanged(vec![temp_dir.join("src").join("lib.sw")])
+        );
+
+        fs::write(
+            root.join(MANIFEST_FILE_NAME),
+            fs::read_to_string(root.join(MANIFEST_FILE_NAME)).unwrap() + "\n",
+        )
+        .unwrap();
+        let event = next_event(&mut events, |event| {
+            matches!(event, WatchEvent::ManifestChanged(_))
+        })
+        .await;
+        assert_eq!(
+            event,
+            WatchEvent::ManifestChanged(vec![root.join(MANIFEST_FILE_NAME)])
+        );
+
+        sync.stop_watchers();
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn paused_watchers_drop_their_events() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let mut events = sync.subscribe();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        sync.watch_and_sync_sources(tx).unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        // Let the source watcher start watching before changing the workspace.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sync.pause_watcher();
+        assert!(sync.is_watcher_paused());
+        fs::write(root.join("src").join("paused.sw"), "library;\n").unwrap();
+        // Wait for the debounced event to be delivered and dropped.
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(events.try_recv().is_err());
+        assert!(!temp_dir.join("src").join("paused.sw").exists());
+
+        sync.resume_watcher();
+        fs::write(root.join("src").join("resumed.sw"), "library;\n").unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event,
+            WatchEvent::SourceChanged(vec![temp_dir.join("src").join("resumed.sw")])
+        );
+
+        sync.close().await;
+    }
+
+    #[tokio::test]
+    async fn hard_resync_rebuilds_temp_dir_and_restarts_watchers() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.watch_and_sync_manifest().unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        sync.watch_and_sync_sources(tx).unwrap();
+
+        let old_temp_dir = sync.temp_dir().unwrap();
+        fs::write(old_temp_dir.join("src").join("stale.sw"), "library;\n").unwrap();
+
+        sync.hard_resync().unwrap();
+
+        let temp_dir = sync.temp_dir().unwrap();
+        assert_ne!(temp_dir, old_temp_dir);
+        assert!(!old_temp_dir.exists());
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        assert!(!temp_dir.join("src").join("stale.sw").exists());
+        assert!(sync.notify_join_handle.read().is_some());
+        assert!(sync.source_watch_join_handle.read().is_some());
+        assert!(sync.status().watcher_active);
+
+        sync.stop_watchers();
+        assert!(!sync.status().watcher_active);
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn hard_resync_keeps_the_registered_roots() {
+        let projects = tempfile::tempdir().unwrap();
+        let root = projects.path().canonicalize().unwrap();
+        write_package(&root.join("first"), "first", "");
+        write_package(&root.join("second"), "second", "");
+        let main = Path::new("src").join("main.sw");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root.join("first"))
+            .unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.register_root(&root.join("second")).unwrap();
+
+        sync.hard_resync().unwrap();
+        let second_url = get_url_from_path(&root.join("second").join(&main)).unwrap();
+        let second_temp_path = sync
+            .workspace_to_temp_url(&second_url)
+            .unwrap()
+            .to_file_path()
+            .unwrap();
+        assert_eq!(fs::read_to_string(second_temp_path).unwrap(), "library;\n");
+        sync.remove_temp_dir();
+    }
+
+    #[test]
+    fn hard_resync_keeps_the_old_directories_on_failure() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        let temp_dir = sync.temp_dir().unwrap();
+
+        fs::remove_file(root.join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(sync.hard_resync().is_err());
+        assert_eq!(sync.manifest_dir().unwrap(), root);
+        assert_eq!(sync.temp_dir().unwrap(), temp_dir);
+        assert!(temp_dir.join("src").join("main.sw").exists());
+        sync.remove_temp_dir();
+    }
+
+    #[tokio::test]
+    async fn close_joins_the_watchers_before_removing_the_temp_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        write_package(&root, "project", "");
+
+        let sync = SyncWorkspace::new();
+        sync.create_temp_dir_from_workspace(&root).unwrap();
+        sync.clone_manifest_dir_to_temp().unwrap();
+        sync.watch_and_sync_manifest().unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        sync.watch_and_sync_sources(tx).unwrap();
+
+        let watchers: Vec<_> = [&sync.notify_join_handle, &sync.source_watch_join_handle]
+            .iter()
+            .map(|handle| handle.read().as_ref().unwrap().abort_handle())
+            .collect();
+        assert!(watchers.iter().all(|watcher| !watcher.is_finished()));
+        let temp_root = sync.temp_root().unwrap();
+
+        sync.close().await;
+        assert!(watchers.iter().all(|watcher| watcher.is_finished()));
+        assert!(sync.notify_join_handle.read().is_none());
+        assert!(sync.source_watch_join_handle.read().is_none());
+        assert!(!sync.status().watcher_active);
+        assert!(!temp_root.exists());
+    }
+
+    #[test]
+    fn watch_and_sync_manifest_fails_on_missing_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let missing = project.path().canonicalize().unwrap().join("missing");
+
+        let sync = SyncWorkspace::new();
+        sync.insert_directory(Directory::Manifest, missing.clone());
+        sync.insert_directory(Directory::Temp, project.path().join("temp"));
+
+        let err = sync.watch_and_sync_manifest().unwrap_err();
+        assert!(matches!(
+            err,
+            LanguageServerError::DirectoryError(DirectoryError::WatchFailed { path, .. })
+                if path == missing.to_string_lossy()
+        ));
+        assert!(sync.notify_join_handle.read().is_none());
+    }
+
+    #[tokio::test]
+    async fn watch_and_sync_sources_fails_on_missing_dir() {
+        let project = tempfile::tempdir().unwrap();
+        let missing = project.path().canonicalize().unwrap().join("missing");
+
+        let sync = SyncWorkspace::new();
+        sync.insert_directory(Directory::Manifest, missing.clone());
+        sync.insert_directory(Directory::Temp, project.path().join("temp"));
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let err = sync.watch_and_sync_sources(tx).unwrap_err();
+        assert!(matches!(
+            err,
+            DirectoryError::WatchFailed { path, .. } if path == missing.to_string_lossy()
+        ));
+        assert!(sync.source_watch_join_handle.read().is_none());
+        assert!(sync.source_changes.read().is_none());
+    }
+
+    #[test]
+    fn cached_directories_follow_inserts() {
+        let sync = SyncWorkspace::new();
+        assert_eq!(
+            sync.manifest_dir(),
+            Err(DirectoryError::ManifestDirNotFound)
+        );
+
+        sync.insert_directory(Directory::Manifest, PathBuf::from("/project"));
+        sync.insert_directory(Directory::Temp, PathBuf::from("/tmp/project"));
+        sync.insert_directory(
+            Directory::MemberManifest("member".to_string()),
+            PathBuf::from("/project/member"),
+        );
+        sync.insert_directory(
+            Directory::MemberTemp("member".to_string()),
+            PathBuf::from("/tmp/project/member"),
+        );
+        assert_eq!(sync.manifest_dir(), Ok(PathBuf::from("/project")));
+        assert_eq!(sync.temp_dir(), Ok(PathBuf::from("/tmp/project")));
+        assert_eq!(sync.members(), vec!["member"]);
+        assert_eq!(
+            sync.member_temp_dir("member"),
+            Ok(PathBuf::from("/tmp/project/member"))
+        );
+
+        sync.insert_directory(Directory::Temp, PathBuf::from("/tmp/other"));
+        assert_eq!(sync.temp_dir(), Ok(PathBuf::from("/tmp/other")));
     }
-    Ok(has_relevant_files)
 }
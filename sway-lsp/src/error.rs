@@ -27,14 +27,29 @@ pub enum LanguageServerError {
     ProgramsIsNone,
     #[error("Unable to acquire a semaphore permit for parsing")]
     UnableToAcquirePermit,
+    #[error(
+        "Forc.lock is out of date, run `forc update`. Mismatched dependencies: {}",
+        dependencies.join(", ")
+    )]
+    LockFileOutOfDate { dependencies: Vec<String> },
+    #[error("Resync was cancelled")]
+    ResyncCancelled,
+    #[error("`forc` wasn't found on the PATH, install it to build the temp workspace")]
+    ForcNotFound,
+    #[error("forc build failed: {0}")]
+    ForcBuildFailed(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum DocumentError {
     #[error("No document found at {:?}", path)]
     DocumentNotFound { path: String },
+    #[error("No directory found at {:?}", dir)]
+    ProjectDirNotFound { dir: String },
     #[error("Missing Forc.toml in {:?}", dir)]
     ManifestFileNotFound { dir: String },
+    #[error("Invalid Forc.toml in {:?} : {:?}", dir, err)]
+    InvalidManifestFile { dir: String, err: String },
     #[error("Cannot get member manifest files for the manifest at {:?}", dir)]
     MemberManifestsFailed { dir: String },
     #[error("Cannot get lock file path for the manifest at {:?}", dir)]
@@ -52,6 +67,21 @@ pub enum DocumentError {
     PermissionDenied { path: String },
     #[error("IO error for path {:?} : {:?}", path, error)]
     IOError { path: String, error: String },
+    #[error(
+        "Invalid key {:?} at line {} of manifest {:?} : {:?}",
+        key,
+        line,
+        path,
+        error
+    )]
+    InvalidManifestKey {
+        path: String,
+        key: String,
+        line: usize,
+        error: String,
+    },
+    #[error("No dependency {:?} found in manifest {:?}", name, path)]
+    DependencyNotFound { name: String, path: String },
     #[error("Invalid range {:?}", range)]
     InvalidRange { range: Range },
 }
@@ -66,14 +96,26 @@ pub enum DirectoryError {
     CantExtractProjectName { dir: String },
     #[error("Failed to create hidden .lsp_locks directory: {0}")]
     LspLocksDirFailed(String),
-    #[error("Failed to create temp directory")]
-    TempDirFailed,
+    #[error("Failed to create temp directory in {:?} : {:?}", path, err)]
+    TempDirFailed { path: String, err: String },
     #[error("Failed to canonicalize path")]
     CanonicalizeFailed,
     #[error("Failed to copy workspace contents to temp directory")]
     CopyContentsFailed,
-    #[error("Failed to create build plan. {0}")]
-    StripPrefixError(std::path::StripPrefixError),
+    #[error("Copy {:?} doesn't match its source", path)]
+    CopyVerificationFailed { path: String },
+    #[error("Failed to copy {:?} to {:?} : {:?}", src, dst, error)]
+    CopyFileFailed {
+        src: String,
+        dst: String,
+        error: String,
+    },
+    #[error("Failed to sync {:?} with the temp directory : {:?}", path, err)]
+    SyncPathFailed { path: String, err: String },
+    #[error("Unable to strip prefix {:?} from path {:?}", prefix, path)]
+    StripPrefixError { path: String, prefix: String },
+    #[error("{:?} isn't in any of the registered projects", url)]
+    NoRootForUrl { url: String },
     #[error("Unable to create Url from path {:?}", path)]
     UrlFromPathFailed { path: String },
     #[error("Unable to create Url from span {:?}", span)]
@@ -84,6 +126,21 @@ pub enum DirectoryError {
     SpanFromPathFailed { path: String },
     #[error("No program ID found for path {:?}", path)]
     ProgramIdNotFound { path: String },
+    #[error("Unable to watch path {:?} for changes : {:?}", path, err)]
+    WatchFailed { path: String, err: String },
+    #[error(
+        "Path {:?} of dependency {} in {:?} resolves outside of {:?}",
+        path,
+        dependency,
+        manifest_dir,
+        root
+    )]
+    DependencyOutsideWorkspace {
+        dependency: String,
+        path: String,
+        manifest_dir: String,
+        root: String,
+    },
 }
 
 #[derive(Debug, Error, PartialEq, Eq)]
@@ -12,9 +12,11 @@ use lsp_types::{
 };
 use std::{
     collections::BTreeMap,
+    ffi::OsStr,
     path::PathBuf,
     sync::{atomic::Ordering, Arc},
 };
+use sway_utils::constants::MANIFEST_FILE_NAME;
 
 pub async fn handle_did_open_text_document(
     state: &ServerState,
@@ -97,8 +99,24 @@ pub async fn handle_did_change_text_document(
         .await?;
     state
         .documents
-        .write_changes_to_file(&uri, &params.content_changes)
+        .write_changes_to_file(
+            &uri,
+            &params.content_changes,
+            session.sync.normalize_line_endings,
+        )
         .await?;
+    // Build with the unsaved manifest, with its dependency paths rewritten like the saved one's.
+    if let Some(manifest_path) = manifest_path(&params.text_document.uri) {
+        let contents = state
+            .documents
+            .get_text_document(&uri)?
+            .get_text()
+            .to_string();
+        // A half-written manifest keeps the previous overlay, so that the session still compiles.
+        if let Err(err) = session.sync.set_manifest_overlay(manifest_path, contents) {
+            tracing::warn!("Failed to read the unsaved manifest: {}", err);
+        }
+    }
 
     let file_versions = file_versions(
         &state.documents,
@@ -117,6 +135,13 @@ pub async fn handle_did_change_text_document(
     Ok(())
 }
 
+/// The path of the file at `uri` if it is a manifest.
+fn manifest_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path()
+        .ok()
+        .filter(|path| path.file_name() == Some(OsStr::new(MANIFEST_FILE_NAME)))
+}
+
 fn file_versions(
     documents: &Documents,
     uri: &Url,
@@ -144,7 +169,18 @@ pub(crate) async fn handle_did_save_text_document(
     let (uri, session) = state
         .uri_and_session_from_workspace(&params.text_document.uri)
         .await?;
-    session.sync.resync()?;
+    // The saved manifest is read from the disk again.
+    if let Some(manifest_path) = manifest_path(&params.text_document.uri) {
+        session.sync.clear_manifest_overlay(&manifest_path)?;
+    }
+    match session.sync.resync_debounced().await {
+        Ok(true) => {}
+        // A later save resyncs and compiles the workspace instead.
+        Ok(false) => return Ok(()),
+        // The workspace was still synced, so compile it anyway and let the user know.
+        Err(err @ LanguageServerError::LockFileOutOfDate { .. }) => tracing::warn!("{}", err),
+        Err(err) => return Err(err),
+    }
     let file_versions = file_versions(&state.documents, &uri, None);
     send_new_compilation_request(state, session.clone(), &uri, None, false, file_versions);
     state.wait_for_parsing().await;
@@ -7,7 +7,7 @@ use crate::{
         session::{self, Session},
     },
     error::{DirectoryError, DocumentError, LanguageServerError},
-    utils::{debug, keyword_docs::KeywordDocs},
+    utils::{debug, document::get_path_from_url, keyword_docs::KeywordDocs},
 };
 use crossbeam_channel::{Receiver, Sender};
 use dashmap::{mapref::multiple::RefMulti, DashMap};
@@ -28,6 +28,7 @@ use std::{
     },
 };
 use sway_core::LspConfig;
+use sway_utils::SWAY_EXTENSION;
 use tokio::sync::Notify;
 use tower_lsp::{jsonrpc, Client};
 
@@ -42,7 +43,7 @@ pub struct ServerState {
     /// This cache limits memory usage by maintaining a fixed number of active sessions, automatically
     /// evicting the least recently used sessions when the capacity is reached.
     pub sessions: LruSessionCache,
-    pub documents: Documents,
+    pub documents: Arc<Documents>,
     // Compilation thread related fields
     pub(crate) retrigger_compilation: Arc<AtomicBool>,
     pub is_compiling: Arc<AtomicBool>,
@@ -62,7 +63,7 @@ impl Default for ServerState {
             config: Arc::new(RwLock::new(Config::default())),
             keyword_docs: Arc::new(KeywordDocs::new()),
             sessions: LruSessionCache::new(DEFAULT_SESSION_CACHE_CAPACITY),
-            documents: Documents::new(),
+            documents: Arc::new(Documents::new()),
             retrigger_compilation: Arc::new(AtomicBool::new(false)),
             is_compiling: Arc::new(AtomicBool::new(false)),
             cb_tx,
@@ -264,25 +265,31 @@ impl ServerState {
         }
     }
 
-    pub fn shutdown_server(&self) -> jsonrpc::Result<()> {
-        let _p = tracing::trace_span!("shutdown_server").entered();
-        tracing::info!("Shutting Down the Sway Language Server");
+    pub async fn shutdown_server(&self) -> jsonrpc::Result<()> {
+        let sessions: Vec<_> = {
+            let _p = tracing::trace_span!("shutdown_server").entered();
+            tracing::info!("Shutting Down the Sway Language Server");
 
-        // Drain pending compilation requests
-        while self.cb_rx.try_recv().is_ok() {}
+            // Drain pending compilation requests
+            while self.cb_rx.try_recv().is_ok() {}
 
-        // Set the retrigger_compilation flag to true so that the compilation exits early
-        self.retrigger_compilation.store(true, Ordering::SeqCst);
+            // Set the retrigger_compilation flag to true so that the compilation exits early
+            self.retrigger_compilation.store(true, Ordering::SeqCst);
 
-        // Send a terminate message to the compilation thread
-        self.cb_tx
-            .send(TaskMessage::Terminate)
-            .expect("failed to send terminate message");
+            // Send a terminate message to the compilation thread
+            self.cb_tx
+                .send(TaskMessage::Terminate)
+                .expect("failed to send terminate message");
 
-        let _ = self.sessions.iter().map(|item| {
-            let session = item.value();
-            session.shutdown();
-        });
+            // Don't hold the session cache's locks while waiting for the watchers to finish.
+            self.sessions
+                .iter()
+                .map(|item| item.value().clone())
+                .collect()
+        };
+        for session in sessions {
+            session.shutdown().await;
+        }
         Ok(())
     }
 
@@ -340,6 +347,7 @@ impl ServerState {
         workspace_uri: &Url,
     ) -> Result<(Url, Arc<Session>), LanguageServerError> {
         let session = self.url_to_session(workspace_uri).await?;
+        session.sync.ensure_temp_dir()?;
         let uri = session.sync.workspace_to_temp_url(workspace_uri)?;
         Ok((uri, session))
     }
@@ -349,16 +357,27 @@ impl ServerState {
         let manifest_dir = if let Some(cached_dir) = self.manifest_cache.get(uri) {
             cached_dir.clone()
         } else {
-            // Otherwise, find the manifest directory from the uri and cache it
-            let path = PathBuf::from(uri.path());
+            // Otherwise, find the manifest directory from the uri and cache it. The members of a
+            // workspace share the session of the workspace, which syncs all of them.
+            let path = get_path_from_url(uri)?;
             let manifest = PackageManifestFile::from_dir(&path).map_err(|_| {
                 DocumentError::ManifestFileNotFound {
                     dir: path.to_string_lossy().to_string(),
                 }
             })?;
-            let dir = Arc::new(
+            let workspace =
                 manifest
-                    .path()
+                    .workspace()
+                    .map_err(|err| DocumentError::InvalidManifestFile {
+                        dir: manifest.dir().to_string_lossy().to_string(),
+                        err: err.to_string(),
+                    })?;
+            let manifest_path = match &workspace {
+                Some(workspace) => workspace.path(),
+                None => manifest.path(),
+            };
+            let dir = Arc::new(
+                manifest_path
                     .parent()
                     .ok_or(DirectoryError::ManifestDirNotFound)?
                     .to_path_buf(),
@@ -374,12 +393,55 @@ impl ServerState {
 
         // If no session can be found, then we need to call init and insert a new session into the map
         let session = Arc::new(Session::new());
-        session.init(uri, &self.documents).await?;
+        session.init(&manifest_dir, &self.documents).await?;
+        self.recompile_on_external_changes(&session);
         self.sessions
             .insert((*manifest_dir).clone(), session.clone());
 
         Ok(session)
     }
+
+    /// Start watching the session's source files and request a new compilation whenever
+    /// the watcher syncs files that were changed outside of the editor.
+    fn recompile_on_external_changes(&self, session: &Arc<Session>) {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Vec<PathBuf>>();
+        if let Err(err) = session.sync.watch_and_sync_sources(tx) {
+            tracing::error!("Failed to watch the source files: {}", err);
+            return;
+        }
+
+        let session = Arc::downgrade(session);
+        let cb_tx = self.cb_tx.clone();
+        let is_compiling = self.is_compiling.clone();
+        let retrigger_compilation = self.retrigger_compilation.clone();
+        let config = self.config.clone();
+        tokio::spawn(async move {
+            while let Some(paths) = rx.recv().await {
+                let Some(session) = session.upgrade() else {
+                    return;
+                };
+                let Some(uri) = paths
+                    .iter()
+                    .find(|path| path.extension().is_some_and(|ext| ext == SWAY_EXTENSION))
+                    .and_then(|path| Url::from_file_path(path).ok())
+                else {
+                    continue;
+                };
+                if is_compiling.load(Ordering::SeqCst) {
+                    retrigger_compilation.store(true, Ordering::SeqCst);
+                }
+                // If a compilation request is already pending it will pick up the synced files.
+                let _ = cb_tx.try_send(TaskMessage::CompilationContext(CompilationContext {
+                    session: Some(session),
+                    uri: Some(uri),
+                    version: None,
+                    optimized_build: false,
+                    gc_options: config.read().garbage_collection.clone(),
+                    file_versions: BTreeMap::new(),
+                }));
+            }
+        });
+    }
 }
 
 /// A Least Recently Used (LRU) cache for storing and managing `Session` objects.
@@ -467,6 +529,49 @@ mod tests {
     use std::path::PathBuf;
     use std::sync::Arc;
 
+    #[tokio::test]
+    async fn members_of_a_workspace_share_its_session() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("Forc.toml"),
+            "[workspace]\nmembers = [\"member_a\", \"member_b\"]\n",
+        )
+        .unwrap();
+        for name in ["member_a", "member_b"] {
+            std::fs::create_dir_all(root.join(name).join("src")).unwrap();
+            std::fs::write(
+                root.join(name).join("Forc.toml"),
+                format!("[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"{name}\"\nimplicit-std = false\n"),
+            )
+            .unwrap();
+            std::fs::write(root.join(name).join("src").join("main.sw"), "library;\n").unwrap();
+        }
+
+        let state = ServerState::default();
+        let member_url =
+            |name: &str| Url::from_file_path(root.join(name).join("src").join("main.sw")).unwrap();
+        let (temp_url_a, session_a) = state
+            .uri_and_session_from_workspace(&member_url("member_a"))
+            .await
+            .unwrap();
+        let (temp_url_b, session_b) = state
+            .uri_and_session_from_workspace(&member_url("member_b"))
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&session_a, &session_b));
+        assert_eq!(session_a.sync.manifest_dir().unwrap(), root);
+        let temp_dir = session_a.sync.temp_dir().unwrap();
+        for (temp_url, name) in [(temp_url_a, "member_a"), (temp_url_b, "member_b")] {
+            assert_eq!(
+                temp_url.to_file_path().unwrap(),
+                temp_dir.join(name).join("src").join("main.sw")
+            );
+            assert!(temp_url.to_file_path().unwrap().exists());
+        }
+        session_a.shutdown().await;
+    }
+
     #[test]
     fn test_lru_session_cache_insertion_and_retrieval() {
         let cache = LruSessionCache::new(2);
@@ -401,7 +401,7 @@ impl Parse for ItemConst {
             ty.parse(ctx);
         }
 
-        if let Some(expr) = self.expr_opt.as_ref() {
+        if let Some((.., expr)) = self.expr_opt.as_ref() {
             expr.parse(ctx);
         }
     }
@@ -118,7 +118,7 @@ macro_rules! test_lsp_capability {
 
         // Call the specific LSP capability function that was passed in.
         let _ = $capability(&server, &uri).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     }};
 }
 
@@ -150,7 +150,7 @@ fn did_open() {
     run_async!({
         let server = ServerState::default();
         let _ = open(&server, e2e_test_dir().join("src/main.sw")).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -332,7 +332,7 @@ fn compilation_succeeds_when_triggered_from_module() {
             test_fixtures_dir().join("tokens/modules/src/test_mod.sw"),
         )
         .await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -342,7 +342,7 @@ fn show_ast() {
         let server = ServerState::default();
         let uri = open(&server, e2e_test_dir().join("src/main.sw")).await;
         lsp::show_ast_request(&server, &uri, "typed", None).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -353,7 +353,7 @@ fn visualize() {
         let server = ServerState::default();
         let uri = open(&server, e2e_test_dir().join("src/main.sw")).await;
         lsp::visualize_request(&server, &uri, "build_plan").await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -374,7 +374,7 @@ fn go_to_definition() {
             def_path: uri.as_str(),
         };
         lsp::definition_check(&server, &go_to).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -430,7 +430,7 @@ fn go_to_definition_for_fields() {
         // Foo
         lsp::definition_check(&server, &opt_go_to).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -480,7 +480,7 @@ fn go_to_definition_inside_turbofish() {
         lsp::definition_check_with_req_offset(&server, &mut res_go_to, 23, 27).await;
         lsp::definition_check_with_req_offset(&server, &mut res_go_to, 24, 33).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -594,7 +594,7 @@ fn go_to_definition_for_matches() {
         // ExampleStruct.variable
         lsp::definition_check(&server, &go_to).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -637,7 +637,7 @@ fn go_to_definition_for_modules() {
         // mod deep_mod;
         lsp::definition_check(&server, &opt_go_to).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1020,7 +1020,7 @@ fn go_to_definition_for_paths() {
         // dfun
         lsp::definition_check(&server, &go_to).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1051,7 +1051,7 @@ fn go_to_definition_for_traits() {
         trait_go_to.req_char = 20;
         trait_go_to.def_line = 3;
         lsp::definition_check(&server, &trait_go_to).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1144,7 +1144,7 @@ fn go_to_definition_for_variables() {
         lsp::definition_check_with_req_offset(&server, &mut go_to, 60, 50).await;
         lsp::definition_check_with_req_offset(&server, &mut go_to, 61, 50).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1592,7 +1592,7 @@ fn go_to_definition_for_storage() {
         // storage.var1.z.x
         lsp::definition_check(&server, &go_to).await;
 
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1619,7 +1619,7 @@ fn hover_docs_for_consts() {
         hover.req_char = 49;
         hover.documentation = vec![" CONSTANT_2 has a value of 200"];
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1641,7 +1641,7 @@ fn hover_docs_for_functions_vscode() {
         documentation: vec!["```sway\npub fn bar(p: Point) -> Point\n```\n---\n A function declaration with struct as a function parameter\n\n---\nGo to [Point](command:sway.goToLocation?%5B%7B%22range%22%3A%7B%22end%22%3A%7B%22character%22%3A1%2C%22line%22%3A5%7D%2C%22start%22%3A%7B%22character%22%3A0%2C%22line%22%3A2%7D%7D%2C%22uri%22%3A%22file","sway%2Fsway-lsp%2Ftests%2Ffixtures%2Ftokens%2Ffunctions%2Fsrc%2Fmain.sw%22%7D%5D \"functions::Point\")"],
     };
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1680,7 +1680,7 @@ fn hover_docs_for_structs() {
             documentation: vec!["```sway\nstruct MyStruct\n```\n---\n My struct type"],
         };
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1709,7 +1709,7 @@ fn hover_docs_for_enums() {
         hover.req_char = 29;
         hover.documentation = vec![" Docs for variants"];
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1726,7 +1726,7 @@ fn hover_docs_for_abis() {
             documentation: vec!["```sway\nabi MyContract\n```\n---\n Docs for MyContract"],
         };
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1747,7 +1747,7 @@ fn hover_docs_for_variables() {
             documentation: vec!["```sway\nlet variable8: ContractCaller<TestAbi>\n```\n---"],
         };
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1764,7 +1764,7 @@ fn hover_docs_with_code_examples() {
             documentation: vec!["```sway\nstruct Data\n```\n---\n Struct holding:\n\n 1. A `value` of type `NumberOrString`\n 2. An `address` of type `u64`"],
         };
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1792,7 +1792,7 @@ fn hover_docs_for_self_keywords_vscode() {
             "\"Go to implementations\")"
         ];
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1810,7 +1810,7 @@ fn hover_docs_for_self_keywords() {
         };
 
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1836,7 +1836,7 @@ fn hover_docs_for_boolean_keywords() {
         hover.req_char = 31;
         hover.documentation = vec!["\n```sway\ntrue\n```\n\n---\n\n A value of type [`bool`] representing logical **true**.\n\n Logically `true` is not equal to [`false`].\n\n ## Control structures that check for **true**\n\n Several of Sway's control structures will check for a `bool` condition evaluating to **true**.\n\n   * The condition in an [`if`] expression must be of type `bool`.\n     Whenever that condition evaluates to **true**, the `if` expression takes\n     on the value of the first block. If however, the condition evaluates\n     to `false`, the expression takes on value of the `else` block if there is one.\n\n   * [`while`] is another control flow construct expecting a `bool`-typed condition.\n     As long as the condition evaluates to **true**, the `while` loop will continually\n     evaluate its associated block.\n\n   * [`match`] arms can have guard clauses on them."];
         lsp::hover_request(&server, &hover).await;
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -1953,7 +1953,7 @@ fn rename() {
             new_name: "NEW_TYPE_NAME", // from ZERO_B256
         };
         assert_eq!(lsp::prepare_rename_request(&server, &rename).await, None);
-        let _ = server.shutdown_server();
+        let _ = server.shutdown_server().await;
     });
 }
 
@@ -2153,7 +2153,7 @@ async fn write_all_example_asts() {
             lsp::show_ast_request(&server, &uri, "typed", example_dir).await;
         }
     }
-    let _ = server.shutdown_server();
+    let _ = server.shutdown_server().await;
 }
 
 #[test]
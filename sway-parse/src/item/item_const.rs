@@ -1,24 +1,65 @@
-use crate::{Parse, ParseResult, Parser};
+use crate::{lex, Parse, ParseResult, Parser, RESERVED_KEYWORDS};
 
-use sway_ast::ItemConst;
+use extension_trait::extension_trait;
+use sway_ast::{
+    keywords::{ColonToken, ConstToken, PubToken, SemicolonToken},
+    token::{Comment, CommentKind},
+    Expr, ItemConst, ItemConstSignature, Ty,
+};
+use sway_error::{
+    handler::{ErrorEmitted, Handler},
+    parser_error::ParseErrorKind,
+};
+use sway_features::ExperimentalFeatures;
+use sway_types::{ast::PunctKind, Ident, Span, Spanned};
+
+/// The visibility, `const` keyword, name and type of a const.
+type ConstPrefix = (
+    Option<PubToken>,
+    ConstToken,
+    Ident,
+    Option<(ColonToken, Ty)>,
+);
+
+/// Parses the tokens shared by [ItemConst] and [ItemConstSignature], up to the initializer.
+fn parse_const_prefix(parser: &mut Parser) -> ParseResult<ConstPrefix> {
+    let pub_token = parser.take();
+    let const_token = parser.parse()?;
+    let name = parse_const_name(parser)?;
+    let ty_opt = match parser.take() {
+        Some(colon_token) => {
+            let ty = parser.parse()?;
+            Some((colon_token, ty))
+        }
+        None => None,
+    };
+    Ok((pub_token, const_token, name, ty_opt))
+}
+
+/// Parses the name of a const, with a dedicated error naming the keyword if it is a reserved one.
+fn parse_const_name(parser: &mut Parser) -> ParseResult<Ident> {
+    if let Some(name) = parser.peek::<Ident>() {
+        if !name.is_raw_ident() && RESERVED_KEYWORDS.contains(name.as_str()) {
+            let span = name.span();
+            return Err(parser.emit_error_with_span(
+                ParseErrorKind::ReservedKeywordConstName { keyword: name },
+                span,
+            ));
+        }
+    }
+    parser.parse()
+}
 
 impl Parse for ItemConst {
     fn parse(parser: &mut Parser) -> ParseResult<ItemConst> {
-        let pub_token = parser.take();
-        let const_token = parser.parse()?;
-        let name = parser.parse()?;
-        let ty_opt = match parser.take() {
-            Some(colon_token) => {
-                let ty = parser.parse()?;
-                Some((colon_token, ty))
+        let (pub_token, const_token, name, ty_opt) = parse_const_prefix(parser)?;
+        let expr_opt = match parser.take() {
+            Some(eq_token) => {
+                let expr = parser.parse()?;
+                Some((eq_token, expr))
             }
             None => None,
         };
-        let eq_token_opt = parser.take();
-        let expr_opt = match &eq_token_opt {
-            Some(_eq) => Some(parser.parse()?),
-            None => None,
-        };
         // Use the default here since the braces parsing is expecting
         // a semicolon, that allows us to re-use the same parsing code
         // between associated consts and module-level consts.
@@ -28,9 +69,581 @@ impl Parse for ItemConst {
             const_token,
             name,
             ty_opt,
-            eq_token_opt,
             expr_opt,
             semicolon_token,
+            leading_trivia: Vec::new(),
+            trailing_trivia: None,
+        })
+    }
+}
+
+/// Sets the leading and trailing trivia of `item` from the `comments` the lexer found around it,
+/// once its visibility is known, as the leading comments are the ones right above its first token.
+///
+/// Only the `//` comments on whole lines right above the const are leading, without a blank line
+/// between them, and only a `//` comment after the semicolon on the same line is trailing. Doc
+/// comments are attributes and never trivia.
+pub(crate) fn attach_trivia(item: &mut ItemConst, comments: &[Comment]) {
+    let start = item.visibility_span().unwrap_or_else(|| item.const_span());
+    item.leading_trivia = leading_trivia(&start, comments);
+    item.trailing_trivia = trailing_trivia(&item.semicolon_token.span(), comments);
+}
+
+fn leading_trivia(start: &Span, comments: &[Comment]) -> Vec<Comment> {
+    if start.is_dummy() {
+        return Vec::new();
+    }
+    let text = &start.src().text;
+    let preceding =
+        &comments[..comments.partition_point(|comment| comment.span.start() < start.start())];
+    let mut next_start = start.start();
+    let mut leading = preceding
+        .iter()
+        .rev()
+        .take_while(|comment| {
+            let gap = &text[comment.span.end()..next_start];
+            next_start = comment.span.start();
+            comment.comment_kind == CommentKind::Newlined
+                && is_line_comment(comment)
+                && gap.trim().is_empty()
+                && gap.matches('\n').count() == 1
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    leading.reverse();
+    leading
+}
+
+fn trailing_trivia(semicolon: &Span, comments: &[Comment]) -> Option<Comment> {
+    if semicolon.is_dummy() {
+        return None;
+    }
+    let comment =
+        comments.get(comments.partition_point(|comment| comment.span.start() < semicolon.end()))?;
+    let gap = &semicolon.src().text[semicolon.end()..comment.span.start()];
+    (comment.comment_kind == CommentKind::Trailing
+        && is_line_comment(comment)
+        && gap.trim().is_empty()
+        && !gap.contains('\n'))
+    .then(|| comment.clone())
+}
+
+/// Returns true if `comment` is a `//` comment rather than a block one.
+fn is_line_comment(comment: &Comment) -> bool {
+    comment.span.as_str().starts_with("//")
+}
+
+impl Parse for ItemConstSignature {
+    fn parse(parser: &mut Parser) -> ParseResult<ItemConstSignature> {
+        let (pub_token, const_token, name, ty_opt) = parse_const_prefix(parser)?;
+        // The initializer spans all the tokens up to the semicolon. Semicolons nested in
+        // blocks are part of their block's token tree, so they don't end it early.
+        let initializer_opt = match parser.take() {
+            Some(eq_token) => {
+                let initializer = parser
+                    .consume_until::<SemicolonToken>()
+                    .ok_or_else(|| parser.emit_error(ParseErrorKind::ExpectedExpression))?;
+                Some((eq_token, initializer))
+            }
+            None => None,
+        };
+        let semicolon_token = parser.peek().unwrap_or_default();
+        Ok(ItemConstSignature {
+            pub_token,
+            const_token,
+            name,
+            ty_opt,
+            initializer_opt,
+            semicolon_token,
+        })
+    }
+}
+
+#[extension_trait]
+pub impl ItemConstSignatureExt for ItemConstSignature {
+    /// Parses the initializer that was skipped when parsing the signature, if the const has one.
+    fn parse_initializer(
+        &self,
+        handler: &Handler,
+        experimental: ExperimentalFeatures,
+    ) -> Result<Option<Expr>, ErrorEmitted> {
+        let Some((_eq_token, initializer)) = &self.initializer_opt else {
+            return Ok(None);
+        };
+        let ts = lex(
+            handler,
+            initializer.src().clone(),
+            initializer.start(),
+            initializer.end(),
+            initializer.source_id().copied(),
+        )?;
+        let mut parser = Parser::new(handler, &ts, experimental);
+        let expr = parser.parse()?;
+        if !parser.is_empty() {
+            return Err(parser.emit_error(ParseErrorKind::ExpectedPunct {
+                kinds: vec![PunctKind::Semicolon],
+            }));
+        }
+        Ok(Some(expr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+    use num_bigint::BigUint;
+    use sway_ast::{attribute::Annotated, literal::LitIntType, ConstValue};
+    use sway_error::{diagnostic::Level, error::CompileError};
+    use sway_types::SourceEngine;
+
+    /// Parses a module-level const, the only ones getting trivia.
+    fn parse_const_item(input: &str) -> ItemConst {
+        match parse::<sway_ast::Item>(input).value {
+            sway_ast::ItemKind::Const(item) => item,
+            _ => panic!("expected a const"),
+        }
+    }
+
+    #[test]
+    fn parse_const_token_spans() {
+        let item = parse::<ItemConst>("pub const FOO: u64 = 42;");
+        let spans: Vec<_> = item.token_spans().collect();
+        assert_eq!(
+            spans.iter().map(|span| span.as_str()).collect::<Vec<_>>(),
+            ["pub", "const", "FOO", "u64", "=", "42", ";"]
+        );
+        assert!(spans
+            .windows(2)
+            .all(|pair| pair[0].end() <= pair[1].start()));
+    }
+
+    #[test]
+    fn parse_const_token_spans_without_optional_tokens() {
+        let item = parse::<ItemConst>("const FOO;");
+        assert_eq!(
+            item.token_spans()
+                .map(|span| span.as_str().to_string())
+                .collect::<Vec<_>>(),
+            ["const", "FOO", ";"]
+        );
+    }
+
+    #[test]
+    fn parse_const_span() {
+        let item = parse::<ItemConst>("pub const FOO: u64 = 42;");
+        assert_eq!(item.span().as_str(), "pub const FOO: u64 = 42");
+        let item = parse::<ItemConst>("const FOO;");
+        assert_eq!(item.span().as_str(), "const FOO");
+    }
+
+    #[test]
+    fn display_const() {
+        let render = |input| parse::<ItemConst>(input).to_string();
+        assert_eq!(
+            render("pub   const FOO :u64=  1 + 2 ;"),
+            "pub const FOO: u64 = 1 + 2;"
+        );
+        assert_eq!(
+            render("const\nBAR: str[3]\n=\n__to_str_array(\"abc\");"),
+            "const BAR: str[3] = __to_str_array(\"abc\");"
+        );
+        assert_eq!(render("const BAZ = true;"), "const BAZ = true;");
+        assert_eq!(render("const r#contract;"), "const r#contract;");
+        // The rendered const parses back to itself.
+        let rendered = render("pub const  FOO:u64 = 1;");
+        assert_eq!(render(&rendered), rendered);
+    }
+
+    #[test]
+    fn display_const_keeps_inner_source_text() {
+        let render = |input| parse::<ItemConst>(input).to_string();
+        // Only the tokens of the const itself are respaced, formatting is left to swayfmt.
+        assert_eq!(
+            render("const  X :( u64 ,bool )= (1+2 , !true) ;"),
+            "const X: ( u64 ,bool ) = (1+2 , !true);"
+        );
+    }
+
+    #[test]
+    fn display_built_const() {
+        let item =
+            ItemConst::builder(Ident::new_no_span("FOO".to_string()), parse::<Ty>("u64")).build();
+        assert_eq!(item.to_string(), "const FOO: u64;");
+
+        let item = ItemConst::builder(Ident::new_no_span("FOO".to_string()), parse::<Ty>("u64"))
+            .public(true)
+            .initializer(parse::<Expr>("42_u64"))
+            .build();
+        assert_eq!(item.to_string(), "pub const FOO: u64 = 42_u64;");
+    }
+
+    #[test]
+    fn build_const() {
+        let ty = parse::<Ty>("u64");
+        let item = ItemConst::builder(Ident::new_no_span("FOO".to_string()), ty).build();
+        assert!(!item.is_public());
+        assert_eq!(item.name.as_str(), "FOO");
+        assert!(item.expr_opt.is_none());
+        // Only the type has a span that isn't a dummy.
+        assert_eq!(item.span().as_str(), "u64");
+
+        let item = ItemConst::builder(Ident::new_no_span("FOO".to_string()), parse::<Ty>("u64"))
+            .public(true)
+            .initializer(parse::<Expr>("42"))
+            .build();
+        assert!(item.is_public());
+        assert_eq!(
+            item.try_const_eval(),
+            Some(ConstValue::Int {
+                value: BigUint::from(42u8),
+                ty: LitIntType::U64,
+            })
+        );
+        // The type and the initializer were parsed from different sources.
+        assert!(item.span().is_dummy());
+
+        let ty = Ty::Infer {
+            underscore_token: Default::default(),
+        };
+        let item = ItemConst::builder(Ident::new_no_span("FOO".to_string()), ty).build();
+        assert!(item.span().is_dummy());
+    }
+
+    #[test]
+    fn parse_const_visibility() {
+        let item = parse::<ItemConst>("pub const FOO: u64 = 42;");
+        assert!(item.is_public());
+        assert_eq!(item.visibility_span().unwrap().as_str(), "pub");
+
+        let item = parse::<ItemConst>("const FOO: u64 = 42;");
+        assert!(!item.is_public());
+        assert!(item.visibility_span().is_none());
+    }
+
+    #[test]
+    fn parse_attributed_const_span() {
+        let item = parse::<Annotated<ItemConst>>(
+            "/// The answer.\n#[cfg(experimental_new_encoding = true)]\npub const FOO: u64 = 42;",
+        );
+        assert_eq!(item.attributes.len(), 2);
+        assert_eq!(item.value.span().as_str(), "pub const FOO: u64 = 42");
+        assert_eq!(
+            item.span().as_str(),
+            "/// The answer.\n#[cfg(experimental_new_encoding = true)]\npub const FOO: u64 = 42"
+        );
+
+        let item = parse::<Annotated<ItemConst>>("const FOO: u64 = 42;");
+        assert!(item.attributes.is_empty());
+        assert_eq!(item.span().as_str(), item.value.span().as_str());
+    }
+
+    #[test]
+    fn serialize_const_span() {
+        let source_engine = SourceEngine::default();
+        let source_id = source_engine.get_source_id(&std::path::PathBuf::from("/main.sw"));
+        let input = "\n    pub const FOO: u64 = 42; // trailing";
+        let handler = Handler::default();
+        let ts =
+            crate::token::lex(&handler, input.into(), 0, input.len(), Some(source_id)).unwrap();
+        let item: ItemConst = Parser::new(&handler, &ts, ExperimentalFeatures::default())
+            .parse()
+            .unwrap();
+
+        let value = serde_json::to_value(&item).unwrap();
+        let source_id = serde_json::to_value(source_id).unwrap();
+        assert_eq!(
+            value["span"],
+            serde_json::json!({ "start": 5, "end": 28, "source_id": source_id })
+        );
+        assert_eq!(&input[5..28], "pub const FOO: u64 = 42");
+    }
+
+    #[test]
+    fn deserialize_const_round_trips() {
+        let item = parse_const_item("// doc\npub const FOO: u64 = 1 + 2; // note\n");
+        let value = serde_json::to_value(&item).unwrap();
+        let deserialized: ItemConst = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&deserialized).unwrap(), value);
+        assert_eq!(deserialized.span(), item.span());
+        assert_eq!(deserialized.span_with_trivia(), item.span_with_trivia());
+        assert_eq!(deserialized.leading_trivia, item.leading_trivia);
+        assert_eq!(deserialized.trailing_trivia, item.trailing_trivia);
+        assert_eq!(deserialized.to_string(), item.to_string());
+    }
+
+    #[test]
+    fn serialize_const_structurally() {
+        let item = parse::<ItemConst>("pub const FOO: u64 = 1 + 2;");
+        assert_eq!(
+            serde_json::to_value(item.structural()).unwrap(),
+            serde_json::json!({
+                "is_public": true,
+                "name": "FOO",
+                "ty": "u64",
+                "expr": "1 + 2",
+            })
+        );
+
+        // The offsets of the tokens don't show, unlike in the span-inclusive serialization.
+        let shifted = parse::<ItemConst>("\n\n    pub const FOO: u64 = 1 + 2;");
+        assert_eq!(
+            serde_json::to_value(shifted.structural()).unwrap(),
+            serde_json::to_value(item.structural()).unwrap()
+        );
+        assert_ne!(
+            serde_json::to_value(&shifted).unwrap(),
+            serde_json::to_value(&item).unwrap()
+        );
+
+        let item = parse::<ItemConst>("const BAR;");
+        assert_eq!(
+            serde_json::to_value(item.structural()).unwrap(),
+            serde_json::json!({ "is_public": false, "name": "BAR", "ty": null, "expr": null })
+        );
+    }
+
+    #[test]
+    fn parse_const_trivia() {
+        let item = parse_const_item("// doc\n// more\nconst X: u64 = 1; // note\n");
+        assert_eq!(
+            item.leading_trivia
+                .iter()
+                .map(|comment| comment.span.as_str())
+                .collect::<Vec<_>>(),
+            ["// doc", "// more"]
+        );
+        assert!(item
+            .leading_trivia
+            .iter()
+            .all(|comment| comment.comment_kind == CommentKind::Newlined));
+        let trailing = item.trailing_trivia.as_ref().unwrap();
+        assert_eq!(trailing.span.as_str(), "// note");
+        assert_eq!(trailing.comment_kind, CommentKind::Trailing);
+        assert_eq!(item.span().as_str(), "const X: u64 = 1");
+        assert_eq!(
+            item.span_with_trivia().as_str(),
+            "// doc\n// more\nconst X: u64 = 1; // note"
+        );
+    }
+
+    #[test]
+    fn parse_const_without_trivia() {
+        let item = parse_const_item("const X: u64 = 1;");
+        assert!(item.leading_trivia.is_empty());
+        assert!(item.trailing_trivia.is_none());
+        assert_eq!(item.span_with_trivia().as_str(), item.span().as_str());
+
+        // Comments separated from the const by a blank line, or sharing a line with other
+        // tokens, are not its trivia, and neither are doc comments.
+        let item = parse_const_item("// far\n\n/// The answer.\nconst X: u64 = 1; /* block */\n");
+        assert!(item.leading_trivia.is_empty());
+        assert!(item.trailing_trivia.is_none());
+        let item = parse_const_item("/* block */ // other\nconst X: u64 = 1;\n// next\n");
+        assert!(item.leading_trivia.is_empty());
+        assert!(item.trailing_trivia.is_none());
+
+        // The trivia is attached when the const is parsed as an item, not by itself.
+        let item = parse::<ItemConst>("// doc\nconst X: u64 = 1; // note\n");
+        assert!(item.leading_trivia.is_empty());
+        assert!(item.trailing_trivia.is_none());
+    }
+
+    #[test]
+    fn parse_pub_const_item_trivia() {
+        let item = parse_const_item("// doc\npub const X: u64 = 1; // note\n");
+        assert_eq!(
+            item.span_with_trivia().as_str(),
+            "// doc\npub const X: u64 = 1; // note"
+        );
+    }
+
+    #[test]
+    fn parse_const_reserved_keyword_name() {
+        let input = "const contract: u64 = 42;";
+        let handler = Handler::default();
+        let ts = lex(&handler, input.into(), 0, input.len(), None).unwrap();
+        let res = Parser::new(&handler, &ts, ExperimentalFeatures::default()).parse::<ItemConst>();
+        assert!(res.is_err());
+
+        let (errors, _warnings) = handler.consume();
+        let [CompileError::Parse { error }] = errors.as_slice() else {
+            panic!("expected a single parse error, got {errors:?}");
+        };
+        assert_eq!(error.span.as_str(), "contract");
+        assert_eq!(
+            error.kind.to_string(),
+            "Constant name cannot be the reserved keyword `contract`."
+        );
+
+        // Raw identifiers can still be used to name a const after a keyword.
+        let item = parse::<ItemConst>("const r#contract: u64 = 42;");
+        assert_eq!(item.name.as_str(), "contract");
+    }
+
+    fn const_eval(input: &str) -> Option<ConstValue> {
+        parse::<ItemConst>(input).try_const_eval()
+    }
+
+    fn int(value: u64, ty: LitIntType) -> Option<ConstValue> {
+        Some(ConstValue::Int {
+            value: BigUint::from(value),
+            ty,
         })
     }
+
+    #[test]
+    fn const_eval_literals_and_operators() {
+        assert_eq!(const_eval("const X: u64 = 42;"), int(42, LitIntType::U64));
+        assert_eq!(const_eval("const X = 42;"), int(42, LitIntType::U64));
+        assert_eq!(const_eval("const X = 42u8;"), int(42, LitIntType::U8));
+        assert_eq!(
+            const_eval("const X: u32 = (1 + 2) * 3 - 4 / 2 % 3;"),
+            int(7, LitIntType::U32)
+        );
+        assert_eq!(
+            const_eval("const X: u8 = 2 ** 7;"),
+            int(128, LitIntType::U8)
+        );
+        assert_eq!(
+            const_eval("const X: u8 = 0b1100 & 0b1010 | 1 ^ 0;"),
+            int(9, LitIntType::U8)
+        );
+        assert_eq!(
+            const_eval("const X: u8 = 129 << 1;"),
+            int(2, LitIntType::U8)
+        );
+        assert_eq!(const_eval("const X: u8 = 1 << 8;"), int(0, LitIntType::U8));
+        assert_eq!(const_eval("const X: u8 = !0;"), int(255, LitIntType::U8));
+        assert_eq!(
+            const_eval("const X: bool = 1 < 2 && !(3 == 4);"),
+            Some(ConstValue::Bool(true))
+        );
+        assert_eq!(
+            const_eval("const X: str = \"foo\";"),
+            Some(ConstValue::String("foo".into()))
+        );
+    }
+
+    #[test]
+    fn const_eval_rejects_failing_or_unsupported_initializers() {
+        // Overflows and underflows.
+        assert_eq!(const_eval("const X: u8 = 255 + 1;"), None);
+        assert_eq!(const_eval("const X: u8 = 256;"), None);
+        assert_eq!(const_eval("const X: u64 = 0 - 1;"), None);
+        assert_eq!(const_eval("const X = 18446744073709551616;"), None);
+        assert_eq!(const_eval("const X: u256 = 2 ** 256;"), None);
+        assert_eq!(const_eval("const X: u64 = 1 / 0;"), None);
+        // Mismatched types.
+        assert_eq!(const_eval("const X: u8 = 1u16;"), None);
+        assert_eq!(const_eval("const X = 1u8 + 1u16;"), None);
+        assert_eq!(const_eval("const X: bool = 1;"), None);
+        // Anything requiring type checking.
+        assert_eq!(const_eval("const X: u64 = Y + 1;"), None);
+        assert_eq!(const_eval("const X: u64 = foo();"), None);
+        assert_eq!(const_eval("const X: MyU64 = 1;"), None);
+        assert_eq!(const_eval("const X: u64;"), None);
+    }
+
+    #[test]
+    fn lint_literal_out_of_range() {
+        let item = parse::<ItemConst>("const X: u8 = 256;");
+        let diagnostic = item.lint_literal_range().unwrap();
+        assert_eq!(diagnostic.level(), Level::Warning);
+        assert_eq!(
+            diagnostic.reason().unwrap().description(),
+            "Literal is out of range"
+        );
+        assert_eq!(diagnostic.issue().span().as_str(), "256");
+        assert_eq!(
+            diagnostic.issue().text(),
+            "Literal `256` does not fit in the type \"u8\" of constant \"X\"."
+        );
+        assert_eq!(diagnostic.hints[0].span().as_str(), "u8");
+
+        let item = parse::<ItemConst>(
+            "const X: u256 = 0x10000000000000000000000000000000000000000000000000000000000000000;",
+        );
+        assert!(item.lint_literal_range().is_some());
+    }
+
+    #[test]
+    fn lint_literal_range_resolves_source_paths() {
+        let source_engine = SourceEngine::default();
+        let source_id = source_engine.get_source_id(&std::path::PathBuf::from("/main.sw"));
+        let input = "const X: u8 = 256;";
+        let handler = Handler::default();
+        let ts =
+            crate::token::lex(&handler, input.into(), 0, input.len(), Some(source_id)).unwrap();
+        let item: ItemConst = Parser::new(&handler, &ts, ExperimentalFeatures::default())
+            .parse()
+            .unwrap();
+
+        let diagnostic = item.lint_literal_range().unwrap();
+        assert!(diagnostic.labels().is_empty());
+        let diagnostic = diagnostic.resolve_source_paths(&source_engine);
+        assert_eq!(diagnostic.labels().len(), 2);
+        assert_eq!(
+            diagnostic.issue().source_path().unwrap().as_path_buf(),
+            &std::path::PathBuf::from("/main.sw")
+        );
+    }
+
+    #[test]
+    fn lint_literal_range_is_silent_without_a_clear_mismatch() {
+        let lint = |input| parse::<ItemConst>(input).lint_literal_range().is_none();
+        assert!(lint("const X: u8 = 255;"));
+        assert!(lint("const X: u64 = 18446744073709551615;"));
+        // Not a literal, or not an unsigned integer type.
+        assert!(lint("const X: u8 = 255 + 1;"));
+        assert!(lint("const X: u8 = (256);"));
+        assert!(lint("const X: MyU8 = 256;"));
+        assert!(lint("const X: bool = 256;"));
+        assert!(lint("const X = 256;"));
+        assert!(lint("const X: u8;"));
+        // A suffix contradicting the annotation is a type error instead.
+        assert!(lint("const X: u8 = 256u16;"));
+    }
+
+    #[test]
+    fn parse_const_signature_defers_initializer() {
+        let item = parse::<ItemConstSignature>("pub const FOO: u64 = { let x = 1; x + 2 };");
+        assert!(item.pub_token.is_some());
+        assert_eq!(item.name.as_str(), "FOO");
+        let (_eq_token, initializer) = item.initializer_opt.as_ref().unwrap();
+        assert_eq!(initializer.as_str(), "{ let x = 1; x + 2 }");
+        assert_eq!(
+            item.span().as_str(),
+            "pub const FOO: u64 = { let x = 1; x + 2 }"
+        );
+
+        let handler = Handler::default();
+        let expr = item
+            .parse_initializer(&handler, ExperimentalFeatures::default())
+            .unwrap()
+            .unwrap();
+        assert!(matches!(expr, Expr::Block(_)));
+        assert_eq!(expr.span().as_str(), "{ let x = 1; x + 2 }");
+        assert!(!handler.has_errors());
+    }
+
+    #[test]
+    fn parse_const_signature_without_initializer() {
+        let item = parse::<ItemConstSignature>("const FOO: u64;");
+        assert!(item.initializer_opt.is_none());
+        assert!(item
+            .parse_initializer(&Handler::default(), ExperimentalFeatures::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn parse_const_signature_reports_invalid_initializer_lazily() {
+        let item = parse::<ItemConstSignature>("const FOO: u64 = 1 2;");
+        let handler = Handler::default();
+        assert!(item
+            .parse_initializer(&handler, ExperimentalFeatures::default())
+            .is_err());
+        assert!(handler.has_errors());
+    }
 }
@@ -23,6 +23,8 @@ mod item_trait;
 mod item_type_alias;
 mod item_use;
 
+pub use item_const::ItemConstSignatureExt;
+
 impl Parse for ItemKind {
     fn parse(parser: &mut Parser) -> ParseResult<ItemKind> {
         // FIXME(Centril): Visibility should be moved out of `ItemKind` variants,
@@ -58,6 +60,7 @@ impl Parse for ItemKind {
             ItemKind::Abi(item)
         } else if let Some(mut item) = parser.guarded_parse::<ConstToken, ItemConst>()? {
             item.pub_token = visibility.take();
+            item_const::attach_trivia(&mut item, parser.comments());
             parser.take::<SemicolonToken>().ok_or_else(|| {
                 parser.emit_error(ParseErrorKind::ExpectedPunct {
                     kinds: vec![sway_types::ast::PunctKind::Semicolon],
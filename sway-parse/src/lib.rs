@@ -21,6 +21,7 @@ mod where_clause;
 
 use crate::priv_prelude::*;
 pub use crate::{
+    item::ItemConstSignatureExt,
     keywords::RESERVED_KEYWORDS,
     parse::Parse,
     parser::Parser,
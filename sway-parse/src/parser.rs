@@ -4,7 +4,7 @@ use std::cell::RefCell;
 use sway_ast::keywords::Keyword;
 use sway_ast::literal::Literal;
 use sway_ast::token::{
-    DocComment, GenericTokenTree, Group, Punct, Spacing, TokenStream, TokenTree,
+    Comment, DocComment, GenericTokenTree, Group, Punct, Spacing, TokenStream, TokenTree,
 };
 use sway_ast::PubToken;
 use sway_error::error::CompileError;
@@ -19,6 +19,7 @@ use sway_types::{
 pub struct Parser<'a, 'e> {
     token_trees: &'a [TokenTree],
     full_span: Span,
+    comments: &'a [Comment],
     handler: &'e Handler,
     pub check_double_underscore: bool,
     pub experimental: ExperimentalFeatures,
@@ -33,6 +34,7 @@ impl<'a, 'e> Parser<'a, 'e> {
         Parser {
             token_trees: token_stream.token_trees(),
             full_span: token_stream.span(),
+            comments: token_stream.comments(),
             handler,
             check_double_underscore: true,
             experimental,
@@ -111,6 +113,7 @@ impl<'a, 'e> Parser<'a, 'e> {
         let mut fork = Parser {
             token_trees: self.token_trees,
             full_span: self.full_span.clone(),
+            comments: self.comments,
             handler: &handler,
             check_double_underscore: self.check_double_underscore,
             experimental: self.experimental,
@@ -172,6 +175,7 @@ impl<'a, 'e> Parser<'a, 'e> {
         let mut fork = Parser {
             token_trees: self.token_trees,
             full_span: self.full_span.clone(),
+            comments: self.comments,
             handler: &handler,
             check_double_underscore: self.check_double_underscore,
             experimental: self.experimental,
@@ -207,6 +211,7 @@ impl<'a, 'e> Parser<'a, 'e> {
         let mut fork = Parser {
             token_trees: self.token_trees,
             full_span: self.full_span.clone(),
+            comments: self.comments,
             handler: &handler,
             check_double_underscore: self.check_double_underscore,
             experimental: self.experimental,
@@ -261,6 +266,7 @@ impl<'a, 'e> Parser<'a, 'e> {
         let fork = Parser {
             token_trees: self.token_trees,
             full_span: self.full_span.clone(),
+            comments: self.comments,
             handler: &handler,
             check_double_underscore: self.check_double_underscore,
             experimental: self.experimental,
@@ -288,6 +294,7 @@ impl<'a, 'e> Parser<'a, 'e> {
                 let parser = Parser {
                     token_trees: token_stream.token_trees(),
                     full_span: token_stream.span(),
+                    comments: token_stream.comments(),
                     handler: self.handler,
                     check_double_underscore: self.check_double_underscore,
                     experimental: self.experimental,
@@ -307,6 +314,21 @@ impl<'a, 'e> Parser<'a, 'e> {
             .then_some(ParserConsumed { _priv: PhantomData })
     }
 
+    /// Consumes the tokens without parsing them, until the next one is a `P` or the end is reached.
+    ///
+    /// Returns the span of the consumed tokens, or `None` if there were none.
+    pub fn consume_until<P: Peek>(&mut self) -> Option<Span> {
+        let mut spans = Vec::new();
+        while let Some(token_tree) = self.token_trees.first() {
+            if self.peek::<P>().is_some() {
+                break;
+            }
+            spans.push(token_tree.span());
+            self.token_trees = &self.token_trees[1..];
+        }
+        (!spans.is_empty()).then(|| Span::join_all(spans))
+    }
+
     pub fn debug_tokens(&self) -> &[TokenTree] {
         let len = std::cmp::min(5, self.token_trees.len());
         &self.token_trees[..len]
@@ -328,6 +350,13 @@ impl<'a, 'e> Parser<'a, 'e> {
     pub fn full_span(&self) -> &Span {
         &self.full_span
     }
+
+    /// The comments between the tokens being parsed, in source order.
+    /// The comments nested in delimited groups are only seen after entering them.
+    pub fn comments(&self) -> &'a [Comment] {
+        self.comments
+    }
+
     /// Consume tokens while its line equals to `line`.
     ///
     /// # Warning
@@ -517,6 +546,7 @@ impl<'a> ParseRecoveryStrategies<'_, 'a, '_> {
             Parser {
                 token_trees: self.fork_token_trees,
                 full_span: self.fork_full_span.clone(),
+                comments: original.comments,
                 handler: &self.handler,
                 check_double_underscore: original.check_double_underscore,
                 experimental: original.experimental,
@@ -93,6 +93,8 @@ struct Lexer<'l> {
     src: &'l Source,
     source_id: &'l Option<SourceId>,
     stream: &'l mut CharIndices<'l>,
+    /// The end of the lexed range, which is the end of `src` unless only part of it is lexed.
+    end: usize,
 }
 
 pub fn lex(
@@ -122,6 +124,7 @@ pub fn lex_commented(
         src: &src,
         source_id,
         stream,
+        end,
     };
     let mut file_start_offset: usize = 0;
 
@@ -299,14 +302,8 @@ pub fn lex_commented(
         let span = span_one(&l, open_index, open_delimiter.as_open_char());
         error(l.handler, LexError { kind, span });
 
-        token_trees = lex_close_delimiter(
-            &mut l,
-            src.text.len(),
-            parent,
-            token_trees,
-            open_index,
-            open_delimiter,
-        );
+        token_trees =
+            lex_close_delimiter(&mut l, end, parent, token_trees, open_index, open_delimiter);
     }
     Ok(CommentedTokenStream {
         token_trees,
@@ -383,7 +380,7 @@ fn lex_block_comment(l: &mut Lexer<'_>, index: usize) -> Option<CommentedTokenTr
     let mut unclosed_indices = vec![index];
 
     let unclosed_multiline_comment = |l: &Lexer<'_>, unclosed_indices: Vec<_>| {
-        let span = span(l, *unclosed_indices.last().unwrap(), l.src.text.len() - 1);
+        let span = span(l, *unclosed_indices.last().unwrap(), l.end - 1);
         let kind = LexErrorKind::UnclosedMultilineComment { unclosed_indices };
         error(l.handler, LexError { kind, span });
         None
@@ -450,7 +447,7 @@ fn lex_string(
         };
         let (next_index, next_character) = l.stream.next().ok_or_else(|| {
             // last character may not be a unicode boundary
-            let mut end = l.src.text.len() - 1;
+            let mut end = l.end - 1;
             while !l.src.text.is_char_boundary(end) {
                 end -= 1;
             }
@@ -458,7 +455,7 @@ fn lex_string(
         })?;
         parsed.push(match next_character {
             '\\' => parse_escape_code(l)
-                .map_err(|e| e.unwrap_or_else(|| unclosed_string_lit(l, l.src.text.len())))?,
+                .map_err(|e| e.unwrap_or_else(|| unclosed_string_lit(l, l.end)))?,
             '"' => break,
             // do not allow text direction codepoints
             ALM | FSI | LRE | LRI | LRM | LRO | PDF | PDI | RLE | RLI | RLM | RLO => {
@@ -491,7 +488,7 @@ fn lex_char(
     let unclosed_char_lit = |l: &Lexer<'_>| {
         let err = LexError {
             kind: LexErrorKind::UnclosedCharLiteral { position: index },
-            span: span(l, index, l.src.text.len()),
+            span: span(l, index, l.end),
         };
         error(l.handler, err)
     };
@@ -669,7 +666,7 @@ fn lex_int_lit(
                 let span = span(l, index, end);
                 error(l.handler, LexError { kind, span })
             };
-            let (digit_pos, digit) = d.ok_or_else(|| incomplete_int_lit(l.src.text.len()))?;
+            let (digit_pos, digit) = d.ok_or_else(|| incomplete_int_lit(l.end))?;
             let radix_digit = digit
                 .to_digit(radix)
                 .ok_or_else(|| incomplete_int_lit(digit_pos))?;
@@ -693,7 +690,7 @@ fn lex_int_lit(
     let ty_opt = lex_int_ty_opt(l)?;
 
     let literal = Literal::Int(LitInt {
-        span: span(l, index, end_opt.unwrap_or(l.src.text.len())),
+        span: span(l, index, end_opt.unwrap_or(l.end)),
         parsed: big_uint,
         ty_opt,
         is_generated_b256: false,
@@ -715,7 +712,7 @@ fn lex_int_ty_opt(l: &mut Lexer<'_>) -> Result<Option<(LitIntType, Span)>> {
                 let _ = l.stream.next();
             }
             Some((pos, _)) => break *pos,
-            None => break l.src.text.len(),
+            None => break l.end,
         }
     };
     // Parse the suffix to a known one, or if unknown, recover by throwing it away.
@@ -782,7 +779,7 @@ fn lex_punctuation(l: &mut Lexer<'_>, index: usize, character: char) -> Option<C
 }
 
 fn span_until(l: &mut Lexer<'_>, start: usize) -> Span {
-    let end = l.stream.peek().map_or(l.src.text.len(), |(end, _)| *end);
+    let end = l.stream.peek().map_or(l.end, |(end, _)| *end);
     span(l, start, end)
 }
 
@@ -1057,4 +1054,24 @@ mod tests {
         );
         assert_eq!(tts.next(), None);
     }
+
+    #[test]
+    fn lex_range_spans_end_at_range() {
+        let input = "const FOO: u64 = { 1 } + 2; const BAR: u64 = 3;";
+        let start = input.find('{').unwrap();
+        let end = input.find(';').unwrap();
+        let handler = Handler::default();
+        let stream = lex(&handler, input.into(), start, end, None).unwrap();
+        assert!(!handler.has_errors());
+        let spans: Vec<_> = stream
+            .token_trees()
+            .iter()
+            .map(|tt| tt.span().as_str().to_string())
+            .collect();
+        assert_eq!(spans, ["{ 1 }", "+", "2"]);
+
+        let end = input.find('}').unwrap() + 1;
+        let stream = lex(&handler, input.into(), start, end, None).unwrap();
+        assert_eq!(stream.token_trees()[0].span().as_str(), "{ 1 }");
+    }
 }
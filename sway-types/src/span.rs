@@ -2,7 +2,9 @@ use crate::SourceId;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::{
+    cell::RefCell,
     cmp,
+    collections::HashMap,
     fmt::{self, Display},
     hash::Hash,
     sync::Arc,
@@ -43,12 +45,51 @@ impl serde::Serialize for Source {
 impl<'de> serde::Deserialize<'de> for Source {
     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let mut src = Self::deserialize(deserializer)?;
-        src.line_starts = Self::calc_line_starts(&src.text);
-        Ok(src)
+        SHARED_SOURCES.with(|shared| {
+            let mut shared = shared.borrow_mut();
+            if let Some(shared_src) = shared.as_ref().and_then(|sources| sources.get(&*src.text)) {
+                return Ok(shared_src.clone());
+            }
+            src.line_starts = Self::calc_line_starts(&src.text);
+            if let Some(sources) = shared.as_mut() {
+                sources.insert(src.text.clone(), src.clone());
+            }
+            Ok(src)
+        })
     }
 }
 
+thread_local! {
+    /// The sources deserialized so far by [Source::deserialize_shared], keyed by their text.
+    static SHARED_SOURCES: RefCell<Option<HashMap<Arc<str>, Source>>> = const { RefCell::new(None) };
+}
+
 impl Source {
+    /// Runs `f`, deserializing a single [Source] for all the spans with the same text, as if they
+    /// were parsed from it. Spans can only be joined if they share their source, so this is needed
+    /// to deserialize nodes whose span is joined from the spans of their children.
+    pub fn deserialize_shared<R>(f: impl FnOnce() -> R) -> R {
+        /// Stops sharing the sources once the outermost call returns, or panics.
+        struct Reset;
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                SHARED_SOURCES.with(|shared| *shared.borrow_mut() = None);
+            }
+        }
+
+        let outermost = SHARED_SOURCES.with(|shared| {
+            let mut shared = shared.borrow_mut();
+            let outermost = shared.is_none();
+            if outermost {
+                *shared = Some(HashMap::new());
+            }
+            outermost
+        });
+        let _reset = outermost.then_some(Reset);
+        f()
+    }
+
     fn calc_line_starts(text: &str) -> Arc<Vec<usize>> {
         let mut lines_starts = Vec::with_capacity(text.len() / 80);
         lines_starts.push(0);
@@ -144,10 +185,7 @@ impl Eq for Span {}
 
 impl From<Span> for std::ops::Range<usize> {
     fn from(value: Span) -> Self {
-        Self {
-            start: value.start,
-            end: value.end,
-        }
+        value.range()
     }
 }
 
@@ -213,6 +251,11 @@ impl Span {
         self.end
     }
 
+    /// Returns the byte range of the span in its source, from [Span::start] to [Span::end].
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
     /// Both lines and columns start at index 1
     pub fn start_line_col_one_index(&self) -> LineCol {
         self.src.line_col_one_index(self.start)
@@ -276,17 +319,38 @@ impl Span {
     /// This panics if the spans are not from the same file. This should
     /// only be used on spans that are actually next to each other.
     pub fn join(s1: Span, s2: &Span) -> Span {
-        assert!(
-            Arc::ptr_eq(&s1.src.text, &s2.src.text) && s1.source_id == s2.source_id,
-            "Spans from different files cannot be joined.",
-        );
+        Span::try_join(s1, s2).expect("Spans from different files cannot be joined.")
+    }
 
-        Span {
+    /// Joins the spans like [Span::join], but returns `None` instead of panicking
+    /// if the spans are not from the same file.
+    ///
+    /// The joined span always starts before it ends. Joining inverted spans, which only come from
+    /// deserialization or from bugs like a faulty parse error recovery, logs a warning and returns
+    /// the empty span at the start of the join instead, as an inverted one can't be sliced.
+    pub fn try_join(s1: Span, s2: &Span) -> Option<Span> {
+        if !Arc::ptr_eq(&s1.src.text, &s2.src.text) || s1.source_id != s2.source_id {
+            return None;
+        }
+
+        let start = cmp::min(s1.start, s2.start);
+        let mut end = cmp::max(s1.end, s2.end);
+        if start > end {
+            tracing::warn!(
+                "Joining inverted spans {}..{} and {}..{}",
+                s1.start,
+                s1.end,
+                s2.start,
+                s2.end
+            );
+            end = start;
+        }
+        Some(Span {
             src: s1.src,
-            start: cmp::min(s1.start, s2.start),
-            end: cmp::max(s1.end, s2.end),
+            start,
+            end,
             source_id: s1.source_id,
-        }
+        })
     }
 
     pub fn join_all(spans: impl IntoIterator<Item = Span>) -> Span {
@@ -296,6 +360,15 @@ impl Span {
             .unwrap_or_else(Span::dummy)
     }
 
+    /// Returns the minimal span covering all of the spans like [Span::join_all], but returns `None`
+    /// instead of a dummy span if there are no spans, or instead of panicking if the spans are not
+    /// all from the same file.
+    pub fn try_join_all(spans: impl IntoIterator<Item = Span>) -> Option<Span> {
+        let mut spans = spans.into_iter();
+        let first = spans.next()?;
+        spans.try_fold(first, |s1, s2| Span::try_join(s1, &s2))
+    }
+
     /// Returns the line and column start and end using index 1.
     pub fn line_col_one_index(&self) -> LineColRange {
         LineColRange {
@@ -312,12 +385,26 @@ impl Span {
         self.start == self.end
     }
 
-    /// Returns true if `self` contains `other`.
-    pub fn contains(&self, other: &Span) -> bool {
-        Arc::ptr_eq(&self.src.text, &other.src.text)
-            && self.source_id == other.source_id
-            && self.start <= other.start
-            && self.end >= other.end
+    /// Returns true if the byte `offset` is within the span, i.e. from [Span::start] included
+    /// to [Span::end] excluded. An empty span contains no offset.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Returns true if `self` contains `other`. Spans from different sources never contain
+    /// each other.
+    pub fn contains_span(&self, other: &Span) -> bool {
+        self.is_in_same_source(other) && self.start <= other.start && self.end >= other.end
+    }
+
+    /// Returns true if `self` and `other` share at least one byte. Adjacent spans, where one
+    /// ends where the other starts, don't overlap, and neither do spans from different sources.
+    pub fn overlaps(&self, other: &Span) -> bool {
+        self.is_in_same_source(other) && self.start < other.end && other.start < self.end
+    }
+
+    fn is_in_same_source(&self, other: &Span) -> bool {
+        Arc::ptr_eq(&self.src.text, &other.src.text) && self.source_id == other.source_id
     }
 }
 
@@ -370,3 +457,133 @@ impl Display for LineCol {
         f.write_fmt(format_args!("line {}:{}", self.line, self.col))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn join_spans_from_the_same_source() {
+        let src = Source::new("const X: u64 = 0;");
+        let start = Span::new(src.clone(), 0, 5, Some(SourceId::new(0, 0))).unwrap();
+        let end = Span::new(src, 15, 16, Some(SourceId::new(0, 0))).unwrap();
+
+        let joined = Span::try_join(start.clone(), &end).unwrap();
+        assert_eq!(joined.as_str(), "const X: u64 = 0");
+        assert_eq!(Span::join(start, &end), joined);
+    }
+
+    #[test]
+    fn join_inverted_spans() {
+        let src = Source::new("const X: u64 = 0;");
+        let inverted = |start, end| Span {
+            src: src.clone(),
+            start,
+            end,
+            source_id: None,
+        };
+
+        let joined = Span::join(inverted(9, 6), &inverted(12, 7));
+        assert_eq!((joined.start(), joined.end()), (9, 9));
+        assert_eq!(joined.as_str(), "");
+
+        // Joining an inverted span with a valid one covering it is valid as usual.
+        let valid = Span::new(src.clone(), 0, 16, None).unwrap();
+        let joined = Span::join(inverted(9, 6), &valid);
+        assert_eq!(joined.as_str(), "const X: u64 = 0");
+    }
+
+    #[test]
+    fn try_join_spans_from_different_sources() {
+        let src = Source::new("const X: u64 = 0;");
+        let start = Span::new(src.clone(), 0, 5, Some(SourceId::new(0, 0))).unwrap();
+        let end = Span::new(src.clone(), 15, 16, Some(SourceId::new(0, 1))).unwrap();
+        assert!(Span::try_join(start.clone(), &end).is_none());
+
+        let other_src = Span::new(
+            Source::new("const X: u64 = 0;"),
+            15,
+            16,
+            start.source_id().copied(),
+        )
+        .unwrap();
+        assert!(Span::try_join(start, &other_src).is_none());
+    }
+
+    #[test]
+    fn try_join_all_spans() {
+        let src = Source::new("const X: u64 = 0;");
+        let span = |start, end, source_id| {
+            Span::new(src.clone(), start, end, Some(SourceId::new(0, source_id))).unwrap()
+        };
+
+        let joined = Span::try_join_all([span(9, 12, 0), span(0, 5, 0), span(15, 16, 0)]).unwrap();
+        assert_eq!(joined.as_str(), "const X: u64 = 0");
+        assert_eq!(Span::try_join_all([span(6, 7, 0)]).unwrap().as_str(), "X");
+        assert!(Span::try_join_all([]).is_none());
+        assert!(Span::try_join_all([span(0, 5, 0), span(15, 16, 1)]).is_none());
+    }
+
+    #[test]
+    fn range_of_span() {
+        let src = Source::new("const X: u64 = 0;");
+        let span = Span::new(src.clone(), 6, 7, Some(SourceId::new(0, 0))).unwrap();
+        assert_eq!(span.range(), 6..7);
+        assert_eq!(&src.text[span.range()], span.as_str());
+        assert_eq!(Span::dummy().range(), 0..0);
+    }
+
+    #[test]
+    fn span_contains_offsets() {
+        let src = Source::new("const X: u64 = 0;");
+        let span = Span::new(src.clone(), 6, 7, None).unwrap();
+        assert!(span.contains(6));
+        assert!(!span.contains(5));
+        assert!(!span.contains(7));
+        assert!(!Span::new(src, 6, 6, None).unwrap().contains(6));
+    }
+
+    #[test]
+    fn span_contains_and_overlaps_spans() {
+        let src = Source::new("const X: u64 = 0;");
+        let span =
+            |start, end| Span::new(src.clone(), start, end, Some(SourceId::new(0, 0))).unwrap();
+        let item = span(0, 17);
+        let ty = span(9, 12);
+        assert!(item.contains_span(&ty));
+        assert!(item.contains_span(&item));
+        assert!(!ty.contains_span(&item));
+        assert!(item.overlaps(&ty));
+        assert!(span(0, 10).overlaps(&span(9, 12)));
+
+        // Adjacent spans touch without overlapping.
+        let name = span(6, 7);
+        let colon = span(7, 8);
+        assert!(!name.overlaps(&colon));
+        assert!(!colon.overlaps(&name));
+        assert!(!span(0, 8).contains_span(&span(7, 9)));
+
+        // Spans from different sources neither contain nor overlap each other.
+        let other_file = Span::new(src.clone(), 9, 12, Some(SourceId::new(0, 1))).unwrap();
+        assert!(!item.contains_span(&other_file));
+        assert!(!item.overlaps(&other_file));
+        let other_text = Span::new(
+            Source::new("const X: u64 = 0;"),
+            9,
+            12,
+            Some(SourceId::new(0, 0)),
+        )
+        .unwrap();
+        assert!(!item.contains_span(&other_text));
+        assert!(!item.overlaps(&other_text));
+    }
+
+    #[test]
+    #[should_panic(expected = "Spans from different files cannot be joined.")]
+    fn join_spans_from_different_sources_panics() {
+        let src = Source::new("const X: u64 = 0;");
+        let start = Span::new(src.clone(), 0, 5, Some(SourceId::new(0, 0))).unwrap();
+        let end = Span::new(src, 15, 16, Some(SourceId::new(0, 1))).unwrap();
+        Span::join(start, &end);
+    }
+}
@@ -45,11 +45,7 @@ impl Format for ItemAbi {
         }
 
         if abi_items.is_empty() {
-            write_comments(
-                formatted_code,
-                self.abi_items.span().start()..self.abi_items.span().end(),
-                formatter,
-            )?;
+            write_comments(formatted_code, self.abi_items.span().range(), formatter)?;
         }
 
         Self::close_curly_brace(formatted_code, formatter)?;
@@ -37,13 +37,9 @@ impl Format for ItemConst {
             ty.format(formatted_code, formatter)?;
         }
 
-        // Check if ` = ` exists
-        if self.eq_token_opt.is_some() {
+        // Check if ` = ` and the expression exist
+        if let Some((_eq_token, expr)) = &self.expr_opt {
             write!(formatted_code, " {} ", EqToken::AS_STR)?;
-        }
-
-        // Check if expression exists
-        if let Some(expr) = &self.expr_opt {
             expr.format(formatted_code, formatter)?;
         }
 
@@ -71,10 +67,8 @@ impl LeafSpans for ItemConst {
         if let Some(ty) = &self.ty_opt {
             collected_spans.append(&mut ty.leaf_spans());
         }
-        if let Some(eq_token) = &self.eq_token_opt {
+        if let Some((eq_token, expr)) = &self.expr_opt {
             collected_spans.push(ByteSpan::from(eq_token.span()));
-        }
-        if let Some(expr) = &self.expr_opt {
             collected_spans.append(&mut expr.leaf_spans());
         }
         collected_spans.push(ByteSpan::from(self.semicolon_token.span()));
@@ -45,11 +45,7 @@ impl Format for CodeBlockContents {
                 }
             }
         } else {
-            let comments: bool = write_comments(
-                formatted_code,
-                self.span().start()..self.span().end(),
-                formatter,
-            )?;
+            let comments: bool = write_comments(formatted_code, self.span().range(), formatter)?;
             if !comments {
                 formatter.shape.block_unindent(&formatter.config);
             }
@@ -153,11 +153,8 @@ fn format_then_block(
     {
         if_expr.then_block.get().format(formatted_code, formatter)?;
     } else {
-        let comments = write_comments(
-            formatted_code,
-            if_expr.then_block.span().start()..if_expr.then_block.span().end(),
-            formatter,
-        )?;
+        let comments =
+            write_comments(formatted_code, if_expr.then_block.span().range(), formatter)?;
         if !comments {
             formatter.shape.block_unindent(&formatter.config);
         }